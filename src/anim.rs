@@ -0,0 +1,172 @@
+//! Animations driven by `Event::Tick`: skill gauges that fill up, the
+//! Welcome text typing itself out and tabs sliding in.
+//!
+//! Every animation is a number of ticks to count down. Any key finishes
+//! them, and with reduced motion none of them starts.
+
+/// Ticks the gauges take to fill.
+const GAUGE_TICKS: u16 = 6;
+/// Ticks a tab takes to slide in.
+const SLIDE_TICKS: u16 = 3;
+/// Characters the Welcome text types per tick.
+const CHARS_PER_TICK: usize = 40;
+
+/// Where an animation is, as ticks done out of the total.
+#[derive(Clone, Copy, Debug)]
+struct Timeline {
+    done: u16,
+    length: u16,
+}
+
+impl Timeline {
+    fn new(length: u16) -> Timeline {
+        Timeline { done: 0, length: length.max(1) }
+    }
+
+    fn progress(&self) -> f32 {
+        self.done as f32 / self.length as f32
+    }
+
+    /// Advances a tick, `None` once the animation is over.
+    fn tick(self) -> Option<Timeline> {
+        if self.done + 1 >= self.length {
+            None
+        } else {
+            Some(Timeline { done: self.done + 1, length: self.length })
+        }
+    }
+}
+
+/// Fast at first and slow at the end.
+fn ease_out(progress: f32) -> f32 {
+    1.0 - (1.0 - progress).powi(3)
+}
+
+/// The side a tab slides in from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+pub struct Animations {
+    enabled: bool,
+    gauges: Option<Timeline>,
+    typing: Option<Timeline>,
+    slide: Option<(Timeline, Side)>,
+}
+
+impl Animations {
+    /// The animations of the start of the UI, which opens on the Welcome
+    /// tab, `intro` being its text.
+    pub fn new(enabled: bool, intro: &str) -> Animations {
+        let typing = (visible_len(intro) + CHARS_PER_TICK - 1) / CHARS_PER_TICK;
+        Animations {
+            enabled,
+            gauges: None,
+            typing: if enabled { Some(Timeline::new(typing as u16)) } else { None },
+            slide: None,
+        }
+    }
+
+    pub fn sliding(&self) -> bool {
+        self.slide.is_some()
+    }
+
+    pub fn tick(&mut self) {
+        self.gauges = self.gauges.and_then(Timeline::tick);
+        self.typing = self.typing.and_then(Timeline::tick);
+        self.slide = self.slide.and_then(|(timeline, side)| timeline.tick().map(|t| (t, side)));
+    }
+
+    /// Finishes every animation.
+    pub fn skip(&mut self) {
+        self.gauges = None;
+        self.typing = None;
+        self.slide = None;
+    }
+
+    /// Starts the animations of a tab that has just been opened.
+    pub fn open(&mut self, tab: usize, from: Side) {
+        if !self.enabled {
+            return;
+        }
+        self.slide = Some((Timeline::new(SLIDE_TICKS), from));
+        if tab == 2 {
+            self.gauges = Some(Timeline::new(GAUGE_TICKS));
+        }
+    }
+
+    /// How full a gauge of `level` is now.
+    pub fn gauge(&self, level: u16) -> u16 {
+        match self.gauges {
+            Some(timeline) => (level as f32 * ease_out(timeline.progress())).round() as u16,
+            None => level,
+        }
+    }
+
+    /// The part of the Welcome text typed so far, as `Paragraph` markup.
+    pub fn typed(&self, text: &str) -> String {
+        match self.typing {
+            Some(timeline) => truncate_markup(text, timeline.done as usize * CHARS_PER_TICK),
+            None => text.to_string(),
+        }
+    }
+
+    /// The columns the content of a tab is still pushed in by, and from
+    /// which side. Tabs start three quarters in, as narrower layouts are not
+    /// worth drawing.
+    pub fn slide(&self, width: u16) -> Option<(u16, Side)> {
+        self.slide.map(|(timeline, side)| ((width as f32 * 0.75 * (1.0 - ease_out(timeline.progress()))) as u16, side))
+    }
+}
+
+/// Goes through `Paragraph` markup, calling `visible` for the characters
+/// that are shown; it returns false to stop. Styles are `{mod=bold text}`
+/// and do not nest.
+fn walk<F: FnMut(char) -> bool>(text: &str, mut visible: F) -> usize {
+    let (mut in_span, mut in_spec) = (false, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' if !in_span => {
+                in_span = true;
+                in_spec = true;
+            }
+            ' ' if in_spec => in_spec = false,
+            '}' if in_span => in_span = false,
+            _ if in_spec => {}
+            c => {
+                if !visible(c) {
+                    return i;
+                }
+            }
+        }
+    }
+    text.len()
+}
+
+fn visible_len(text: &str) -> usize {
+    let mut count = 0;
+    walk(text, |_| {
+        count += 1;
+        true
+    });
+    count
+}
+
+/// The markup of the first `visible` characters of `text`, closing the
+/// style it stops in.
+fn truncate_markup(text: &str, visible: usize) -> String {
+    let mut count = 0;
+    let end = walk(text, |_| {
+        count += 1;
+        count <= visible
+    });
+    let mut truncated = text[..end].to_string();
+    let opened = truncated.matches('{').count();
+    let closed = truncated.matches('}').count();
+    if opened > closed {
+        truncated.push('}');
+    }
+    truncated
+}
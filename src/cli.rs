@@ -0,0 +1,483 @@
+//! Command line parsing. Without arguments the terminal UI is started.
+
+use std::time::Duration;
+
+use export::Format;
+use graphics::GraphicsMode;
+use links::LinkMode;
+
+pub const USAGE: &str = "\
+Usage:
+    rust-cv [--content <file>] [--links <when>] [--graphics <mode>] [--reduced-motion]
+            [--dashboard <dir>]                Browse the curriculum in the terminal
+    rust-cv export --format <format> [--output <file>] [--content <file>]
+                                               Write the curriculum in another format
+    rust-cv import --from <format> <file> [--output <file>]
+                                               Turn a curriculum into a content file
+    rust-cv lint <file>                        Report the text of a content file that is
+                                               cleaned up before it is shown
+    rust-cv guestbook <dir> [--hide <n>]       List the entries of the guestbook, or hide one
+    rust-cv serve ssh --host-key <file> [--listen <address>] [--allow <users>] [--guestbook]
+                  [--maildir <dir>]
+                  [--content <file>] [--links <when>] [--graphics <mode>] [--reduced-motion]
+                                               Let anyone browse the curriculum over SSH
+    rust-cv serve telnet [--listen <address> | --inetd] [--guestbook] [--maildir <dir>]
+                  [--content <file>] [--links <when>] [--graphics <mode>] [--reduced-motion]
+                                               The same over Telnet
+    rust-cv serve http [--listen <address> | --inetd] [--content <file>]
+                                               Serve the curriculum to browsers and curl
+    rust-cv serve finger [--listen <address> | --inetd] [--roster <dir>] [--content <file>]
+                                               Answer finger with the curriculum
+    rust-cv serve gopher [--listen <address>] [--hostname <name>] [--content <file>]
+                                               Publish the curriculum as a Gopher hole
+    rust-cv serve gemini --certificate <file> --key <file> [--listen <address>]
+                  [--hostname <name>] [--content <file>]
+                                               Publish the curriculum as a Gemini capsule
+    rust-cv help                               Show this message
+
+Every serve command also takes:
+    [--max-sessions <n>] [--rate <n>] [--burst <n>] [--idle-timeout <seconds>]
+    [--session-timeout <seconds>] [--ban-list <file>] [--ban-time <seconds>]
+    [--user <name>] [--log-dir <dir>] [--fork-sessions] [--allow-unsandboxed]
+    [--metrics <address>]
+
+A content file is the curriculum as JSON. Without one the built-in content
+is shown. Control characters in its text, which terminals would take as
+commands, are shown as symbols, and bidirectional overrides are removed;
+lint lists them, and fails when there are any. Imported curriculums are
+cleaned up the same way.
+
+URLs and emails are clickable in terminals that support hyperlinks. <when>
+is auto (the default, for terminals known to support them), always or
+never.
+
+The photo of the content file is shown with the graphics of the terminal.
+<mode> is auto (the default), kitty, sixel, blocks (Unicode half blocks in
+true colour) or none.
+
+--reduced-motion turns off the animations of the gauges, the Welcome text
+and the tabs.
+
+--dashboard adds a Dashboard tab with the visits logged in <dir>, the
+--log-dir of the servers: the sessions of every protocol, how often and
+how long each tab was looked at, and the sessions of the last week hour by
+hour. Only the local UI has it.
+
+serve ssh listens on <address> (0.0.0.0:2222 by default) and shows the
+terminal UI to every SSH client, with any user name unless --allow lists
+the ones allowed, separated by commas. No password or key is asked for.
+The host key is an unencrypted ed25519 key in the OpenSSH format, which is
+created when <file> does not exist. Links and graphics are detected from
+what the client sends about its terminal.
+
+serve telnet listens on <address> (0.0.0.0:2323 by default) and negotiates
+the size and type of the terminal of every client.
+
+serve http listens on <address> (0.0.0.0:8080 by default) and answers
+curl, wget and HTTPie with coloured text as wide as ?width= (80 by
+default), requests that accept application/json with JSON and others with
+an HTML page. /personal, /skills, /experience, /courses and /looking-for
+have a single section.
+
+serve finger listens on <address> (0.0.0.0:79 by default) and answers with
+a summary of the curriculum, or all of it for finger -l. With a roster,
+every content file <user>.json in <dir> answers finger <user>@host.
+Without one the curriculum answers to any user name.
+
+serve gopher listens on <address> (0.0.0.0:70 by default) with a menu of
+the tabs of the terminal UI and the contact details. Its items link to
+<name>, the address listened on by default, which clients elsewhere need
+to be told with --hostname.
+
+serve gemini listens on <address> (0.0.0.0:1965 by default) with the
+curriculum as gemtext at / and a section at /personal, /skills and the
+others. The certificate and its key are PEM files, which are created, self
+signed for <name> (localhost by default), when neither exists. With
+--hostname, requests for other hosts are refused.
+
+Started by systemd socket activation, the servers accept connections on
+the socket systemd passes instead of <address>. With --inetd, the telnet,
+finger and http servers answer the one connection on the standard input,
+as inetd and systemd with Accept=yes start them.
+
+The servers handle up to --max-sessions connections at once (100 by
+default), and every address may connect --rate times a minute (30 by
+default), up to --burst times in a row (10 by default). SSH and Telnet
+sessions without any key for --idle-timeout seconds (600 by default) are
+closed, and connections of any kind after --session-timeout seconds (3600
+by default). Addresses and networks (such as 192.0.2.0/24) in the ban list,
+one per line, cannot connect, and addresses that keep connecting too fast
+are banned for --ban-time seconds (600 by default, 0 for never). Refused
+clients are told why.
+
+Once listening, the servers run sandboxed: started as root they become
+--user (nobody by default), they can only read the content and write in
+--log-dir, and only make the system calls they need. With --fork-sessions
+every connection is handled by a process of its own, with limited memory,
+processor time and files. The servers do not start when the sandbox cannot
+be set up, unless --allow-unsandboxed is given.
+
+With --log-dir the servers log every session to visits.log in it: its
+protocol, when it started and ended, the tabs it opened and what it
+copied or downloaded. The address of the client is cut down to its
+network, a /24 or a /48, and the log is only readable by its owner.
+
+With --guestbook, which needs --log-dir, SSH and Telnet sessions have a
+Guestbook tab where visitors leave a name, a way to reach them and a
+message, kept in guestbook.log in the log directory. A network can sign
+once every ten minutes, and the guestbook takes 30 entries an hour.
+guestbook lists the entries kept in <dir> with their numbers, and --hide
+hides entry <n> from visitors; only the owner of the file can.
+
+With --maildir, SSH and Telnet sessions can write to the owner of the
+curriculum: m in the Looking For tab opens a form for a name, an email
+address, a subject and a message, which is delivered as mail to the
+Maildir <dir>, created when it does not exist, for mail clients to pick
+up. Senders are given a reference, which the message has in its
+X-Rust-CV-Reference header, and a session sends up to three messages. A
+sandboxed server writes them as --user, which has to be able to.
+
+--metrics serves the counters of the server to Prometheus at /metrics on
+<address>, such as 127.0.0.1:9100: the sessions going on, the connections
+accepted and refused, how long answers and screens take to render, the
+bytes sent and how often the content was read again. The content file is
+read again whenever it changes.";
+
+/// How the terminal UI looks, wherever it runs.
+#[derive(Clone, Copy)]
+pub struct Ui {
+    pub links: LinkMode,
+    pub graphics: GraphicsMode,
+    pub motion: bool,
+}
+
+/// Where the connections of a server come from.
+pub enum Listen {
+    Address(String),
+    /// The one connection inetd accepted.
+    Inetd,
+}
+
+/// How much of a server its clients may use, whatever the protocol.
+#[derive(Clone)]
+pub struct Limits {
+    /// The connections handled at once.
+    pub sessions: usize,
+    /// The connections an address may make in a minute, and in a row.
+    pub rate: u32,
+    pub burst: u32,
+    /// How long a terminal session may go without a key.
+    pub idle: Duration,
+    /// How long any connection may last.
+    pub session: Duration,
+    pub ban_list: Option<String>,
+    /// How long addresses that keep connecting too fast are banned, not at
+    /// all when zero.
+    pub ban_time: Duration,
+}
+
+/// What the servers are kept from doing.
+pub struct Sandbox {
+    /// Who servers started as root become.
+    pub user: Option<String>,
+    /// Where servers log, the only place they may write besides the
+    /// Maildir of the contact form.
+    pub log_dir: Option<String>,
+    /// Whether every connection is handled by a process of its own.
+    pub fork: bool,
+    /// Whether a server runs without the sandbox that cannot be set up.
+    pub unsandboxed: bool,
+}
+
+pub enum Server {
+    Ssh { listen: String, host_key: String, allow: Vec<String>, ui: Ui, guestbook: bool, maildir: Option<String> },
+    Telnet { listen: Listen, ui: Ui, guestbook: bool, maildir: Option<String> },
+    Http { listen: Listen },
+    Finger { listen: Listen, roster: Option<String> },
+    Gopher { listen: String, hostname: Option<String> },
+    Gemini { listen: String, certificate: String, key: String, hostname: Option<String> },
+}
+
+pub enum Command {
+    Tui { content: Option<String>, ui: Ui, dashboard: Option<String> },
+    Export { format: Format, output: Option<String>, content: Option<String> },
+    Import { from: Format, input: String, output: Option<String> },
+    Lint { content: String },
+    Guestbook { dir: String, hide: Option<usize> },
+    Serve { content: Option<String>, server: Box<Server>, limits: Limits, sandbox: Sandbox, metrics: Option<String> },
+    Help,
+}
+
+/// The `--name value` and `--name=value` pairs of a command, the `--name`
+/// flags, and the arguments that are not options.
+struct Arguments {
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Arguments {
+    fn option(&self, name: &str) -> Option<String> {
+        self.options.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn no_positional(&self) -> Result<(), String> {
+        match self.positional.first() {
+            Some(arg) => Err(format!("unexpected argument '{}'", arg)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The options of the terminal UI, which every command showing it takes.
+const UI_OPTIONS: &[&str] = &["links", "graphics"];
+const UI_FLAGS: &[&str] = &["reduced-motion"];
+
+fn ui(args: &Arguments) -> Result<Ui, String> {
+    let links = match args.option("links") {
+        Some(links) => links.parse()?,
+        None => LinkMode::Auto,
+    };
+    let graphics = match args.option("graphics") {
+        Some(graphics) => graphics.parse()?,
+        None => GraphicsMode::Auto,
+    };
+    Ok(Ui { links, graphics, motion: !args.flag("reduced-motion") })
+}
+
+/// The options of the limits, the sandbox and the metrics, which every
+/// server takes.
+const SERVE_OPTIONS: &[&str] = &[
+    "max-sessions",
+    "rate",
+    "burst",
+    "idle-timeout",
+    "session-timeout",
+    "ban-list",
+    "ban-time",
+    "user",
+    "log-dir",
+    "metrics",
+];
+const SERVE_FLAGS: &[&str] = &["fork-sessions", "allow-unsandboxed"];
+
+/// The value of `--name`, a number that can only be zero when `zero` says
+/// so.
+fn number(args: &Arguments, name: &str, default: u64, zero: bool) -> Result<u64, String> {
+    match args.option(name) {
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|&n: &u64| zero || n > 0)
+            .ok_or_else(|| format!("'--{}' takes a number, not '{}'", name, value)),
+        None => Ok(default),
+    }
+}
+
+fn limits(args: &Arguments) -> Result<Limits, String> {
+    Ok(Limits {
+        sessions: number(args, "max-sessions", 100, false)? as usize,
+        rate: number(args, "rate", 30, false)? as u32,
+        burst: number(args, "burst", 10, false)? as u32,
+        idle: Duration::from_secs(number(args, "idle-timeout", 600, false)?),
+        session: Duration::from_secs(number(args, "session-timeout", 3600, false)?),
+        ban_list: args.option("ban-list"),
+        ban_time: Duration::from_secs(number(args, "ban-time", 600, true)?),
+    })
+}
+
+fn sandbox(args: &Arguments) -> Sandbox {
+    Sandbox {
+        user: args.option("user"),
+        log_dir: args.option("log-dir"),
+        fork: args.flag("fork-sessions"),
+        unsandboxed: args.flag("allow-unsandboxed"),
+    }
+}
+
+/// The serve command of `server`, with the options every server takes.
+fn serve(args: &Arguments, server: Server) -> Result<Command, String> {
+    let metrics = args.option("metrics");
+    if metrics.is_some() && args.flag("inetd") {
+        return Err("'--metrics' cannot be used with '--inetd'".to_string());
+    }
+    Ok(Command::Serve { content: args.option("content"), server: Box::new(server), limits: limits(args)?, sandbox: sandbox(args), metrics })
+}
+
+/// Whether sessions have a guestbook, which is kept in the log directory.
+fn guestbook(args: &Arguments) -> Result<bool, String> {
+    match (args.flag("guestbook"), args.option("log-dir")) {
+        (true, None) => Err("'--guestbook' needs '--log-dir', where the entries are kept".to_string()),
+        (guestbook, _) => Ok(guestbook),
+    }
+}
+
+/// `--listen`, or `default`, unless `--inetd` is given.
+fn listen(args: &Arguments, default: &str) -> Result<Listen, String> {
+    match (args.option("listen"), args.flag("inetd")) {
+        (Some(_), true) => Err("'--inetd' and '--listen' cannot be used together".to_string()),
+        (_, true) => Ok(Listen::Inetd),
+        (listen, false) => Ok(Listen::Address(listen.unwrap_or_else(|| default.to_string()))),
+    }
+}
+
+/// Splits the arguments of a command, rejecting the options that are not in
+/// `known` and the flags that are not in `flags`.
+fn arguments(args: &[String], known: &[&str], flags: &[&str]) -> Result<Arguments, String> {
+    let mut parsed = Arguments { options: Vec::new(), flags: Vec::new(), positional: Vec::new() };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            parsed.positional.push(arg.clone());
+            continue;
+        }
+        let (name, value) = match arg.find('=') {
+            Some(i) => (arg[2..i].to_string(), Some(arg[i + 1..].to_string())),
+            None => (arg[2..].to_string(), None),
+        };
+        if flags.contains(&name.as_str()) {
+            if value.is_some() {
+                return Err(format!("'--{}' takes no value", name));
+            }
+            parsed.flags.push(name);
+            continue;
+        }
+        if !known.contains(&name.as_str()) {
+            return Err(format!("unknown option '--{}'", name));
+        }
+        let value = match value {
+            Some(value) => value,
+            None => args.next().cloned().ok_or_else(|| format!("missing value for '--{}'", name))?,
+        };
+        parsed.options.push((name, value));
+    }
+    Ok(parsed)
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let args: Vec<String> = args.into_iter().collect();
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) if !command.starts_with("--") || command == "--help" => (command.as_str(), rest),
+        _ => ("tui", &args[..]),
+    };
+    match command {
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "tui" => {
+            let args = arguments(rest, &[&["content", "dashboard"], UI_OPTIONS].concat(), UI_FLAGS)?;
+            args.no_positional()?;
+            Ok(Command::Tui { content: args.option("content"), ui: ui(&args)?, dashboard: args.option("dashboard") })
+        }
+        "export" => {
+            let args = arguments(rest, &["format", "output", "content"], &[])?;
+            args.no_positional()?;
+            let format = args.option("format").ok_or("missing '--format'")?.parse()?;
+            Ok(Command::Export { format, output: args.option("output"), content: args.option("content") })
+        }
+        "import" => {
+            let args = arguments(rest, &["from", "output"], &[])?;
+            let from = args.option("from").ok_or("missing '--from'")?.parse()?;
+            let input = match args.positional.len() {
+                1 => args.positional[0].clone(),
+                0 => return Err("missing the file to import".to_string()),
+                _ => return Err(format!("unexpected argument '{}'", args.positional[1])),
+            };
+            Ok(Command::Import { from, input, output: args.option("output") })
+        }
+        "lint" => {
+            let args = arguments(rest, &[], &[])?;
+            let content = match args.positional.len() {
+                1 => args.positional[0].clone(),
+                0 => return Err("missing the content file to lint".to_string()),
+                _ => return Err(format!("unexpected argument '{}'", args.positional[1])),
+            };
+            Ok(Command::Lint { content })
+        }
+        "guestbook" => {
+            let args = arguments(rest, &["hide"], &[])?;
+            let dir = match args.positional.len() {
+                1 => args.positional[0].clone(),
+                0 => return Err("missing the directory of the guestbook".to_string()),
+                _ => return Err(format!("unexpected argument '{}'", args.positional[1])),
+            };
+            let hide = match args.option("hide") {
+                Some(number) => Some(number.parse().map_err(|_| format!("'--hide' takes the number of an entry, not '{}'", number))?),
+                None => None,
+            };
+            Ok(Command::Guestbook { dir, hide })
+        }
+        "serve" => {
+            let (protocol, rest) = rest.split_first().ok_or("missing the protocol to serve")?;
+            match protocol.as_str() {
+                "ssh" => {
+                    let args = arguments(rest, &[&["content", "listen", "host-key", "allow", "maildir"], UI_OPTIONS, SERVE_OPTIONS].concat(), &[UI_FLAGS, SERVE_FLAGS, &["guestbook"]].concat())?;
+                    args.no_positional()?;
+                    let allow = match args.option("allow") {
+                        Some(users) => users.split(',').filter(|u| !u.is_empty()).map(|u| u.to_string()).collect(),
+                        None => Vec::new(),
+                    };
+                    let server = Server::Ssh {
+                        listen: args.option("listen").unwrap_or_else(|| "0.0.0.0:2222".to_string()),
+                        host_key: args.option("host-key").ok_or("missing '--host-key'")?,
+                        allow,
+                        ui: ui(&args)?,
+                        guestbook: guestbook(&args)?,
+                        maildir: args.option("maildir"),
+                    };
+                    serve(&args, server)
+                }
+                "telnet" => {
+                    let args = arguments(rest, &[&["content", "listen", "maildir"], UI_OPTIONS, SERVE_OPTIONS].concat(), &[UI_FLAGS, SERVE_FLAGS, &["inetd", "guestbook"]].concat())?;
+                    args.no_positional()?;
+                    let server = Server::Telnet {
+                        listen: listen(&args, "0.0.0.0:2323")?,
+                        ui: ui(&args)?,
+                        guestbook: guestbook(&args)?,
+                        maildir: args.option("maildir"),
+                    };
+                    serve(&args, server)
+                }
+                "http" => {
+                    let args = arguments(rest, &[&["content", "listen"], SERVE_OPTIONS].concat(), &[SERVE_FLAGS, &["inetd"]].concat())?;
+                    args.no_positional()?;
+                    let server = Server::Http { listen: listen(&args, "0.0.0.0:8080")? };
+                    serve(&args, server)
+                }
+                "finger" => {
+                    let args = arguments(rest, &[&["content", "listen", "roster"], SERVE_OPTIONS].concat(), &[SERVE_FLAGS, &["inetd"]].concat())?;
+                    args.no_positional()?;
+                    let server = Server::Finger {
+                        listen: listen(&args, "0.0.0.0:79")?,
+                        roster: args.option("roster"),
+                    };
+                    serve(&args, server)
+                }
+                "gopher" => {
+                    let args = arguments(rest, &[&["content", "listen", "hostname"], SERVE_OPTIONS].concat(), SERVE_FLAGS)?;
+                    args.no_positional()?;
+                    let server = Server::Gopher {
+                        listen: args.option("listen").unwrap_or_else(|| "0.0.0.0:70".to_string()),
+                        hostname: args.option("hostname"),
+                    };
+                    serve(&args, server)
+                }
+                "gemini" => {
+                    let args = arguments(rest, &[&["content", "listen", "certificate", "key", "hostname"], SERVE_OPTIONS].concat(), SERVE_FLAGS)?;
+                    args.no_positional()?;
+                    let server = Server::Gemini {
+                        listen: args.option("listen").unwrap_or_else(|| "0.0.0.0:1965".to_string()),
+                        certificate: args.option("certificate").ok_or("missing '--certificate'")?,
+                        key: args.option("key").ok_or("missing '--key'")?,
+                        hostname: args.option("hostname"),
+                    };
+                    serve(&args, server)
+                }
+                _ => Err(format!("unknown protocol '{}', expected one of: ssh, telnet, http, finger, gopher, gemini", protocol)),
+            }
+        }
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
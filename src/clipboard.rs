@@ -0,0 +1,14 @@
+//! Copying to the clipboard with the OSC 52 escape sequence.
+//!
+//! The terminal itself sets its clipboard, so this works the same over SSH
+//! as it does locally, as long as the terminal allows it.
+
+use std::io::{self, Write};
+
+use base64;
+
+/// Asks the terminal behind `out` to put `text` in the clipboard.
+pub fn copy<W: Write>(out: &mut W, text: &str) -> Result<(), io::Error> {
+    write!(out, "\x1b]52;c;{}\x07", base64::encode(text.as_bytes()))?;
+    out.flush()
+}
@@ -0,0 +1,323 @@
+//! The messages of the contact form of the UI, delivered as mail (RFC 5322)
+//! to a Maildir so they reach the owner with the rest of their mail. A
+//! message is written to `tmp/` and then moved to `new/`, as Maildir asks,
+//! so mail clients never see half of one.
+
+use std::ffi::{CStr, CString};
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libc;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use base64;
+use cv::{ContactKind, Cv};
+use sanitize;
+
+/// The longest name, email address, subject and message, in characters.
+pub const NAME: usize = 80;
+pub const EMAIL: usize = 254;
+pub const SUBJECT: usize = 120;
+pub const BODY: usize = 2000;
+/// Where the lines of a message are wrapped.
+const WIDTH: usize = 72;
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// What a visitor wrote, checked.
+pub struct Message {
+    name: String,
+    email: String,
+    subject: String,
+    body: String,
+}
+
+/// Whether `email` is an address mail can be sent back to: `local@domain`,
+/// with the characters of RFC 5322 dot-atoms on the left and host names on
+/// the right.
+fn valid_email(email: &str) -> bool {
+    let at = match email.rfind('@') {
+        Some(at) => at,
+        None => return false,
+    };
+    let (local, domain) = (&email[..at], &email[at + 1..]);
+    let atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    let labels: Vec<&str> = domain.split('.').collect();
+    local.len() <= 64
+        && local.split('.').all(|atom| !atom.is_empty() && atom.chars().all(atext))
+        && labels.len() > 1
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+impl Message {
+    /// Checks what a visitor typed, saying what is wrong with it. Line
+    /// breaks of the message, whichever they are, become `\n`.
+    pub fn new(name: &str, email: &str, subject: &str, body: &str) -> Result<Message, String> {
+        let body = body.replace("\r\n", "\n").replace('\r', "\n");
+        let (name, email, subject, body) = (name.trim(), email.trim(), subject.trim(), body.trim());
+        let fields = [("name", name, NAME), ("email address", email, EMAIL), ("subject", subject, SUBJECT), ("message", body, BODY)];
+        for &(field, text, limit) in &fields {
+            if text.is_empty() {
+                return Err(format!("The {} is missing", field));
+            }
+            if text.chars().count() > limit {
+                return Err(format!("The {} is longer than {} characters", field, limit));
+            }
+        }
+        // Line breaks in a header would start another one.
+        for &(field, text, _) in &fields[..3] {
+            if text.chars().any(|c| c.is_control()) {
+                return Err(format!("The {} cannot have control characters", field));
+            }
+        }
+        if email.len() > EMAIL || !valid_email(email) {
+            return Err(format!("'{}' is not an email address", email));
+        }
+        Ok(Message { name: name.to_string(), email: email.to_string(), subject: subject.to_string(), body: body.to_string() })
+    }
+}
+
+/// `text` as encoded words (RFC 2047), each short enough for a line.
+fn encoded_words(text: &str) -> String {
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        // 45 bytes are 60 in Base64, and 72 with the rest of the word.
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", base64::encode(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    words.push(format!("=?UTF-8?B?{}?=", base64::encode(chunk.as_bytes())));
+    words.join("\n ")
+}
+
+fn printable(text: &str) -> bool {
+    text.chars().all(|c| c == ' ' || c.is_ascii_graphic())
+}
+
+/// A name in front of an address, quoted or encoded as it needs.
+fn phrase(name: &str) -> String {
+    if printable(name) {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        encoded_words(name)
+    }
+}
+
+/// Free text in a header, such as the subject.
+fn unstructured(text: &str) -> String {
+    if printable(text) {
+        text.to_string()
+    } else {
+        encoded_words(text)
+    }
+}
+
+/// `text` wrapped at `WIDTH` columns a paragraph at a time, a paragraph
+/// being a line of it.
+fn wrap(text: &str) -> String {
+    text.split('\n').map(wrap_paragraph).collect::<Vec<_>>().join("\n")
+}
+
+/// A paragraph wrapped at `WIDTH` columns, breaking words that are longer.
+/// Control characters left in words would be commands to the terminal
+/// the message is read in, so they are shown as symbols.
+fn wrap_paragraph(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = sanitize::clean(word).chars().collect();
+        while word.len() > WIDTH {
+            if !line.is_empty() {
+                lines.push(line.clone());
+                line.clear();
+            }
+            lines.push(word.drain(..WIDTH).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > WIDTH {
+            lines.push(line.clone());
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    lines.push(line);
+    lines.join("\n")
+}
+
+/// `seconds` since the epoch as the date of a message, in UTC.
+fn date(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    // The civil calendar from days, after Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[((days + 4) % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// The name of this host, which Maildir puts in the names of messages.
+fn hostname() -> String {
+    let mut buf = [0 as c_char; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len() - 1) } != 0 {
+        return "localhost".to_string();
+    }
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy().into_owned();
+    // The two characters Maildir does not take in a name.
+    name.replace('/', "\\057").replace(':', "\\072")
+}
+
+/// The Maildir messages are delivered to.
+#[derive(Clone)]
+pub struct Maildir {
+    dir: PathBuf,
+    /// The name of the host, found before the sandbox hides it.
+    host: String,
+}
+
+impl Maildir {
+    /// Opens the Maildir `dir`, creating what is missing of it. What is
+    /// created belongs to the owner of `dir`, so a server started as root
+    /// can still deliver once it is `--user`.
+    pub fn open(dir: &str) -> Result<Maildir, io::Error> {
+        let context = |path: &Path, err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+        DirBuilder::new().recursive(true).mode(0o700).create(dir).map_err(|err| context(Path::new(dir), err))?;
+        let owner = fs::metadata(dir).map_err(|err| context(Path::new(dir), err))?;
+        for sub in &["tmp", "new", "cur"] {
+            let path = Path::new(dir).join(sub);
+            if path.is_dir() {
+                continue;
+            }
+            DirBuilder::new().mode(0o700).create(&path).map_err(|err| context(&path, err))?;
+            if fs::metadata(&path).map_err(|err| context(&path, err))?.uid() != owner.uid() {
+                let c_path = CString::new(path.as_os_str().as_bytes())?;
+                if unsafe { libc::chown(c_path.as_ptr(), owner.uid(), owner.gid()) } != 0 {
+                    return Err(context(&path, io::Error::last_os_error()));
+                }
+            }
+        }
+        Ok(Maildir { dir: PathBuf::from(dir), host: hostname() })
+    }
+
+    /// Delivers `message`, from a visitor on `network`, to the owner of `cv`
+    /// and returns its reference, which the visitor is given.
+    pub fn deliver(&self, message: &Message, cv: &Cv, network: &str) -> Result<String, io::Error> {
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let mut random = [0u8; 4];
+        SystemRandom::new().fill(&mut random).map_err(|_| io::Error::new(io::ErrorKind::Other, "no randomness"))?;
+        let random = u32::from_be_bytes(random);
+        let reference = format!("{:X}-{:04X}", elapsed.as_secs(), random & 0xffff);
+
+        let mut mail = format!("Date: {}\n", date(elapsed.as_secs()));
+        mail.push_str(&format!("From: {} <{}>\n", phrase(&message.name), message.email));
+        if let Some(contact) = cv.contacts.iter().find(|contact| contact.kind == ContactKind::Email) {
+            mail.push_str(&format!("To: {} <{}>\n", phrase(&cv.name), contact.value));
+        }
+        mail.push_str(&format!("Subject: {}\n", unstructured(&message.subject)));
+        mail.push_str(&format!("Message-ID: <{}@{}>\n", reference.to_lowercase(), self.host));
+        mail.push_str(&format!("X-Rust-CV-Reference: {}\n", reference));
+        if !network.is_empty() {
+            mail.push_str(&format!("X-Rust-CV-Network: {}\n", network));
+        }
+        mail.push_str("MIME-Version: 1.0\nContent-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n");
+        mail.push_str(&wrap(&message.body));
+        mail.push('\n');
+
+        let name = format!(
+            "{}.M{}P{}R{:08x}.{}",
+            elapsed.as_secs(),
+            elapsed.subsec_micros(),
+            process::id(),
+            random,
+            self.host
+        );
+        let (tmp, new) = (self.dir.join("tmp").join(&name), self.dir.join("new").join(&name));
+        let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&tmp)?;
+        let written = file.write_all(mail.as_bytes()).and_then(|_| file.sync_all()).and_then(|_| fs::rename(&tmp, &new));
+        if let Err(err) = written {
+            fs::remove_file(&tmp).ok();
+            return Err(err);
+        }
+        Ok(reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A Maildir that is not there yet, for test `name`.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rust-cv-{}-{}", name, process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect()
+    }
+
+    #[test]
+    fn delivered_to_new() {
+        let dir = scratch("maildir-new");
+        let maildir = Maildir::open(&dir.to_string_lossy()).unwrap();
+        assert!(dir.join("cur").is_dir());
+        let message = Message::new("Ada", "ada@example.com", "Hello", "A line\r\nand another").unwrap();
+        let reference = maildir.deliver(&message, &Cv::default(), "192.0.2.0/24").unwrap();
+
+        assert!(files(&dir.join("tmp")).is_empty());
+        let new = files(&dir.join("new"));
+        assert_eq!(new.len(), 1);
+        let mail = fs::read_to_string(&new[0]).unwrap();
+        assert!(mail.contains("From: \"Ada\" <ada@example.com>\n"), "{}", mail);
+        assert!(mail.contains("To: \"Oscar Forner Martinez\" <oscar.forner.martinez@gmail.com>\n"), "{}", mail);
+        assert!(mail.contains(&format!("X-Rust-CV-Reference: {}\n", reference)), "{}", mail);
+        assert!(mail.contains("X-Rust-CV-Network: 192.0.2.0/24\n"), "{}", mail);
+        assert!(mail.ends_with("\n\nA line\nand another\n"), "{}", mail);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn nothing_left_in_tmp() {
+        let dir = scratch("maildir-tmp");
+        let maildir = Maildir::open(&dir.to_string_lossy()).unwrap();
+        fs::remove_dir(dir.join("new")).unwrap();
+        let message = Message::new("Ada", "ada@example.com", "Hello", "Hi").unwrap();
+        assert!(maildir.deliver(&message, &Cv::default(), "").is_err());
+        assert!(files(&dir.join("tmp")).is_empty());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
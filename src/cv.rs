@@ -0,0 +1,531 @@
+//! The content of the curriculum, kept apart from the way it is drawn so the
+//! terminal UI and the exporters read from the same place.
+//!
+//! Free text may use `**double asterisks**` to mark the words that are shown
+//! in bold, see [`spans`](fn.spans.html).
+//!
+//! A content file is the JSON form of [`Cv`](struct.Cv.html); it can be
+//! written by hand or produced by `rust-cv import`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use serde_json::{self, Value};
+
+use sanitize::{self, Change};
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+    "November", "December",
+];
+
+/// A calendar date with optional month and day, so it can represent "2020" as
+/// well as "August 2019" or "24/03/1988".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u16,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+impl Date {
+    pub fn year(year: u16) -> Date {
+        Date { year, month: None, day: None }
+    }
+
+    pub fn month(year: u16, month: u8) -> Date {
+        Date { year, month: Some(month), day: None }
+    }
+
+    pub fn day(year: u16, month: u8, day: u8) -> Date {
+        Date { year, month: Some(month), day: Some(day) }
+    }
+
+    /// Parses `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    pub fn parse(s: &str) -> Option<Date> {
+        let mut parts = s.trim().splitn(3, '-');
+        let year = parts.next().and_then(|p| p.parse().ok())?;
+        let month = match parts.next() {
+            Some(p) => Some(p.parse().ok().filter(|m| *m >= 1 && *m <= 12)?),
+            None => None,
+        };
+        let day = match parts.next() {
+            Some(p) => Some(p.parse().ok().filter(|d| *d >= 1 && *d <= 31)?),
+            None => None,
+        };
+        Some(Date { year, month, day })
+    }
+
+    /// The date in ISO 8601 form, as precise as it is known.
+    pub fn iso(&self) -> String {
+        match (self.month, self.day) {
+            (Some(m), Some(d)) => format!("{:04}-{:02}-{:02}", self.year, m, d),
+            (Some(m), None) => format!("{:04}-{:02}", self.year, m),
+            _ => format!("{:04}", self.year),
+        }
+    }
+
+    pub fn month_name(&self) -> Option<&'static str> {
+        self.month.map(|m| MONTHS[(m as usize - 1) % 12])
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.month, self.day) {
+            (Some(m), Some(d)) => write!(f, "{:02}/{:02}/{}", d, m, self.year),
+            (Some(_), None) => write!(f, "{} {}", self.month_name().unwrap(), self.year),
+            _ => write!(f, "{}", self.year),
+        }
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.iso())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Date::parse(&s).ok_or_else(|| de::Error::custom(format!("invalid date '{}', expected YYYY, YYYY-MM or YYYY-MM-DD", s)))
+    }
+}
+
+/// Formats a period as shown in the titles, e.g. `2020 - Present`.
+pub fn period(start: &Date, end: &Option<Date>) -> String {
+    match *end {
+        Some(ref end) => format!("{} - {}", start, end),
+        None => format!("{} - Present", start),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactKind {
+    Email,
+    Phone,
+    Website,
+    Twitter,
+    GitHub,
+    LinkedIn,
+}
+
+impl ContactKind {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            ContactKind::Email => "Email",
+            ContactKind::Phone => "Phone",
+            ContactKind::Website => "Website",
+            ContactKind::Twitter => "Twitter",
+            ContactKind::GitHub => "GitHub",
+            ContactKind::LinkedIn => "LinkedIn",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Contact {
+    pub kind: ContactKind,
+    pub value: String,
+}
+
+impl Contact {
+    /// The URL that opens this contact, `mailto:` and `tel:` included.
+    pub fn url(&self) -> String {
+        match self.kind {
+            ContactKind::Email => format!("mailto:{}", self.value),
+            ContactKind::Phone => {
+                let digits: String = self.value.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+                format!("tel:{}", digits)
+            }
+            _ => self.value.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Language {
+    pub name: String,
+    pub level: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Study {
+    pub start: Date,
+    pub end: Date,
+    pub degree: String,
+    pub summary: String,
+    /// The level of the degree in the European Qualifications Framework.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eqf_level: Option<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Skill {
+    pub name: String,
+    /// How confident I am with the skill, out of 100.
+    pub level: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SkillGroup {
+    pub title: String,
+    pub skills: Vec<Skill>,
+}
+
+/// A group of tools listed without a level, shown under "Others".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ToolGroup {
+    pub title: String,
+    pub items: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Highlight {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+    pub start: Date,
+    /// `None` while it is my current position.
+    #[serde(default)]
+    pub end: Option<Date>,
+    pub position: String,
+    pub company: String,
+    pub highlights: Vec<Highlight>,
+}
+
+impl Job {
+    pub fn title(&self) -> String {
+        format!("{}: {} at {}", period(&self.start, &self.end), self.position, self.company)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Course {
+    pub title: String,
+    pub provider: String,
+    pub date: Date,
+    pub summary: String,
+}
+
+impl Course {
+    pub fn heading(&self) -> String {
+        format!("{} (from {}) - {}", self.title, self.provider, self.date)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LookingFor {
+    pub headline: String,
+    pub intro: String,
+    pub roles: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cv {
+    pub name: String,
+    /// How the name is shown in titles, usually without the second surname.
+    pub short_name: String,
+    #[serde(default)]
+    pub headline: String,
+    /// A picture for the Personal tab. A relative path is relative to the
+    /// content file. A URL, which imported resumes have, is only exported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+    /// The FIGlet font of the banner in the Welcome tab, one of those in
+    /// `fonts/`. By default the largest that fits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner_font: Option<String>,
+    #[serde(default)]
+    pub birth_date: Option<Date>,
+    #[serde(default)]
+    pub nationality: String,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub about: Vec<String>,
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+    #[serde(default)]
+    pub languages: Vec<Language>,
+    #[serde(default)]
+    pub studies: Vec<Study>,
+    #[serde(default)]
+    pub skills: Vec<SkillGroup>,
+    #[serde(default)]
+    pub tools: Vec<ToolGroup>,
+    #[serde(default)]
+    pub experience: Vec<Job>,
+    #[serde(default)]
+    pub courses: Vec<Course>,
+    #[serde(default)]
+    pub looking_for: LookingFor,
+    #[serde(default)]
+    pub interests: Vec<String>,
+    /// Data kept from imported files that has no place in the curriculum, so
+    /// exporting to the same format again loses nothing.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+impl Cv {
+    /// The personal details that are known, as label and value.
+    pub fn information(&self) -> Vec<(&'static str, String)> {
+        let mut information = Vec::new();
+        if let Some(ref date) = self.birth_date {
+            information.push(("Date of Birth", date.to_string()));
+        }
+        if !self.nationality.is_empty() {
+            information.push(("Nationality", self.nationality.clone()));
+        }
+        if !self.location.is_empty() {
+            information.push(("Location", self.location.clone()));
+        }
+        information
+    }
+
+    /// Reads a content file.
+    pub fn load(path: &str) -> Result<Cv, io::Error> {
+        Cv::read(path).map(|(cv, _)| cv)
+    }
+
+    /// Reads a content file, with the characters of its text that could not
+    /// be shown as they were, see [`sanitize`](../sanitize/index.html).
+    pub fn read(path: &str) -> Result<(Cv, Vec<Change>), io::Error> {
+        let invalid = |err: serde_json::Error| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err));
+        let file = File::open(path)?;
+        let mut value: Value = serde_json::from_reader(BufReader::new(file)).map_err(invalid)?;
+        let changes = sanitize::json(&mut value);
+        let mut cv: Cv = serde_json::from_value(value).map_err(invalid)?;
+        if let (Some(photo), Some(dir)) = (cv.photo.take(), Path::new(path).parent()) {
+            cv.photo = Some(if photo.contains("://") { photo } else { dir.join(photo).to_string_lossy().into_owned() });
+        }
+        Ok((cv, changes))
+    }
+
+    /// The curriculum with its text cleaned up as that of content files.
+    pub fn sanitized(self) -> Result<(Cv, Vec<Change>), io::Error> {
+        let invalid = |err: serde_json::Error| io::Error::new(io::ErrorKind::InvalidData, err);
+        let mut value = serde_json::to_value(&self).map_err(invalid)?;
+        let changes = sanitize::json(&mut value);
+        Ok((serde_json::from_value(value).map_err(invalid)?, changes))
+    }
+}
+
+/// A piece of free text that is either plain or bold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub bold: bool,
+}
+
+/// Splits free text on its `**` markers.
+pub fn spans<'a>(text: &'a str) -> Vec<Span<'a>> {
+    text.split("**")
+        .enumerate()
+        .filter(|&(_, s)| !s.is_empty())
+        .map(|(i, s)| Span { text: s, bold: i % 2 == 1 })
+        .collect()
+}
+
+/// Free text without its `**` markers.
+pub fn plain(text: &str) -> String {
+    text.replace("**", "")
+}
+
+fn s(text: &str) -> String {
+    text.to_string()
+}
+
+fn highlight(name: &str, text: &str) -> Highlight {
+    Highlight { name: Some(s(name)), text: s(text) }
+}
+
+fn note(text: &str) -> Highlight {
+    Highlight { name: None, text: s(text) }
+}
+
+fn skill(name: &str, level: u16) -> Skill {
+    Skill { name: s(name), level }
+}
+
+fn tools(title: &str, items: &[&str]) -> ToolGroup {
+    ToolGroup { title: s(title), items: items.iter().map(|i| s(i)).collect() }
+}
+
+impl Default for Cv {
+    fn default() -> Cv {
+        Cv {
+            name: s("Oscar Forner Martinez"),
+            short_name: s("Oscar Forner"),
+            headline: s("Senior Software Engineer"),
+            photo: None,
+            banner_font: None,
+            birth_date: Some(Date::day(1988, 3, 24)),
+            nationality: s("Spanish"),
+            location: s("Spain"),
+            about: vec![
+                s("I am a Senior Software Engineer interested in **Backend development** and **Systems Programming**."),
+                s("I am a regular attendee of the **C++** and **Go** MeetUps in London (Virtually)."),
+                s("I enjoy playing **handball** and all sorts of **board games**, including **Magic the Gathering**, and **videogames**."),
+            ],
+            contacts: vec![
+                Contact { kind: ContactKind::Email, value: s("oscar.forner.martinez@gmail.com") },
+                Contact { kind: ContactKind::Phone, value: s("+34 617 60 60 12") },
+                Contact { kind: ContactKind::Website, value: s("https://oscarforner.com/") },
+                Contact { kind: ContactKind::Twitter, value: s("https://twitter.com/oscar_forner") },
+                Contact { kind: ContactKind::GitHub, value: s("https://github.com/maitesin") },
+                Contact { kind: ContactKind::LinkedIn, value: s("http://linkedin.com/in/oscarforner") },
+            ],
+            languages: vec![
+                Language { name: s("Spanish"), level: s("Native") },
+                Language { name: s("Catalan"), level: s("Native") },
+                Language { name: s("English"), level: s("Fluent") },
+            ],
+            studies: vec![
+                Study {
+                    start: Date::year(2010),
+                    end: Date::year(2013),
+                    degree: s("Bachelor of Engineering in Computer Science"),
+                    summary: s("Advanced Data Structures, Compilers and Interpreters, Advanced Operating Systems, and Distributed Systems."),
+                    eqf_level: Some(6),
+                },
+                Study {
+                    start: Date::year(2006),
+                    end: Date::year(2010),
+                    degree: s("Associate Degree in Computer Science"),
+                    summary: s("Operating Systems, Real Time Operating Systems and Embedded Systems."),
+                    eqf_level: Some(5),
+                },
+            ],
+            skills: vec![
+                SkillGroup {
+                    title: s("Programming Languages"),
+                    skills: vec![skill("Go", 95), skill("Python", 85), skill("C++(11/14)", 80), skill("C", 70)],
+                },
+                SkillGroup {
+                    title: s("Operating Systems"),
+                    skills: vec![skill("GNU/Linux", 95), skill("FreeBSD", 80), skill("OpenBSD", 70)],
+                },
+            ],
+            tools: vec![
+                tools("Orchestration", &["Kubernetes", "Helm", "Istio", "Docker Compose", "Heroku", "GKE"]),
+                tools("Build Systems", &["CMake", "Meson", "Make", "Ninja"]),
+                tools("CI/CD", &["Gitlab CI", "Jenkins", "GitHub Actions", "Travis"]),
+                tools("Analysis", &["Golint", "Go vet", "Clang-sanitizer", "Perf", "Valgrind"]),
+                tools("Databases", &["MongoDB", "PostgreSQL", "SQLite", "CockroachDB", "MariaDB", "MySQL"]),
+                tools("Message Broker", &["RabbitMQ", "PubSub"]),
+                tools("Hypervisors", &["KVM", "VirtualBox", "VMWare", "OpenBSD's VMM"]),
+            ],
+            experience: vec![
+                Job {
+                    start: Date::year(2020),
+                    end: None,
+                    position: s("Lead Software Engineer"),
+                    company: s("Paack"),
+                    highlights: vec![
+                        note("During my time in Paack I have worked in multiple projects, such as **Warehouse Management System**, **Business Rules Engine**, **New Retailer API**, and **Communications Service**. All of them were developed in **Go** using **PostgreSQL** as the database to store the information of the services and they were deployed in **Kubernetes** using **Helm** and **Istio**."),
+                        note("As part of my role as Lead Software Engineer I mentored several people in my team and I was heavily involved in the hiring process of the company."),
+                    ],
+                },
+                Job {
+                    start: Date::year(2018),
+                    end: Some(Date::year(2020)),
+                    position: s("Senior Software Engineer"),
+                    company: s("GlobalSign"),
+                    highlights: vec![
+                        highlight("High Performance Platform", "Implemented new functionalities that served thousands of certificates per second. Lead the migration of backend storage from MongoDB to PostgreSQL."),
+                        highlight("Kubernetes prototype", "Implemented a prototype of the system used in production to test the feasibility of a future migration."),
+                        highlight("Others", "Mentored multiple new-hires and had a highly involved role in the hiring process. As a side project I developed an static analysis tool to enforce the code style used in the company."),
+                    ],
+                },
+                Job {
+                    start: Date::year(2016),
+                    end: Some(Date::year(2018)),
+                    position: s("Software Engineer"),
+                    company: s("VCA Technology"),
+                    highlights: vec![
+                        highlight("Network library", "Allows the user to interact with Boost ASIO through the std::stream interface."),
+                        highlight("Tools", "Improve the toolchain used internally to allow the usage of clang-tidy for the linting of our code. Another tool allows to switch between several versions of the toolchain without having to change the environment."),
+                        highlight("Backend development", "Several activities regarding the extension and implementation of new features in the backend of the product such as Authentication and GStreamer sinks."),
+                    ],
+                },
+                Job {
+                    start: Date::year(2015),
+                    end: Some(Date::year(2016)),
+                    position: s("Software Developer"),
+                    company: s("Programming Research"),
+                    highlights: vec![
+                        highlight("Static analysis of code", "Checks if the code has some patterns that have an undefined behaviour, unspecified in the Standard, and/or implementation-defined."),
+                        highlight("Dataflow analysis of code", "Checks the complexity of methods, pointer problems, memory handling, etc."),
+                        note("Took over two projects to refactor, maintain and add new features."),
+                    ],
+                },
+            ],
+            courses: vec![
+                Course {
+                    title: s("LFS258: Kubernetes Fundamentals"),
+                    provider: s("Linux Foundation"),
+                    date: Date::month(2019, 8),
+                    summary: s("Learnt how to setup, mantain and use a Kubernetes cluster, including how to deploy a containerized application and manipulating resources via the API."),
+                },
+                Course {
+                    title: s("LFD331: Developing Linux Device Drivers"),
+                    provider: s("Linux Foundation"),
+                    date: Date::month(2016, 4),
+                    summary: s("Learnt about the different Linux device drivers, APIs and methods through which devices interface with the kernel."),
+                },
+                Course {
+                    title: s("LFD320: Linux Kernel Internals and Debugging"),
+                    provider: s("Linux Foundation"),
+                    date: Date::month(2016, 3),
+                    summary: s("Learnt how Linux is architected, the basic methods for developing on the kernel, and how to work with the community."),
+                },
+                Course {
+                    title: s("Agile for Developers"),
+                    provider: s("Accelebrate"),
+                    date: Date::month(2015, 8),
+                    summary: s("Teaches intermediate and advanced object-oriented developers the practices of Agile and Scrum."),
+                },
+                Course {
+                    title: s("Algorithms, Part II"),
+                    provider: s("Coursera"),
+                    date: Date::month(2014, 11),
+                    summary: s("Focuses on graph, and string processing algorithms."),
+                },
+                Course {
+                    title: s("Algorithms, Part I"),
+                    provider: s("Coursera"),
+                    date: Date::month(2014, 9),
+                    summary: s("Covers elementary data structures, sorting, and searching algorithms."),
+                },
+            ],
+            looking_for: LookingFor {
+                headline: s("I am currently looking for new opportunities"),
+                intro: s("My ideal roles involve a combination of the following:"),
+                roles: vec![
+                    s("Write low level libraries and/or components."),
+                    s("Design, develop and maintain a high performance and reliable systems."),
+                    s("Create and integrate APIs to expose and extend the functionality."),
+                    s("Create and improve the tools used during the development process."),
+                    s("Work in the internals of Operating Systems such as GNU/Linux and FreeBSD."),
+                    s("Work on compilers and/or interpreters and designing programming languages."),
+                    s("Contribute to Open Source software."),
+                ],
+            },
+            interests: vec![s("Handball"), s("Board games"), s("Magic the Gathering"), s("Videogames")],
+            extensions: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! The curriculum as coloured text for terminals, which is what `curl`
+//! gets from `serve http`.
+//!
+//! Text takes the colours of its `theme::Role` as SGR escape sequences and
+//! is wrapped to a width, the terminal not being there to ask.
+
+use std::cmp;
+use std::io::{self, Write};
+
+use tui::style::{Color, Modifier};
+
+use cv::{self, Cv};
+use theme::Role;
+
+use super::Section;
+
+/// The width when none is given.
+pub const WIDTH: usize = 80;
+
+const RESET: &str = "\x1b[0m";
+const GAUGE_FULL: char = '█';
+const GAUGE_EMPTY: char = '░';
+
+fn color(color: Color) -> Option<u8> {
+    match color {
+        Color::Black => Some(30),
+        Color::Red => Some(31),
+        Color::Green => Some(32),
+        Color::Yellow => Some(33),
+        Color::Blue => Some(34),
+        Color::Magenta => Some(35),
+        Color::Cyan => Some(36),
+        Color::Gray => Some(37),
+        Color::DarkGray => Some(90),
+        Color::LightRed => Some(91),
+        Color::LightGreen => Some(92),
+        Color::LightYellow => Some(93),
+        Color::LightBlue => Some(94),
+        Color::LightMagenta => Some(95),
+        Color::LightCyan => Some(96),
+        Color::White => Some(97),
+        _ => None,
+    }
+}
+
+/// The escape sequence that starts text in `role`, empty for plain text.
+/// Backgrounds are left to the terminal.
+fn sgr(role: Role) -> String {
+    let style = role.style();
+    let mut codes = Vec::new();
+    match style.modifier {
+        Modifier::Bold => codes.push(1),
+        Modifier::Italic => codes.push(3),
+        Modifier::Underline => codes.push(4),
+        _ => {}
+    }
+    codes.extend(color(style.fg));
+    if codes.is_empty() {
+        return String::new();
+    }
+    let codes: Vec<String> = codes.iter().map(|c| c.to_string()).collect();
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn paint(role: Role, text: &str) -> String {
+    match sgr(role) {
+        ref start if start.is_empty() => text.to_string(),
+        start => format!("{}{}{}", start, text, RESET),
+    }
+}
+
+/// A line being filled, which only changes colours where the role changes.
+struct Line {
+    text: String,
+    role: Option<Role>,
+    columns: usize,
+}
+
+impl Line {
+    fn new(prefix: &str, columns: usize) -> Line {
+        Line { text: prefix.to_string(), role: None, columns }
+    }
+
+    fn push(&mut self, role: Role, c: char) {
+        if self.role != Some(role) {
+            if self.role.map_or(false, |r| !sgr(r).is_empty()) {
+                self.text.push_str(RESET);
+            }
+            self.text.push_str(&sgr(role));
+            self.role = Some(role);
+        }
+        self.text.push(c);
+        self.columns += 1;
+    }
+
+    fn finish(mut self) -> String {
+        if self.role.map_or(false, |r| !sgr(r).is_empty()) {
+            self.text.push_str(RESET);
+        }
+        self.text.push('\n');
+        self.text
+    }
+}
+
+/// Free text in `role` wrapped to `width`, its `**` words in bold. The first
+/// line starts with `first` and the others with as many spaces as `indent`.
+fn wrap(text: &str, role: Role, first: &str, indent: usize, width: usize) -> String {
+    // Markers may be in the middle of a word, so words are split after
+    // every character has its role.
+    let mut words: Vec<Vec<(char, Role)>> = vec![Vec::new()];
+    for span in cv::spans(text) {
+        let role = if span.bold && role == Role::Body { Role::Emphasis } else { role };
+        for c in span.text.chars() {
+            if c.is_whitespace() {
+                if !words.last().map_or(true, |w| w.is_empty()) {
+                    words.push(Vec::new());
+                }
+            } else {
+                words.last_mut().unwrap().push((c, role));
+            }
+        }
+    }
+
+    let mut wrapped = String::new();
+    let mut line = Line::new(first, indent);
+    let mut empty = true;
+    for word in words.into_iter().filter(|w| !w.is_empty()) {
+        if !empty && line.columns + 1 + word.len() > width {
+            wrapped.push_str(&line.finish());
+            line = Line::new(&" ".repeat(indent), indent);
+            empty = true;
+        }
+        if !empty {
+            // The space keeps the colour when the next word has it too.
+            let role = match line.role {
+                Some(role) if role == word[0].1 => role,
+                _ => Role::Body,
+            };
+            line.push(role, ' ');
+        }
+        for (c, role) in word {
+            line.push(role, c);
+        }
+        empty = false;
+    }
+    wrapped.push_str(&line.finish());
+    wrapped
+}
+
+fn field<W: Write>(out: &mut W, label: &str, value: &str) -> Result<(), io::Error> {
+    writeln!(out, "  {} {}", paint(Role::Label, &format!("{}:", label)), value)
+}
+
+fn title<W: Write>(out: &mut W, title: &str, width: usize) -> Result<(), io::Error> {
+    let rule = "─".repeat(width.saturating_sub(title.chars().count() + 4));
+    writeln!(out, "\n{}", paint(Role::Section, &format!("── {} {}", title, rule)))
+}
+
+fn gauge(level: u16, width: usize) -> String {
+    let full = cmp::min(level as usize, 100) * width / 100;
+    let bar: String = (0..width).map(|i| if i < full { GAUGE_FULL } else { GAUGE_EMPTY }).collect();
+    paint(Role::Gauge, &bar)
+}
+
+fn personal<W: Write>(cv: &Cv, width: usize, out: &mut W) -> Result<(), io::Error> {
+    field(out, "Name", &cv.name)?;
+    for (label, value) in cv.information() {
+        field(out, label, &value)?;
+    }
+    writeln!(out)?;
+    for paragraph in &cv.about {
+        out.write_all(wrap(paragraph, Role::Body, "  ", 2, width).as_bytes())?;
+    }
+    writeln!(out)?;
+    for language in &cv.languages {
+        field(out, &language.name, &language.level)?;
+    }
+    for study in &cv.studies {
+        writeln!(out)?;
+        let heading = format!("{} - {}: {}", study.start, study.end, study.degree);
+        out.write_all(wrap(&heading, Role::Heading, "  ", 2, width).as_bytes())?;
+        out.write_all(wrap(&study.summary, Role::Body, "  ", 2, width).as_bytes())?;
+    }
+    writeln!(out)?;
+    for contact in &cv.contacts {
+        field(out, contact.kind.label(), &contact.value)?;
+    }
+    Ok(())
+}
+
+fn skills<W: Write>(cv: &Cv, width: usize, out: &mut W) -> Result<(), io::Error> {
+    for group in &cv.skills {
+        writeln!(out, "  {}", paint(Role::Heading, &group.title))?;
+        let names = group.skills.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+        // The name, the gauge and the level, which takes up to four columns.
+        let bar = cmp::min(40, width.saturating_sub(names + 4 + 2 + 4));
+        for skill in &group.skills {
+            writeln!(out, "    {:names$}  {} {:>3}", skill.name, gauge(skill.level, bar), skill.level, names = names)?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "  {}", paint(Role::Heading, "Others"))?;
+    for group in &cv.tools {
+        let first = format!("    {} ", paint(Role::Label, &format!("{}:", group.title)));
+        let indent = 4 + group.title.chars().count() + 2;
+        out.write_all(wrap(&group.items.join(", "), Role::Body, &first, indent, width).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn experience<W: Write>(cv: &Cv, width: usize, out: &mut W) -> Result<(), io::Error> {
+    for (i, job) in cv.experience.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        out.write_all(wrap(&job.title(), Role::Heading, "  ", 2, width).as_bytes())?;
+        for highlight in &job.highlights {
+            let text = match highlight.name {
+                Some(ref name) => format!("**{}:** {}", name, highlight.text),
+                None => highlight.text.clone(),
+            };
+            out.write_all(wrap(&text, Role::Body, "    • ", 6, width).as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn courses<W: Write>(cv: &Cv, width: usize, out: &mut W) -> Result<(), io::Error> {
+    for (i, course) in cv.courses.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        out.write_all(wrap(&course.heading(), Role::Heading, "  ", 2, width).as_bytes())?;
+        out.write_all(wrap(&course.summary, Role::Body, "  ", 2, width).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn looking_for<W: Write>(cv: &Cv, width: usize, out: &mut W) -> Result<(), io::Error> {
+    out.write_all(wrap(&cv.looking_for.headline, Role::Heading, "  ", 2, width).as_bytes())?;
+    out.write_all(wrap(&cv.looking_for.intro, Role::Body, "  ", 2, width).as_bytes())?;
+    for role in &cv.looking_for.roles {
+        out.write_all(wrap(role, Role::Body, "    • ", 6, width).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes `sections` under the name and headline, each with its title.
+pub fn write<W: Write>(cv: &Cv, sections: &[Section], width: usize, out: &mut W) -> Result<(), io::Error> {
+    writeln!(out, "{}", paint(Role::Emphasis, &cv.name))?;
+    if !cv.headline.is_empty() {
+        writeln!(out, "{}", cv.headline)?;
+    }
+    for &section in sections {
+        title(out, section.title(), width)?;
+        match section {
+            Section::Personal => personal(cv, width, out)?,
+            Section::Skills => skills(cv, width, out)?,
+            Section::Experience => experience(cv, width, out)?,
+            Section::Courses => courses(cv, width, out)?,
+            Section::LookingFor => looking_for(cv, width, out)?,
+        }
+    }
+    Ok(())
+}
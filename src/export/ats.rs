@@ -0,0 +1,130 @@
+//! Plain text export for applicant tracking systems.
+//!
+//! Those systems are easily confused by anything but a single column of
+//! text, so this uses the section names they expect, ISO dates and no
+//! decorations at all.
+
+use std::io::{self, Write};
+
+use cv::{self, Cv, Date};
+
+fn period(start: &Date, end: &Option<Date>) -> String {
+    match *end {
+        Some(ref end) => format!("{} - {}", start.iso(), end.iso()),
+        None => format!("{} - Present", start.iso()),
+    }
+}
+
+/// Every skill and tool once, in the order they are shown in the Skills tab.
+pub fn keywords(cv: &Cv) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    let skills = cv.skills.iter().flat_map(|g| g.skills.iter().map(|s| &s.name));
+    let tools = cv.tools.iter().flat_map(|g| g.items.iter());
+    for keyword in skills.chain(tools) {
+        if !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+            keywords.push(keyword.clone());
+        }
+    }
+    keywords
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    writeln!(out, "{}", cv.name)?;
+    writeln!(out, "{}", cv.headline)?;
+    if !cv.location.is_empty() {
+        writeln!(out, "Location: {}", cv.location)?;
+    }
+    for contact in &cv.contacts {
+        writeln!(out, "{}: {}", contact.kind.label(), contact.value)?;
+    }
+
+    writeln!(out, "\nSummary")?;
+    for paragraph in &cv.about {
+        writeln!(out, "{}", cv::plain(paragraph))?;
+    }
+
+    writeln!(out, "\nExperience")?;
+    for (i, job) in cv.experience.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", job.position)?;
+        writeln!(out, "{}", job.company)?;
+        writeln!(out, "{}", period(&job.start, &job.end))?;
+        for highlight in &job.highlights {
+            match highlight.name {
+                Some(ref name) => writeln!(out, "- {}: {}", name, cv::plain(&highlight.text))?,
+                None => writeln!(out, "- {}", cv::plain(&highlight.text))?,
+            }
+        }
+    }
+
+    writeln!(out, "\nEducation")?;
+    for (i, study) in cv.studies.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", study.degree)?;
+        writeln!(out, "{}", period(&study.start, &Some(study.end)))?;
+        writeln!(out, "{}", cv::plain(&study.summary))?;
+    }
+
+    writeln!(out, "\nSkills")?;
+    writeln!(out, "Keywords: {}", keywords(cv).join(", "))?;
+    for group in &cv.skills {
+        let names: Vec<&str> = group.skills.iter().map(|s| s.name.as_str()).collect();
+        writeln!(out, "{}: {}", group.title, names.join(", "))?;
+    }
+    for group in &cv.tools {
+        writeln!(out, "{}: {}", group.title, group.items.join(", "))?;
+    }
+
+    writeln!(out, "\nCertifications")?;
+    for (i, course) in cv.courses.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", course.title)?;
+        writeln!(out, "{}", course.provider)?;
+        writeln!(out, "{}", course.date.iso())?;
+        writeln!(out, "{}", cv::plain(&course.summary))?;
+    }
+
+    writeln!(out, "\nLanguages")?;
+    for language in &cv.languages {
+        writeln!(out, "{}: {}", language.name, language.level)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cv::{Skill, SkillGroup, ToolGroup};
+
+    #[test]
+    fn keywords_once_in_tab_order() {
+        let cv = Cv {
+            skills: vec![SkillGroup {
+                title: "Languages".to_string(),
+                skills: vec![Skill { name: "Go".to_string(), level: 90 }, Skill { name: "Rust".to_string(), level: 80 }],
+            }],
+            tools: vec![ToolGroup { title: "Others".to_string(), items: vec!["rust".to_string(), "Make".to_string()] }],
+            ..Cv::default()
+        };
+        assert_eq!(keywords(&cv), ["Go", "Rust", "Make"]);
+    }
+
+    #[test]
+    fn plain_text() {
+        let mut text = Vec::new();
+        write(&Cv::default(), &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        for section in &["Summary", "Experience", "Education", "Skills", "Certifications", "Languages"] {
+            assert!(text.lines().any(|line| line == *section), "no {} in:\n{}", section, text);
+        }
+        assert!(!text.contains("**"), "markup in:\n{}", text);
+        assert!(text.contains(" - Present\n"), "no current position in:\n{}", text);
+        assert!(text.lines().any(|line| line.starts_with("Keywords: Go, Python, ")), "no keywords in:\n{}", text);
+    }
+}
@@ -0,0 +1,294 @@
+//! Office Open XML (`.docx`) export, for the agencies that want to rebrand
+//! the curriculum in Word.
+//!
+//! The document only uses the built-in style names (`Title`, `Heading1`,
+//! `ListBullet`, ...) so restyling it in Word or LibreOffice is a matter of
+//! changing the styles instead of the text.
+
+use std::io::{self, Cursor, Write};
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use cv::{self, Cv};
+use super::xml::{escape, DECLARATION};
+
+const CONTENT_TYPES: &str = r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+<Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/>
+</Types>"#;
+
+const PACKAGE_RELS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>
+</Relationships>"#;
+
+const APP: &str = r#"<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties">
+<Application>rust-cv</Application>
+</Properties>"#;
+
+const STYLES: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults>
+<w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:cs="Calibri"/><w:sz w:val="22"/><w:lang w:val="en-GB"/></w:rPr></w:rPrDefault>
+<w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="264" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+</w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/>
+<w:pPr><w:spacing w:after="60"/></w:pPr><w:rPr><w:b/><w:color w:val="2E7D32"/><w:sz w:val="52"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Subtitle"><w:name w:val="Subtitle"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/>
+<w:pPr><w:spacing w:after="240"/></w:pPr><w:rPr><w:color w:val="595959"/><w:sz w:val="28"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/>
+<w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:color w:val="2E7D32"/><w:sz w:val="32"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/>
+<w:pPr><w:keepNext/><w:spacing w:before="200" w:after="60"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:color w:val="9E7C00"/><w:sz w:val="24"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListBullet"><w:name w:val="List Bullet"/><w:basedOn w:val="Normal"/><w:qFormat/>
+<w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr><w:spacing w:after="60"/></w:pPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="table" w:default="1" w:styleId="TableNormal"><w:name w:val="Normal Table"/>
+<w:tblPr><w:tblInd w:w="0" w:type="dxa"/><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>
+<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:basedOn w:val="TableNormal"/>
+<w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders></w:tblPr></w:style>
+</w:styles>"#;
+
+const NUMBERING: &str = r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:abstractNum w:abstractNumId="0"><w:multiLevelType w:val="singleLevel"/>
+<w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="bullet"/><w:lvlText w:val="&#8226;"/><w:lvlJc w:val="left"/>
+<w:pPr><w:ind w:left="360" w:hanging="360"/></w:pPr></w:lvl>
+</w:abstractNum>
+<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+</w:numbering>"#;
+
+const HYPERLINK_REL: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+
+/// The body of `word/document.xml` together with the relationships its
+/// hyperlinks point to.
+struct Document {
+    body: String,
+    links: Vec<String>,
+}
+
+impl Document {
+    fn new() -> Document {
+        Document { body: String::new(), links: Vec::new() }
+    }
+
+    fn paragraph(&mut self, style: Option<&str>, runs: &str) {
+        self.body.push_str("<w:p>");
+        if let Some(style) = style {
+            self.body.push_str(&format!("<w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>", style));
+        }
+        self.body.push_str(runs);
+        self.body.push_str("</w:p>\n");
+    }
+
+    fn heading(&mut self, level: u8, text: &str) {
+        self.paragraph(Some(&format!("Heading{}", level)), &run(text, false));
+    }
+
+    /// Returns the runs of a hyperlink, registering its relationship.
+    fn hyperlink(&mut self, url: &str, text: &str) -> String {
+        self.links.push(url.to_string());
+        format!(
+            "<w:hyperlink r:id=\"rIdLink{}\" w:history=\"1\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr>{}</w:r></w:hyperlink>",
+            self.links.len(),
+            text_element(text)
+        )
+    }
+
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]) {
+        let columns = header.len();
+        let width = 9000 / columns;
+        self.body.push_str("<w:tbl><w:tblPr><w:tblStyle w:val=\"TableGrid\"/><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr><w:tblGrid>");
+        for _ in 0..columns {
+            self.body.push_str(&format!("<w:gridCol w:w=\"{}\"/>", width));
+        }
+        self.body.push_str("</w:tblGrid>\n");
+        self.body.push_str("<w:tr><w:trPr><w:tblHeader/></w:trPr>");
+        for cell in header {
+            self.cell(width, &run(cell, true));
+        }
+        self.body.push_str("</w:tr>\n");
+        for row in rows {
+            self.body.push_str("<w:tr>");
+            for cell in row {
+                self.cell(width, &runs(cell));
+            }
+            self.body.push_str("</w:tr>\n");
+        }
+        self.body.push_str("</w:tbl>\n");
+        // Word needs a paragraph between two consecutive tables.
+        self.paragraph(None, "");
+    }
+
+    fn cell(&mut self, width: usize, runs: &str) {
+        self.body.push_str(&format!("<w:tc><w:tcPr><w:tcW w:w=\"{}\" w:type=\"dxa\"/></w:tcPr><w:p>{}</w:p></w:tc>", width, runs));
+    }
+
+    fn document_xml(&self) -> String {
+        format!(
+            "{}<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\n<w:body>\n{}\
+             <w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/>\
+             <w:pgMar w:top=\"1134\" w:right=\"1134\" w:bottom=\"1134\" w:left=\"1134\" w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/></w:sectPr>\n\
+             </w:body>\n</w:document>",
+            DECLARATION, self.body
+        )
+    }
+
+    fn relationships_xml(&self) -> String {
+        let mut rels = format!(
+            "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n\
+             <Relationship Id=\"rIdStyles\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.xml\"/>\n\
+             <Relationship Id=\"rIdNumbering\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering\" Target=\"numbering.xml\"/>\n",
+            DECLARATION
+        );
+        for (i, url) in self.links.iter().enumerate() {
+            rels.push_str(&format!(
+                "<Relationship Id=\"rIdLink{}\" Type=\"{}\" Target=\"{}\" TargetMode=\"External\"/>\n",
+                i + 1,
+                HYPERLINK_REL,
+                escape(url)
+            ));
+        }
+        rels.push_str("</Relationships>");
+        rels
+    }
+}
+
+fn text_element(text: &str) -> String {
+    format!("<w:t xml:space=\"preserve\">{}</w:t>", escape(text))
+}
+
+fn run(text: &str, bold: bool) -> String {
+    if bold {
+        format!("<w:r><w:rPr><w:b/></w:rPr>{}</w:r>", text_element(text))
+    } else {
+        format!("<w:r>{}</w:r>", text_element(text))
+    }
+}
+
+/// Runs for free text, honouring its `**` markers.
+fn runs(text: &str) -> String {
+    cv::spans(text).iter().map(|span| run(span.text, span.bold)).collect()
+}
+
+fn core_xml(cv: &Cv) -> String {
+    format!(
+        "{}<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:title>{} - Curriculum Vitae</dc:title>\n<dc:creator>{}</dc:creator>\n</cp:coreProperties>",
+        DECLARATION,
+        escape(&cv.name),
+        escape(&cv.name)
+    )
+}
+
+fn document(cv: &Cv) -> Document {
+    let mut doc = Document::new();
+
+    doc.paragraph(Some("Title"), &run(&cv.name, false));
+    doc.paragraph(Some("Subtitle"), &run(&cv.headline, false));
+
+    doc.heading(1, "Information");
+    for (label, value) in cv.information() {
+        doc.paragraph(None, &format!("{}{}", run(&format!("{}: ", label), true), run(&value, false)));
+    }
+
+    doc.heading(1, "Contact");
+    for contact in &cv.contacts {
+        let link = doc.hyperlink(&contact.url(), &contact.value);
+        doc.paragraph(None, &format!("{}{}", run(&format!("{}: ", contact.kind.label()), true), link));
+    }
+
+    doc.heading(1, "About me");
+    for paragraph in &cv.about {
+        doc.paragraph(None, &runs(paragraph));
+    }
+
+    doc.heading(1, "Experience");
+    for job in &cv.experience {
+        doc.heading(2, &format!("{} at {}", job.position, job.company));
+        doc.paragraph(None, &run(&cv::period(&job.start, &job.end), false));
+        for highlight in &job.highlights {
+            let name = match highlight.name {
+                Some(ref name) => run(&format!("{}: ", name), true),
+                None => String::new(),
+            };
+            doc.paragraph(Some("ListBullet"), &format!("{}{}", name, runs(&highlight.text)));
+        }
+    }
+
+    doc.heading(1, "Skills");
+    for group in &cv.skills {
+        doc.heading(2, &group.title);
+        let rows: Vec<Vec<String>> = group
+            .skills
+            .iter()
+            .map(|skill| vec![skill.name.clone(), format!("{} / 100", skill.level)])
+            .collect();
+        doc.table(&["Skill", "Level"], &rows);
+    }
+    if !cv.tools.is_empty() {
+        doc.heading(2, "Others");
+        let rows: Vec<Vec<String>> = cv
+            .tools
+            .iter()
+            .map(|group| vec![group.title.clone(), group.items.join(", ")])
+            .collect();
+        doc.table(&["Area", "Tools"], &rows);
+    }
+
+    doc.heading(1, "Languages");
+    for language in &cv.languages {
+        doc.paragraph(None, &format!("{}{}", run(&format!("{}: ", language.name), true), run(&language.level, false)));
+    }
+
+    doc.heading(1, "Studies");
+    for study in &cv.studies {
+        doc.heading(2, &format!("{} - {}: {}", study.start, study.end, study.degree));
+        doc.paragraph(None, &runs(&study.summary));
+    }
+
+    doc.heading(1, "Courses");
+    for course in &cv.courses {
+        doc.heading(2, &course.heading());
+        doc.paragraph(None, &runs(&course.summary));
+    }
+
+    doc.heading(1, "What I am looking for");
+    doc.paragraph(None, &run(&cv.looking_for.headline, true));
+    doc.paragraph(None, &runs(&cv.looking_for.intro));
+    for role in &cv.looking_for.roles {
+        doc.paragraph(Some("ListBullet"), &runs(role));
+    }
+
+    doc
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    let doc = document(cv);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let parts = [
+        ("[Content_Types].xml", format!("{}{}", DECLARATION, CONTENT_TYPES)),
+        ("_rels/.rels", format!("{}{}", DECLARATION, PACKAGE_RELS)),
+        ("docProps/core.xml", core_xml(cv)),
+        ("docProps/app.xml", format!("{}{}", DECLARATION, APP)),
+        ("word/document.xml", doc.document_xml()),
+        ("word/_rels/document.xml.rels", doc.relationships_xml()),
+        ("word/styles.xml", format!("{}{}", DECLARATION, STYLES)),
+        ("word/numbering.xml", format!("{}{}", DECLARATION, NUMBERING)),
+    ];
+    for &(name, ref content) in &parts {
+        zip.start_file(name, options)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    let archive = zip.finish()?;
+    out.write_all(&archive.into_inner())
+}
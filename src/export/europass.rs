@@ -0,0 +1,333 @@
+//! Europass CV export, in the XML form of the Europass data model (schema
+//! version 3.4), for applications to EU institutions.
+//!
+//! Languages are split between mother tongues and foreign languages with a
+//! CEFR self-assessment grid, studies become education entries with their EQF
+//! level and courses become training, which Europass lists with the
+//! achievements as `courses`.
+
+use std::io::{self, Write};
+use std::slice;
+
+use cv::{self, ContactKind, Cv, Date};
+use super::xml::{escape, DECLARATION};
+
+const SCHEMA_VERSION: &str = "V3.4";
+
+/// A level of the Common European Framework of Reference for Languages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cefr {
+    A1,
+    A2,
+    B1,
+    B2,
+    C1,
+    C2,
+}
+
+impl Cefr {
+    fn code(&self) -> &'static str {
+        match *self {
+            Cefr::A1 => "A1",
+            Cefr::A2 => "A2",
+            Cefr::B1 => "B1",
+            Cefr::B2 => "B2",
+            Cefr::C1 => "C1",
+            Cefr::C2 => "C2",
+        }
+    }
+}
+
+/// How a level from the Languages panel reads in Europass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Proficiency {
+    MotherTongue,
+    Foreign(Cefr),
+}
+
+fn proficiency(level: &str) -> Option<Proficiency> {
+    let level = level.trim().to_lowercase();
+    let cefr = match level.as_str() {
+        "native" | "mother tongue" | "bilingual" => return Some(Proficiency::MotherTongue),
+        "a1" | "beginner" => Cefr::A1,
+        "a2" | "basic" | "elementary" => Cefr::A2,
+        "b1" | "intermediate" => Cefr::B1,
+        "b2" | "upper intermediate" | "professional working" => Cefr::B2,
+        "c1" | "fluent" | "advanced" => Cefr::C1,
+        "c2" | "proficient" | "full professional" => Cefr::C2,
+        _ => return None,
+    };
+    Some(Proficiency::Foreign(cefr))
+}
+
+/// ISO 639-1 codes of the languages Europass lists.
+fn language_code(name: &str) -> Option<&'static str> {
+    let code = match name.trim().to_lowercase().as_str() {
+        "spanish" | "español" | "castellano" => "es",
+        "catalan" | "català" => "ca",
+        "english" => "en",
+        "french" | "français" => "fr",
+        "german" | "deutsch" => "de",
+        "italian" | "italiano" => "it",
+        "portuguese" | "português" => "pt",
+        "basque" | "euskara" => "eu",
+        "galician" | "galego" => "gl",
+        "dutch" | "nederlands" => "nl",
+        "polish" | "polski" => "pl",
+        "romanian" | "română" => "ro",
+        "greek" => "el",
+        "swedish" => "sv",
+        "danish" => "da",
+        "finnish" => "fi",
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn country_code(name: &str) -> Option<&'static str> {
+    let code = match name.trim().to_lowercase().as_str() {
+        "spain" | "españa" => "ES",
+        "united kingdom" | "uk" => "UK",
+        "france" => "FR",
+        "germany" => "DE",
+        "italy" => "IT",
+        "portugal" => "PT",
+        "ireland" => "IE",
+        "netherlands" => "NL",
+        "belgium" => "BE",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// The attributes Europass uses for dates: `year`, `month` as `--MM` and
+/// `day` as `---DD`.
+fn date_attributes(date: &Date) -> String {
+    let mut attributes = format!("year=\"{:04}\"", date.year);
+    if let Some(month) = date.month {
+        attributes.push_str(&format!(" month=\"--{:02}\"", month));
+    }
+    if let Some(day) = date.day {
+        attributes.push_str(&format!(" day=\"---{:02}\"", day));
+    }
+    attributes
+}
+
+fn period(from: &Date, to: Option<&Date>) -> String {
+    match to {
+        Some(to) => format!("<Period><From {}/><To {}/></Period>", date_attributes(from), date_attributes(to)),
+        None => format!("<Period><From {}/><Current>true</Current></Period>", date_attributes(from)),
+    }
+}
+
+/// Europass rich text fields hold HTML, which is escaped once more to fit
+/// in the XML.
+fn rich_text(paragraphs: &[String]) -> String {
+    let html: String = paragraphs
+        .iter()
+        .map(|p| {
+            let inner: String = cv::spans(p)
+                .iter()
+                .map(|s| if s.bold { format!("<b>{}</b>", escape(s.text)) } else { escape(s.text) })
+                .collect();
+            format!("<p>{}</p>", inner)
+        })
+        .collect();
+    escape(&html)
+}
+
+fn identification(cv: &Cv) -> String {
+    let mut names = cv.name.splitn(2, ' ');
+    let first = names.next().unwrap_or("");
+    let surname = names.next().unwrap_or("");
+    let mut xml = format!(
+        "<Identification>\n<PersonName><FirstName>{}</FirstName><Surname>{}</Surname></PersonName>\n<ContactInfo>\n",
+        escape(first),
+        escape(surname)
+    );
+    if !cv.location.is_empty() {
+        let code = country_code(&cv.location).map(|c| format!("<Code>{}</Code>", c)).unwrap_or_default();
+        xml.push_str(&format!(
+            "<Address><Contact><Country>{}<Label>{}</Label></Country></Contact></Address>\n",
+            code,
+            escape(&cv.location)
+        ));
+    }
+    let contacts = |kind: ContactKind| cv.contacts.iter().filter(move |c| c.kind == kind);
+    if let Some(email) = contacts(ContactKind::Email).next() {
+        xml.push_str(&format!("<Email><Contact>{}</Contact></Email>\n", escape(&email.value)));
+    }
+    let phones: String = contacts(ContactKind::Phone)
+        .map(|c| format!("<Telephone><Contact>{}</Contact><Use><Code>mobile</Code></Use></Telephone>", escape(&c.value)))
+        .collect();
+    if !phones.is_empty() {
+        xml.push_str(&format!("<TelephoneList>{}</TelephoneList>\n", phones));
+    }
+    let websites: String = cv
+        .contacts
+        .iter()
+        .filter(|c| c.kind == ContactKind::Website || c.kind == ContactKind::GitHub || c.kind == ContactKind::LinkedIn)
+        .map(|c| {
+            let usage = if c.kind == ContactKind::Website { "personal" } else { "business" };
+            format!("<Website><Contact>{}</Contact><Use><Code>{}</Code></Use></Website>", escape(&c.value), usage)
+        })
+        .collect();
+    if !websites.is_empty() {
+        xml.push_str(&format!("<WebsiteList>{}</WebsiteList>\n", websites));
+    }
+    let messaging: String = contacts(ContactKind::Twitter)
+        .map(|c| format!("<InstantMessaging><Contact>{}</Contact><Use><Label>Twitter</Label></Use></InstantMessaging>", escape(&c.value)))
+        .collect();
+    if !messaging.is_empty() {
+        xml.push_str(&format!("<InstantMessagingList>{}</InstantMessagingList>\n", messaging));
+    }
+    xml.push_str("</ContactInfo>\n<Demographics>");
+    if let Some(ref date) = cv.birth_date {
+        xml.push_str(&format!("<Birthdate {}/>", date_attributes(date)));
+    }
+    if !cv.nationality.is_empty() {
+        xml.push_str(&format!(
+            "<NationalityList><Nationality><Label>{}</Label></Nationality></NationalityList>",
+            escape(&cv.nationality)
+        ));
+    }
+    xml.push_str("</Demographics>\n</Identification>\n");
+    xml
+}
+
+fn work_experience(cv: &Cv) -> String {
+    let mut xml = String::from("<WorkExperienceList>\n");
+    for job in &cv.experience {
+        let activities: Vec<String> = job
+            .highlights
+            .iter()
+            .map(|h| match h.name {
+                Some(ref name) => format!("**{}:** {}", name, h.text),
+                None => h.text.clone(),
+            })
+            .collect();
+        xml.push_str(&format!(
+            "<WorkExperience>{}<Position><Label>{}</Label></Position><Activities>{}</Activities>\
+             <Employer><Name>{}</Name></Employer></WorkExperience>\n",
+            period(&job.start, job.end.as_ref()),
+            escape(&job.position),
+            rich_text(&activities),
+            escape(&job.company)
+        ));
+    }
+    xml.push_str("</WorkExperienceList>\n");
+    xml
+}
+
+fn education(cv: &Cv) -> String {
+    let mut xml = String::from("<EducationList>\n");
+    for study in &cv.studies {
+        let level = study.eqf_level.map(|l| format!("<Level><Code>{}</Code></Level>", l)).unwrap_or_default();
+        xml.push_str(&format!(
+            "<Education>{}<Title>{}</Title><Skills>{}</Skills>{}</Education>\n",
+            period(&study.start, Some(&study.end)),
+            escape(&study.degree),
+            rich_text(slice::from_ref(&study.summary)),
+            level
+        ));
+    }
+    xml.push_str("</EducationList>\n");
+    xml
+}
+
+/// The courses, a training entry each, with where and when they were taken
+/// before what they were about.
+fn training(cv: &Cv) -> String {
+    if cv.courses.is_empty() {
+        return String::new();
+    }
+    let mut xml = String::from("<AchievementList>\n");
+    for course in &cv.courses {
+        let mut paragraphs = vec![format!("**{}**, {}, {}", course.title, course.provider, course.date)];
+        if !course.summary.is_empty() {
+            paragraphs.push(course.summary.clone());
+        }
+        xml.push_str(&format!(
+            "<Achievement><Title><Code>courses</Code><Label>Courses</Label></Title><Description>{}</Description></Achievement>\n",
+            rich_text(&paragraphs)
+        ));
+    }
+    xml.push_str("</AchievementList>\n");
+    xml
+}
+
+fn language_description(name: &str) -> String {
+    let code = language_code(name).map(|c| format!("<Code>{}</Code>", c)).unwrap_or_default();
+    format!("<Description>{}<Label>{}</Label></Description>", code, escape(name))
+}
+
+fn skills(cv: &Cv) -> String {
+    let mut mother_tongues = String::new();
+    let mut foreign = String::new();
+    for language in &cv.languages {
+        match proficiency(&language.level) {
+            Some(Proficiency::MotherTongue) => {
+                mother_tongues.push_str(&format!("<MotherTongue>{}</MotherTongue>", language_description(&language.name)));
+            }
+            Some(Proficiency::Foreign(level)) => {
+                let grid: String = ["Listening", "Reading", "SpokenInteraction", "SpokenProduction", "Writing"]
+                    .iter()
+                    .map(|skill| format!("<{0}>{1}</{0}>", skill, level.code()))
+                    .collect();
+                foreign.push_str(&format!(
+                    "<ForeignLanguage>{}<ProficiencyLevel>{}</ProficiencyLevel></ForeignLanguage>",
+                    language_description(&language.name),
+                    grid
+                ));
+            }
+            // Without a level that maps to the grid it is still worth
+            // listing the language.
+            None => {
+                foreign.push_str(&format!("<ForeignLanguage>{}</ForeignLanguage>", language_description(&language.name)));
+            }
+        }
+    }
+    let mut computer: Vec<String> = cv
+        .skills
+        .iter()
+        .map(|group| {
+            let skills: Vec<String> = group.skills.iter().map(|s| format!("{} ({} / 100)", s.name, s.level)).collect();
+            format!("**{}:** {}", group.title, skills.join(", "))
+        })
+        .collect();
+    computer.extend(cv.tools.iter().map(|group| format!("**{}:** {}", group.title, group.items.join(", "))));
+
+    let mut linguistic = String::new();
+    if !mother_tongues.is_empty() {
+        linguistic.push_str(&format!("<MotherTongueList>{}</MotherTongueList>", mother_tongues));
+    }
+    if !foreign.is_empty() {
+        linguistic.push_str(&format!("<ForeignLanguageList>{}</ForeignLanguageList>", foreign));
+    }
+    format!(
+        "<Skills>\n<Linguistic>{}</Linguistic>\n<Computer><Description>{}</Description></Computer>\n</Skills>\n",
+        linguistic,
+        rich_text(&computer)
+    )
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    write!(
+        out,
+        "{}<SkillsPassport xmlns=\"http://europass.cedefop.europa.eu/Europass\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://europass.cedefop.europa.eu/Europass http://europass.cedefop.europa.eu/xml/v3.4.0/EuropassSchema.xsd\" \
+         locale=\"en\">\n\
+         <DocumentInfo><DocumentType>ECV</DocumentType><XSDVersion>{}</XSDVersion><Generator>rust-cv</Generator></DocumentInfo>\n\
+         <LearnerInfo>\n{}<Headline><Type><Code>position</Code><Label>Position</Label></Type>\
+         <Description><Label>{}</Label></Description></Headline>\n{}{}{}{}</LearnerInfo>\n</SkillsPassport>\n",
+        DECLARATION,
+        SCHEMA_VERSION,
+        identification(cv),
+        escape(&cv.headline),
+        work_experience(cv),
+        education(cv),
+        skills(cv),
+        training(cv)
+    )
+}
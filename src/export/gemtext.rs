@@ -0,0 +1,104 @@
+//! Gemtext, the markup of the Gemini protocol, which `serve gemini` sends.
+//!
+//! Gemtext has headings, link lines and bullet lists, and nothing inline:
+//! links cannot be part of a paragraph and bold text is not a thing, so the
+//! `**` of the content are dropped.
+
+use std::io::{self, Write};
+
+use cv::{self, Cv};
+
+use super::Section;
+
+fn personal<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    for (label, value) in cv.information() {
+        writeln!(out, "* {}: {}", label, value)?;
+    }
+    for paragraph in &cv.about {
+        writeln!(out, "\n{}", cv::plain(paragraph))?;
+    }
+    writeln!(out, "\n### Languages")?;
+    for language in &cv.languages {
+        writeln!(out, "* {}: {}", language.name, language.level)?;
+    }
+    writeln!(out, "\n### Studies")?;
+    for study in &cv.studies {
+        writeln!(out, "\n{} - {}: {}", study.start, study.end, study.degree)?;
+        writeln!(out, "{}", cv::plain(&study.summary))?;
+    }
+    writeln!(out, "\n### Contact")?;
+    for contact in &cv.contacts {
+        writeln!(out, "=> {} {}: {}", contact.url(), contact.kind.label(), contact.value)?;
+    }
+    Ok(())
+}
+
+fn skills<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    for group in &cv.skills {
+        writeln!(out, "\n### {}", group.title)?;
+        for skill in &group.skills {
+            writeln!(out, "* {}: {} / 100", skill.name, skill.level)?;
+        }
+    }
+    writeln!(out, "\n### Others")?;
+    for group in &cv.tools {
+        writeln!(out, "* {}: {}", group.title, group.items.join(", "))?;
+    }
+    Ok(())
+}
+
+fn experience<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    for job in &cv.experience {
+        writeln!(out, "\n### {}", job.title())?;
+        for highlight in &job.highlights {
+            match highlight.name {
+                Some(ref name) => writeln!(out, "* {}: {}", name, cv::plain(&highlight.text))?,
+                None => writeln!(out, "* {}", cv::plain(&highlight.text))?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn courses<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    for course in &cv.courses {
+        writeln!(out, "\n### {}", course.heading())?;
+        writeln!(out, "{}", cv::plain(&course.summary))?;
+    }
+    Ok(())
+}
+
+fn looking_for<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    writeln!(out, "{}\n", cv.looking_for.headline)?;
+    writeln!(out, "{}", cv::plain(&cv.looking_for.intro))?;
+    for role in &cv.looking_for.roles {
+        writeln!(out, "* {}", cv::plain(role))?;
+    }
+    Ok(())
+}
+
+/// Writes `sections` under the name and headline. `links` are written
+/// before them, as link lines to the pages of the sections.
+pub fn write<W: Write>(cv: &Cv, sections: &[Section], links: &[(String, &str)], out: &mut W) -> Result<(), io::Error> {
+    writeln!(out, "# {}", cv.name)?;
+    if !cv.headline.is_empty() {
+        writeln!(out, "{}", cv.headline)?;
+    }
+    if !links.is_empty() {
+        writeln!(out)?;
+        for &(ref url, title) in links {
+            writeln!(out, "=> {} {}", url, title)?;
+        }
+    }
+    for &section in sections {
+        writeln!(out, "\n## {}", section.title())?;
+        match section {
+            Section::Personal => personal(cv, out)?,
+            Section::Skills => skills(cv, out)?,
+            Section::Experience => experience(cv, out)?,
+            Section::Courses => courses(cv, out)?,
+            Section::LookingFor => looking_for(cv, out)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! The curriculum as a standalone HTML page, which is what browsers get from
+//! `serve http`.
+//!
+//! The page needs nothing else: the style sheet is inline and takes the
+//! colours of the `theme` roles, which the elements are classed after.
+
+use std::io::{self, Write};
+
+use tui::style::Modifier;
+
+use cv::{self, Cv};
+use theme::{self, Role};
+
+use super::xml::escape;
+use super::Section;
+
+const ROLES: &[Role] = &[Role::Section, Role::Heading, Role::Label, Role::Emphasis];
+
+fn style_sheet() -> String {
+    let mut css = String::from(
+        "body{font-family:system-ui,sans-serif;max-width:48em;margin:2em auto;padding:0 1em;line-height:1.5}\
+         nav a{margin-right:1em}ul.contacts{list-style:none;padding:0}\
+         table.skills td{padding:0 .5em 0 0}meter{width:12em}\n",
+    );
+    for role in ROLES {
+        let style = role.style();
+        let mut rules = Vec::new();
+        if let Some(color) = theme::hex(style.fg) {
+            rules.push(format!("color:{}", color));
+        }
+        if style.modifier == Modifier::Bold {
+            rules.push("font-weight:bold".to_string());
+        }
+        css.push_str(&format!(".{}{{{}}}\n", role.name(), rules.join(";")));
+    }
+    css
+}
+
+/// Free text with its `**` words in bold.
+fn rich(text: &str) -> String {
+    cv::spans(text)
+        .iter()
+        .map(|s| if s.bold { format!("<strong class=\"Emphasis\">{}</strong>", escape(s.text)) } else { escape(s.text) })
+        .collect()
+}
+
+fn heading(text: &str) -> String {
+    format!("<h3 class=\"Heading\">{}</h3>\n", escape(text))
+}
+
+fn field(label: &str, value: &str) -> String {
+    format!("<li><span class=\"Label\">{}:</span> {}</li>\n", escape(label), value)
+}
+
+fn personal(cv: &Cv) -> String {
+    let mut html = String::from("<ul>\n");
+    html.push_str(&field("Name", &escape(&cv.name)));
+    for (label, value) in cv.information() {
+        html.push_str(&field(label, &escape(&value)));
+    }
+    html.push_str("</ul>\n");
+    for paragraph in &cv.about {
+        html.push_str(&format!("<p>{}</p>\n", rich(paragraph)));
+    }
+    html.push_str("<ul>\n");
+    for language in &cv.languages {
+        html.push_str(&field(&language.name, &escape(&language.level)));
+    }
+    html.push_str("</ul>\n");
+    for study in &cv.studies {
+        html.push_str(&heading(&format!("{} - {}: {}", study.start, study.end, study.degree)));
+        html.push_str(&format!("<p>{}</p>\n", rich(&study.summary)));
+    }
+    html.push_str("<ul class=\"contacts\">\n");
+    for contact in &cv.contacts {
+        let link = format!("<a href=\"{}\">{}</a>", escape(&contact.url()), escape(&contact.value));
+        html.push_str(&field(contact.kind.label(), &link));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+fn skills(cv: &Cv) -> String {
+    let mut html = String::new();
+    for group in &cv.skills {
+        html.push_str(&heading(&group.title));
+        html.push_str("<table class=\"skills\">\n");
+        for skill in &group.skills {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td><meter min=\"0\" max=\"100\" value=\"{}\">{} / 100</meter></td></tr>\n",
+                escape(&skill.name),
+                skill.level,
+                skill.level
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str(&heading("Others"));
+    html.push_str("<ul>\n");
+    for group in &cv.tools {
+        html.push_str(&field(&group.title, &escape(&group.items.join(", "))));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+fn experience(cv: &Cv) -> String {
+    let mut html = String::new();
+    for job in &cv.experience {
+        html.push_str(&heading(&job.title()));
+        html.push_str("<ul>\n");
+        for highlight in &job.highlights {
+            match highlight.name {
+                Some(ref name) => html.push_str(&field(name, &rich(&highlight.text))),
+                None => html.push_str(&format!("<li>{}</li>\n", rich(&highlight.text))),
+            }
+        }
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+fn courses(cv: &Cv) -> String {
+    let mut html = String::new();
+    for course in &cv.courses {
+        html.push_str(&heading(&course.heading()));
+        html.push_str(&format!("<p>{}</p>\n", rich(&course.summary)));
+    }
+    html
+}
+
+fn looking_for(cv: &Cv) -> String {
+    let mut html = heading(&cv.looking_for.headline);
+    html.push_str(&format!("<p>{}</p>\n<ul>\n", rich(&cv.looking_for.intro)));
+    for role in &cv.looking_for.roles {
+        html.push_str(&format!("<li>{}</li>\n", rich(role)));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/// Writes a page with `sections`, linked from a menu when there are more
+/// than one.
+pub fn write<W: Write>(cv: &Cv, sections: &[Section], out: &mut W) -> Result<(), io::Error> {
+    let title = match *sections {
+        [section] => format!("{} - {}", cv.name, section.title()),
+        _ => cv.name.clone(),
+    };
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<header>\n<h1>{}</h1>\n<p>{}</p>\n",
+        escape(&title),
+        style_sheet(),
+        escape(&cv.name),
+        escape(&cv.headline)
+    )?;
+    if sections.len() > 1 {
+        let links: Vec<String> =
+            sections.iter().map(|s| format!("<a href=\"#{}\">{}</a>", s.name(), escape(s.title()))).collect();
+        writeln!(out, "<nav>{}</nav>", links.join(""))?;
+    }
+    writeln!(out, "</header>")?;
+    for &section in sections {
+        let body = match section {
+            Section::Personal => personal(cv),
+            Section::Skills => skills(cv),
+            Section::Experience => experience(cv),
+            Section::Courses => courses(cv),
+            Section::LookingFor => looking_for(cv),
+        };
+        write!(
+            out,
+            "<section id=\"{}\">\n<h2 class=\"Section\">{}</h2>\n{}</section>\n",
+            section.name(),
+            escape(section.title()),
+            body
+        )?;
+    }
+    writeln!(out, "</body>\n</html>")
+}
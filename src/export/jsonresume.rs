@@ -0,0 +1,581 @@
+//! JSON Resume (https://jsonresume.org/schema/) export and import.
+//!
+//! The schema has no place for some of the content, e.g. skill levels, the
+//! names of the highlights or the `**` markers. Exports keep those in an
+//! `x-rust-cv` object that imports read back, and imports keep whatever the
+//! curriculum has no place for in `Cv::extensions`, which exports merge back
+//! into the document. Either way a round trip loses nothing.
+
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value};
+
+use cv::{self, Contact, ContactKind, Course, Cv, Date, Highlight, Job, Language, Study, ToolGroup};
+
+/// The key of our own data in exported documents.
+const EXTENSION: &str = "x-rust-cv";
+
+/// The key in `Cv::extensions` of the parts of an imported document that
+/// were not used.
+const LEFTOVERS: &str = "jsonresume";
+
+/// Fields of `Cv` that the schema cannot represent faithfully.
+const LOSSY_FIELDS: &[&str] = &[
+    "short_name",
+    "banner_font",
+    "birth_date",
+    "nationality",
+    "location",
+    "about",
+    "contacts",
+    "experience",
+    "studies",
+    "skills",
+    "tools",
+    "courses",
+    "looking_for",
+];
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The last segment of a profile URL, which is the username on every
+/// network we link to.
+fn username(url: &str) -> &str {
+    url.trim_end_matches('/').rsplit('/').next().unwrap_or(url)
+}
+
+fn network(kind: ContactKind) -> Option<&'static str> {
+    match kind {
+        ContactKind::Twitter => Some("Twitter"),
+        ContactKind::GitHub => Some("GitHub"),
+        ContactKind::LinkedIn => Some("LinkedIn"),
+        _ => None,
+    }
+}
+
+/// The kind of contact of a profile on `network`, if the curriculum has one.
+fn profile_kind(network: &str) -> Option<ContactKind> {
+    match network.to_lowercase().as_str() {
+        "twitter" | "x" => Some(ContactKind::Twitter),
+        "github" => Some(ContactKind::GitHub),
+        "linkedin" => Some(ContactKind::LinkedIn),
+        _ => None,
+    }
+}
+
+/// The website contact made of a profile on a network without a kind,
+/// which imports keep whole.
+fn other_profile(profile: &Value) -> Option<String> {
+    let network = profile.get("network").and_then(Value::as_str)?;
+    if profile_kind(network).is_some() {
+        return None;
+    }
+    match (profile.get("url").and_then(Value::as_str), profile.get("username").and_then(Value::as_str)) {
+        (Some(url), _) => Some(url.to_string()),
+        (None, Some(username)) => Some(format!("{} {}", network, username)),
+        (None, None) => None,
+    }
+}
+
+/// The location of `basics` as the single line of the curriculum.
+fn place(location: &Value) -> String {
+    ["address", "city", "region", "countryCode"]
+        .iter()
+        .filter_map(|key| location.get(key).and_then(Value::as_str))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn highlight_text(highlight: &Highlight) -> String {
+    match highlight.name {
+        Some(ref name) => format!("{}: {}", name, cv::plain(&highlight.text)),
+        None => cv::plain(&highlight.text),
+    }
+}
+
+/// Splits "Bachelor of Engineering in Computer Science (MIT)" into the study
+/// type, the area and the institution, which imports join that way.
+fn split_degree(degree: &str) -> (&str, Option<&str>, Option<&str>) {
+    let (degree, institution) = match degree.rfind(" (") {
+        Some(i) if degree.ends_with(')') => (&degree[..i], Some(&degree[i + 2..degree.len() - 1])),
+        _ => (degree, None),
+    };
+    match degree.find(" in ") {
+        Some(i) => (&degree[..i], Some(&degree[i + 4..]), institution),
+        None => (degree, None, institution),
+    }
+}
+
+pub fn to_value(cv: &Cv) -> Result<Value, io::Error> {
+    let mut basics = json!({
+        "name": cv.name,
+        "label": cv.headline,
+        "summary": cv.about.iter().map(|p| cv::plain(p)).collect::<Vec<_>>().join("\n\n"),
+    });
+    // An imported location and the profiles of other networks go back as
+    // they were, the location unless it was changed since.
+    let mut leftovers = cv.extensions.get(LEFTOVERS).cloned().unwrap_or(Value::Null);
+    let (kept_location, kept_profiles) = match leftovers.get_mut("basics").and_then(Value::as_object_mut) {
+        Some(kept) => (kept.remove("location"), kept.remove("profiles")),
+        None => (None, None),
+    };
+    match kept_location {
+        Some(location) if place(&location) == cv.location => basics["location"] = location,
+        _ if !cv.location.is_empty() => basics["location"] = json!({ "region": cv.location }),
+        _ => {}
+    }
+    if let Some(ref photo) = cv.photo {
+        basics["image"] = json!(photo);
+    }
+    let kept_profiles = match kept_profiles {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    let others: Vec<String> = kept_profiles.iter().filter_map(other_profile).collect();
+    let mut profiles = Vec::new();
+    for contact in &cv.contacts {
+        let field = match contact.kind {
+            ContactKind::Email => "email",
+            ContactKind::Phone => "phone",
+            ContactKind::Website if others.contains(&contact.value) => continue,
+            ContactKind::Website => "url",
+            kind => {
+                profiles.push(json!({
+                    "network": network(kind),
+                    "username": username(&contact.value),
+                    "url": contact.value,
+                }));
+                continue;
+            }
+        };
+        if basics.get(field).is_none() {
+            basics[field] = json!(contact.value);
+        }
+    }
+    basics["profiles"] = Value::Array(restore_profiles(profiles, kept_profiles));
+
+    let work: Vec<Value> = cv
+        .experience
+        .iter()
+        .map(|job| {
+            let mut work = json!({
+                "name": job.company,
+                "position": job.position,
+                "startDate": job.start.iso(),
+                "highlights": job.highlights.iter().map(highlight_text).collect::<Vec<_>>(),
+            });
+            if let Some(ref end) = job.end {
+                work["endDate"] = json!(end.iso());
+            }
+            work
+        })
+        .collect();
+
+    let education: Vec<Value> = cv
+        .studies
+        .iter()
+        .map(|study| {
+            let (study_type, area, institution) = split_degree(&study.degree);
+            json!({
+                "institution": institution,
+                "studyType": study_type,
+                "area": area,
+                "startDate": study.start.iso(),
+                "endDate": study.end.iso(),
+                "courses": [cv::plain(&study.summary)],
+            })
+        })
+        .collect();
+
+    let certificates: Vec<Value> = cv
+        .courses
+        .iter()
+        .map(|course| json!({ "name": course.title, "date": course.date.iso(), "issuer": course.provider }))
+        .collect();
+
+    let mut skills: Vec<Value> = cv
+        .skills
+        .iter()
+        .map(|group| {
+            json!({
+                "name": group.title,
+                "keywords": group.skills.iter().map(|s| s.name.clone()).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    skills.extend(cv.tools.iter().map(|group| json!({ "name": group.title, "keywords": group.items })));
+
+    let mut resume = json!({
+        "$schema": "https://raw.githubusercontent.com/jsonresume/resume-schema/v1.0.0/schema.json",
+        "basics": basics,
+        "work": work,
+        "education": education,
+        "certificates": certificates,
+        "skills": skills,
+        "languages": cv.languages.iter().map(|l| json!({ "language": l.name, "fluency": l.level })).collect::<Vec<_>>(),
+        "interests": cv.interests.iter().map(|i| json!({ "name": i })).collect::<Vec<_>>(),
+    });
+
+    let mut extension = match serde_json::to_value(cv).map_err(|err| invalid(err.to_string()))? {
+        Value::Object(fields) => fields,
+        _ => unreachable!("a Cv is always serialized as an object"),
+    };
+    extension.retain(|key, _| LOSSY_FIELDS.contains(&key.as_str()));
+    resume[EXTENSION] = Value::Object(extension);
+
+    merge(&mut resume, &leftovers);
+    Ok(resume)
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    serde_json::to_writer_pretty(&mut *out, &to_value(cv)?).map_err(|err| invalid(err.to_string()))?;
+    writeln!(out)
+}
+
+/// The profiles of the contacts, in the order of those `kept` by an import:
+/// those of other networks as they were, the others with what was left of
+/// them. Profiles of contacts added since come last.
+fn restore_profiles(profiles: Vec<Value>, kept: Vec<Value>) -> Vec<Value> {
+    let mut profiles = profiles.into_iter();
+    let mut restored = Vec::new();
+    for kept in kept {
+        if kept.get("network").is_some() {
+            restored.push(kept);
+        } else if let Some(mut profile) = profiles.next() {
+            merge(&mut profile, &kept);
+            restored.push(profile);
+        }
+    }
+    restored.extend(profiles);
+    restored
+}
+
+/// Copies into `target` everything in `extra` that `target` does not have.
+/// Arrays are merged element by element.
+fn merge(target: &mut Value, extra: &Value) {
+    match (target, extra) {
+        (Value::Object(target), Value::Object(extra)) => {
+            for (key, value) in extra {
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(extra)) => {
+            for (i, value) in extra.iter().enumerate() {
+                match target.get_mut(i) {
+                    Some(existing) => merge(existing, value),
+                    None => target.push(value.clone()),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drops the values that were taken out while importing, leaving `null` in
+/// their place, and the objects and arrays that were emptied. Emptied items
+/// are kept inside arrays that still hold something so the indices match.
+fn prune(value: &mut Value) -> bool {
+    match *value {
+        Value::Null => true,
+        Value::Object(ref mut fields) => {
+            let keys: Vec<String> = fields.keys().cloned().collect();
+            for key in keys {
+                if prune(fields.get_mut(&key).unwrap()) {
+                    fields.remove(&key);
+                }
+            }
+            fields.is_empty()
+        }
+        Value::Array(ref mut items) => {
+            let mut empty = true;
+            for item in items.iter_mut() {
+                empty &= prune(item);
+            }
+            empty
+        }
+        _ => false,
+    }
+}
+
+/// Takes a string out of an object, leaving the rest for the leftovers.
+fn take_str(object: &mut Value, key: &str) -> Option<String> {
+    match object.as_object_mut().and_then(|o| o.remove(key)) {
+        Some(Value::String(s)) => Some(s),
+        Some(Value::Null) | None => None,
+        Some(other) => {
+            object[key] = other;
+            None
+        }
+    }
+}
+
+fn take_array(object: &mut Value, key: &str) -> Vec<Value> {
+    match object.get_mut(key) {
+        Some(&mut Value::Array(ref mut items)) => items.iter_mut().map(|item| item.take()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Puts back the parts of the items of an array that were not used.
+fn restore_array(object: &mut Value, key: &str, items: Vec<Value>) {
+    if object.get(key).map_or(false, |v| v.is_array()) {
+        object[key] = Value::Array(items);
+    }
+}
+
+fn take_date(object: &mut Value, key: &str, context: &str) -> Result<Option<Date>, io::Error> {
+    match take_str(object, key) {
+        Some(ref s) if s.is_empty() => Ok(None),
+        Some(s) => Date::parse(&s)
+            .map(Some)
+            .ok_or_else(|| invalid(format!("{}.{}: invalid date '{}'", context, key, s))),
+        None => Ok(None),
+    }
+}
+
+fn take_strings(object: &mut Value, key: &str) -> Vec<String> {
+    let mut items = take_array(object, key);
+    let strings = items
+        .iter_mut()
+        .filter_map(|item| match item.take() {
+            Value::String(s) => Some(s),
+            other => {
+                *item = other;
+                None
+            }
+        })
+        .collect();
+    restore_array(object, key, items);
+    strings
+}
+
+/// Replaces `field` with what the extension has under `key`, if anything.
+fn take_extension<T: DeserializeOwned>(extension: &mut Map<String, Value>, key: &str, field: &mut T) -> Result<(), io::Error> {
+    if let Some(value) = extension.remove(key) {
+        *field = serde_json::from_value(value).map_err(|err| invalid(format!("{}.{}: {}", EXTENSION, key, err)))?;
+    }
+    Ok(())
+}
+
+fn import_contacts(basics: &mut Value) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    for &(field, kind) in &[("email", ContactKind::Email), ("phone", ContactKind::Phone), ("url", ContactKind::Website)] {
+        if let Some(value) = take_str(basics, field).filter(|v| !v.is_empty()) {
+            contacts.push(Contact { kind, value });
+        }
+    }
+    let mut profiles = take_array(basics, "profiles");
+    for profile in &mut profiles {
+        let network = profile.get("network").and_then(Value::as_str).unwrap_or_default().to_string();
+        let kind = match profile_kind(&network) {
+            Some(kind) => kind,
+            // Kept whole for exports, and shown as a website.
+            None => {
+                if let Some(value) = other_profile(profile) {
+                    contacts.push(Contact { kind: ContactKind::Website, value });
+                }
+                continue;
+            }
+        };
+        let value = match (take_str(profile, "url"), take_str(profile, "username")) {
+            (Some(url), _) => url,
+            (None, Some(username)) => match kind {
+                ContactKind::Twitter => format!("https://twitter.com/{}", username),
+                ContactKind::GitHub => format!("https://github.com/{}", username),
+                _ => format!("https://linkedin.com/in/{}", username),
+            },
+            (None, None) => continue,
+        };
+        take_str(profile, "network");
+        contacts.push(Contact { kind, value });
+    }
+    restore_array(basics, "profiles", profiles);
+    contacts
+}
+
+fn import_work(resume: &mut Value) -> Result<Vec<Job>, io::Error> {
+    let mut work = take_array(resume, "work");
+    let mut jobs = Vec::new();
+    for (i, entry) in work.iter_mut().enumerate() {
+        let context = format!("work[{}]", i);
+        let start = take_date(entry, "startDate", &context)?;
+        let end = take_date(entry, "endDate", &context)?;
+        let start = start.or(end).ok_or_else(|| invalid(format!("{}: missing startDate", context)))?;
+        let mut highlights: Vec<Highlight> = Vec::new();
+        if let Some(summary) = take_str(entry, "summary") {
+            highlights.push(Highlight { name: None, text: summary });
+        }
+        highlights.extend(take_strings(entry, "highlights").into_iter().map(|h| Highlight { name: None, text: h }));
+        jobs.push(Job {
+            start,
+            end,
+            position: take_str(entry, "position").unwrap_or_default(),
+            // Older versions of the schema call it "company".
+            company: take_str(entry, "name").or_else(|| take_str(entry, "company")).unwrap_or_default(),
+            highlights,
+        });
+    }
+    restore_array(resume, "work", work);
+    Ok(jobs)
+}
+
+fn import_education(resume: &mut Value) -> Result<Vec<Study>, io::Error> {
+    let mut education = take_array(resume, "education");
+    let mut studies = Vec::new();
+    for (i, entry) in education.iter_mut().enumerate() {
+        let context = format!("education[{}]", i);
+        let start = take_date(entry, "startDate", &context)?;
+        let end = take_date(entry, "endDate", &context)?;
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            (Some(date), None) | (None, Some(date)) => (date, date),
+            (None, None) => return Err(invalid(format!("{}: missing startDate and endDate", context))),
+        };
+        let study_type = take_str(entry, "studyType");
+        let area = take_str(entry, "area");
+        let institution = take_str(entry, "institution");
+        let degree = match (study_type, area) {
+            (Some(study_type), Some(area)) => format!("{} in {}", study_type, area),
+            (Some(degree), None) | (None, Some(degree)) => degree,
+            (None, None) => String::new(),
+        };
+        let degree = match institution {
+            Some(ref institution) if degree.is_empty() => institution.clone(),
+            Some(institution) => format!("{} ({})", degree, institution),
+            None => degree,
+        };
+        studies.push(Study {
+            start,
+            end,
+            degree,
+            summary: take_strings(entry, "courses").join(", "),
+            eqf_level: None,
+        });
+    }
+    restore_array(resume, "education", education);
+    Ok(studies)
+}
+
+fn import_certificates(resume: &mut Value) -> Result<Vec<Course>, io::Error> {
+    let mut certificates = take_array(resume, "certificates");
+    let mut courses = Vec::new();
+    for (i, entry) in certificates.iter_mut().enumerate() {
+        let context = format!("certificates[{}]", i);
+        let date = take_date(entry, "date", &context)?.ok_or_else(|| invalid(format!("{}: missing date", context)))?;
+        courses.push(Course {
+            title: take_str(entry, "name").unwrap_or_default(),
+            provider: take_str(entry, "issuer").unwrap_or_default(),
+            date,
+            summary: String::new(),
+        });
+    }
+    restore_array(resume, "certificates", certificates);
+    Ok(courses)
+}
+
+/// Reads a JSON Resume document into a curriculum.
+pub fn import<R: Read>(input: R) -> Result<Cv, io::Error> {
+    let mut resume: Value = serde_json::from_reader(input).map_err(|err| invalid(err.to_string()))?;
+    if !resume.is_object() {
+        return Err(invalid("a JSON Resume document must be an object".to_string()));
+    }
+    let mut extension = match resume.as_object_mut().unwrap().remove(EXTENSION) {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    resume.as_object_mut().unwrap().remove("$schema");
+
+    let mut basics = resume.get_mut("basics").map(|b| b.take()).unwrap_or(Value::Null);
+    let name = take_str(&mut basics, "name").unwrap_or_default();
+    let headline = take_str(&mut basics, "label").unwrap_or_default();
+    let photo = take_str(&mut basics, "image").filter(|p| !p.is_empty());
+    let about: Vec<String> = take_str(&mut basics, "summary")
+        .map(|s| s.split("\n\n").map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+        .unwrap_or_default();
+    // The location is kept whole for exports, unless it is what they write
+    // themselves.
+    let location = place(basics.get("location").unwrap_or(&Value::Null));
+    if basics.get("location") == Some(&json!({ "region": location })) {
+        basics.as_object_mut().unwrap().remove("location");
+    }
+    let contacts = import_contacts(&mut basics);
+    if resume.get("basics").is_some() {
+        resume["basics"] = basics;
+    }
+
+    let experience = import_work(&mut resume)?;
+    let studies = import_education(&mut resume)?;
+    let courses = import_certificates(&mut resume)?;
+
+    let mut skills = take_array(&mut resume, "skills");
+    let tools: Vec<ToolGroup> = skills
+        .iter_mut()
+        .map(|skill| ToolGroup { title: take_str(skill, "name").unwrap_or_default(), items: take_strings(skill, "keywords") })
+        .collect();
+    restore_array(&mut resume, "skills", skills);
+
+    let mut languages = take_array(&mut resume, "languages");
+    let languages_list: Vec<Language> = languages
+        .iter_mut()
+        .map(|l| Language { name: take_str(l, "language").unwrap_or_default(), level: take_str(l, "fluency").unwrap_or_default() })
+        .collect();
+    restore_array(&mut resume, "languages", languages);
+
+    let mut interests = take_array(&mut resume, "interests");
+    let interests_list: Vec<String> = interests.iter_mut().filter_map(|i| take_str(i, "name")).collect();
+    restore_array(&mut resume, "interests", interests);
+
+    let mut cv = Cv {
+        short_name: name.clone(),
+        name,
+        headline,
+        photo,
+        banner_font: None,
+        birth_date: None,
+        nationality: String::new(),
+        location,
+        about,
+        contacts,
+        languages: languages_list,
+        studies,
+        skills: Vec::new(),
+        tools,
+        experience,
+        courses,
+        looking_for: Default::default(),
+        interests: interests_list,
+        extensions: Default::default(),
+    };
+
+    // What we exported ourselves is more faithful than what the schema kept.
+    take_extension(&mut extension, "short_name", &mut cv.short_name)?;
+    take_extension(&mut extension, "banner_font", &mut cv.banner_font)?;
+    take_extension(&mut extension, "birth_date", &mut cv.birth_date)?;
+    take_extension(&mut extension, "nationality", &mut cv.nationality)?;
+    take_extension(&mut extension, "location", &mut cv.location)?;
+    take_extension(&mut extension, "about", &mut cv.about)?;
+    take_extension(&mut extension, "contacts", &mut cv.contacts)?;
+    take_extension(&mut extension, "experience", &mut cv.experience)?;
+    take_extension(&mut extension, "studies", &mut cv.studies)?;
+    take_extension(&mut extension, "skills", &mut cv.skills)?;
+    take_extension(&mut extension, "tools", &mut cv.tools)?;
+    take_extension(&mut extension, "courses", &mut cv.courses)?;
+    take_extension(&mut extension, "looking_for", &mut cv.looking_for)?;
+    if !extension.is_empty() {
+        resume[EXTENSION] = Value::Object(extension);
+    }
+
+    if !prune(&mut resume) {
+        cv.extensions.insert(LEFTOVERS.to_string(), resume);
+    }
+    Ok(cv)
+}
@@ -0,0 +1,160 @@
+//! Writes the curriculum in formats other than the terminal UI.
+
+pub mod ansi;
+pub mod ats;
+pub mod docx;
+pub mod europass;
+pub mod gemtext;
+pub mod html;
+pub mod jsonresume;
+pub mod odt;
+pub mod vcard;
+mod xml;
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use cv::Cv;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Docx,
+    Odt,
+    Ats,
+    JsonResume,
+    Europass,
+    Vcard,
+    Html,
+    Ansi,
+    Gemtext,
+}
+
+impl Format {
+    pub fn all() -> &'static [Format] {
+        const ALL: &[Format] = &[
+            Format::Docx,
+            Format::Odt,
+            Format::Ats,
+            Format::JsonResume,
+            Format::Europass,
+            Format::Vcard,
+            Format::Html,
+            Format::Ansi,
+            Format::Gemtext,
+        ];
+        ALL
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Format::Docx => "docx",
+            Format::Odt => "odt",
+            Format::Ats => "ats",
+            Format::JsonResume => "jsonresume",
+            Format::Europass => "europass",
+            Format::Vcard => "vcard",
+            Format::Html => "html",
+            Format::Ansi => "ansi",
+            Format::Gemtext => "gemtext",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        Format::all()
+            .iter()
+            .find(|f| f.name() == s)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Format::all().iter().map(|f| f.name()).collect();
+                format!("unknown format '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+/// The parts of the curriculum that can be written on their own, one per
+/// tab of the terminal UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Section {
+    Personal,
+    Skills,
+    Experience,
+    Courses,
+    LookingFor,
+}
+
+impl Section {
+    pub fn all() -> &'static [Section] {
+        const ALL: &[Section] = &[Section::Personal, Section::Skills, Section::Experience, Section::Courses, Section::LookingFor];
+        ALL
+    }
+
+    /// The name used in paths and anchors.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Section::Personal => "personal",
+            Section::Skills => "skills",
+            Section::Experience => "experience",
+            Section::Courses => "courses",
+            Section::LookingFor => "looking-for",
+        }
+    }
+
+    /// The title of its tab.
+    pub fn title(&self) -> &'static str {
+        match *self {
+            Section::Personal => "Personal",
+            Section::Skills => "Skills",
+            Section::Experience => "Experience",
+            Section::Courses => "Courses",
+            Section::LookingFor => "Looking For",
+        }
+    }
+}
+
+impl FromStr for Section {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Section, String> {
+        Section::all()
+            .iter()
+            .find(|section| section.name() == s)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Section::all().iter().map(|section| section.name()).collect();
+                format!("unknown section '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+pub fn export<W: Write>(cv: &Cv, format: Format, out: &mut W) -> Result<(), io::Error> {
+    match format {
+        Format::Docx => docx::write(cv, out),
+        Format::Odt => odt::write(cv, out),
+        Format::Ats => ats::write(cv, out),
+        Format::JsonResume => jsonresume::write(cv, out),
+        Format::Europass => europass::write(cv, out),
+        Format::Vcard => vcard::write(cv, out),
+        Format::Html => html::write(cv, Section::all(), out),
+        Format::Ansi => ansi::write(cv, Section::all(), ansi::WIDTH, out),
+        Format::Gemtext => gemtext::write(cv, Section::all(), &[], out),
+    }
+}
+
+/// Reads a curriculum written in one of the formats that can be imported.
+pub fn import<R: Read>(format: Format, input: R) -> Result<Cv, io::Error> {
+    match format {
+        Format::JsonResume => jsonresume::import(input),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' cannot be imported", format))),
+    }
+}
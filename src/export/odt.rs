@@ -0,0 +1,313 @@
+//! OpenDocument text (`.odt`) export, required by some public-sector
+//! employers.
+//!
+//! Every paragraph and span uses a named style after the `theme::Role` it
+//! plays in the terminal UI, so the whole document can be restyled from the
+//! Styles panel of LibreOffice.
+
+use std::io::{self, Cursor, Write};
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use cv::{self, Cv};
+use theme::{self, Role};
+use tui::style::Modifier;
+use super::xml::{escape, DECLARATION};
+
+const NAMESPACES: &str = "xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\" \
+     xmlns:style=\"urn:oasis:names:tc:opendocument:xmlns:style:1.0\" \
+     xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\" \
+     xmlns:table=\"urn:oasis:names:tc:opendocument:xmlns:table:1.0\" \
+     xmlns:fo=\"urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0\" \
+     xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+     xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+     xmlns:meta=\"urn:oasis:names:tc:opendocument:xmlns:meta:1.0\" \
+     office:version=\"1.2\"";
+
+const MANIFEST: &str = r#"<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+<manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.text"/>
+<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+<manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+<manifest:file-entry manifest:full-path="meta.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>"#;
+
+/// Roles that style whole paragraphs, with the size of their font.
+const PARAGRAPH_ROLES: &[(Role, &str)] = &[(Role::Section, "16pt"), (Role::Heading, "12pt"), (Role::Body, "11pt")];
+
+/// Roles that style spans inside a paragraph.
+const TEXT_ROLES: &[Role] = &[Role::Label, Role::Emphasis];
+
+fn text_properties(role: Role, size: Option<&str>) -> String {
+    let style = role.style();
+    let mut properties = String::from("<style:text-properties");
+    if let Some(size) = size {
+        properties.push_str(&format!(" fo:font-size=\"{}\"", size));
+    }
+    match style.modifier {
+        Modifier::Bold => properties.push_str(" fo:font-weight=\"bold\""),
+        Modifier::Italic => properties.push_str(" fo:font-style=\"italic\""),
+        _ => {}
+    }
+    if let Some(color) = theme::hex(style.fg) {
+        properties.push_str(&format!(" fo:color=\"{}\"", color));
+    }
+    properties.push_str("/>");
+    properties
+}
+
+fn styles_xml() -> String {
+    let mut styles = format!(
+        "{}<office:document-styles {}>\n<office:styles>\n\
+         <style:default-style style:family=\"paragraph\"><style:text-properties style:font-name=\"Liberation Sans\" fo:font-size=\"11pt\" fo:language=\"es\" fo:country=\"ES\"/></style:default-style>\n\
+         <style:style style:name=\"Standard\" style:family=\"paragraph\" style:class=\"text\"/>\n\
+         <style:style style:name=\"Title\" style:family=\"paragraph\" style:parent-style-name=\"Standard\" style:next-style-name=\"Body\" style:class=\"chapter\">\
+         <style:paragraph-properties fo:margin-bottom=\"0.1cm\"/>{}</style:style>\n\
+         <style:style style:name=\"Subtitle\" style:family=\"paragraph\" style:parent-style-name=\"Standard\" style:next-style-name=\"Body\" style:class=\"chapter\">\
+         <style:paragraph-properties fo:margin-bottom=\"0.4cm\"/><style:text-properties fo:font-size=\"14pt\" fo:color=\"#595959\"/></style:style>\n",
+        DECLARATION,
+        NAMESPACES,
+        text_properties(Role::Section, Some("26pt"))
+    );
+    for &(role, size) in PARAGRAPH_ROLES {
+        let (outline, spacing) = match role {
+            Role::Section => (" style:default-outline-level=\"1\"", "fo:margin-top=\"0.5cm\" fo:margin-bottom=\"0.2cm\" fo:keep-with-next=\"always\""),
+            Role::Heading => (" style:default-outline-level=\"2\"", "fo:margin-top=\"0.3cm\" fo:margin-bottom=\"0.1cm\" fo:keep-with-next=\"always\""),
+            _ => ("", "fo:margin-top=\"0cm\" fo:margin-bottom=\"0.2cm\""),
+        };
+        styles.push_str(&format!(
+            "<style:style style:name=\"{name}\" style:display-name=\"{name}\" style:family=\"paragraph\" \
+             style:parent-style-name=\"Standard\" style:next-style-name=\"Body\"{outline} style:class=\"text\">\
+             <style:paragraph-properties {spacing}/>{text}</style:style>\n",
+            name = role.name(),
+            outline = outline,
+            spacing = spacing,
+            text = text_properties(role, Some(size))
+        ));
+    }
+    for &role in TEXT_ROLES {
+        styles.push_str(&format!(
+            "<style:style style:name=\"{name}\" style:display-name=\"{name}\" style:family=\"text\">{text}</style:style>\n",
+            name = role.name(),
+            text = text_properties(role, None)
+        ));
+    }
+    styles.push_str(
+        "<style:style style:name=\"Internet_20_link\" style:display-name=\"Internet link\" style:family=\"text\">\
+         <style:text-properties fo:color=\"#000080\" style:text-underline-style=\"solid\" style:text-underline-width=\"auto\" style:text-underline-color=\"font-color\"/></style:style>\n\
+         <text:list-style style:name=\"Bullets\"><text:list-level-style-bullet text:level=\"1\" text:bullet-char=\"\u{2022}\">\
+         <style:list-level-properties text:list-level-position-and-space-mode=\"label-alignment\">\
+         <style:list-level-label-alignment text:label-followed-by=\"listtab\" text:list-tab-stop-position=\"0.635cm\" fo:text-indent=\"-0.635cm\" fo:margin-left=\"0.635cm\"/>\
+         </style:list-level-properties></text:list-level-style-bullet></text:list-style>\n\
+         </office:styles>\n</office:document-styles>",
+    );
+    styles
+}
+
+fn meta_xml(cv: &Cv) -> String {
+    format!(
+        "{}<office:document-meta {}>\n<office:meta>\n<meta:generator>rust-cv</meta:generator>\n\
+         <dc:title>{} - Curriculum Vitae</dc:title>\n<meta:initial-creator>{}</meta:initial-creator>\n\
+         </office:meta>\n</office:document-meta>",
+        DECLARATION,
+        NAMESPACES,
+        escape(&cv.name),
+        escape(&cv.name)
+    )
+}
+
+fn span(role: Role, text: &str) -> String {
+    format!("<text:span text:style-name=\"{}\">{}</text:span>", role.name(), escape(text))
+}
+
+/// Inline content for free text, honouring its `**` markers.
+fn inline(text: &str) -> String {
+    cv::spans(text)
+        .iter()
+        .map(|s| if s.bold { span(Role::Emphasis, s.text) } else { escape(s.text) })
+        .collect()
+}
+
+fn labelled(label: &str, value: &str) -> String {
+    format!("{} {}", span(Role::Label, &format!("{}:", label)), value)
+}
+
+fn link(url: &str, text: &str) -> String {
+    format!(
+        "<text:a xlink:type=\"simple\" xlink:href=\"{}\" text:style-name=\"Internet_20_link\">{}</text:a>",
+        escape(url),
+        escape(text)
+    )
+}
+
+struct Body {
+    xml: String,
+    tables: usize,
+}
+
+impl Body {
+    fn paragraph(&mut self, style: &str, content: &str) {
+        self.xml.push_str(&format!("<text:p text:style-name=\"{}\">{}</text:p>\n", style, content));
+    }
+
+    fn heading(&mut self, role: Role, text: &str) {
+        let level = if role == Role::Section { 1 } else { 2 };
+        self.xml.push_str(&format!(
+            "<text:h text:style-name=\"{}\" text:outline-level=\"{}\">{}</text:h>\n",
+            role.name(),
+            level,
+            escape(text)
+        ));
+    }
+
+    fn list(&mut self, items: &[String]) {
+        if items.is_empty() {
+            return;
+        }
+        self.xml.push_str("<text:list text:style-name=\"Bullets\">\n");
+        for item in items {
+            self.xml.push_str(&format!(
+                "<text:list-item><text:p text:style-name=\"{}\">{}</text:p></text:list-item>\n",
+                Role::Body.name(),
+                item
+            ));
+        }
+        self.xml.push_str("</text:list>\n");
+    }
+
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]) {
+        self.tables += 1;
+        self.xml.push_str(&format!(
+            "<table:table table:name=\"Table{}\"><table:table-column table:number-columns-repeated=\"{}\"/>\n<table:table-header-rows><table:table-row>",
+            self.tables,
+            header.len()
+        ));
+        for cell in header {
+            self.xml.push_str(&format!(
+                "<table:table-cell office:value-type=\"string\"><text:p text:style-name=\"{}\">{}</text:p></table:table-cell>",
+                Role::Heading.name(),
+                escape(cell)
+            ));
+        }
+        self.xml.push_str("</table:table-row></table:table-header-rows>\n");
+        for row in rows {
+            self.xml.push_str("<table:table-row>");
+            for cell in row {
+                self.xml.push_str(&format!(
+                    "<table:table-cell office:value-type=\"string\"><text:p text:style-name=\"{}\">{}</text:p></table:table-cell>",
+                    Role::Body.name(),
+                    inline(cell)
+                ));
+            }
+            self.xml.push_str("</table:table-row>\n");
+        }
+        self.xml.push_str("</table:table>\n");
+    }
+}
+
+fn content_xml(cv: &Cv) -> String {
+    let body_style = Role::Body.name();
+    let mut body = Body { xml: String::new(), tables: 0 };
+
+    body.paragraph("Title", &escape(&cv.name));
+    body.paragraph("Subtitle", &escape(&cv.headline));
+
+    body.heading(Role::Section, "Information");
+    for (label, value) in cv.information() {
+        body.paragraph(body_style, &labelled(label, &escape(&value)));
+    }
+
+    body.heading(Role::Section, "Contact");
+    for contact in &cv.contacts {
+        body.paragraph(body_style, &labelled(contact.kind.label(), &link(&contact.url(), &contact.value)));
+    }
+
+    body.heading(Role::Section, "About me");
+    for paragraph in &cv.about {
+        body.paragraph(body_style, &inline(paragraph));
+    }
+
+    body.heading(Role::Section, "Experience");
+    for job in &cv.experience {
+        body.heading(Role::Heading, &format!("{} at {}", job.position, job.company));
+        body.paragraph(body_style, &escape(&cv::period(&job.start, &job.end)));
+        let highlights: Vec<String> = job
+            .highlights
+            .iter()
+            .map(|h| match h.name {
+                Some(ref name) => labelled(name, &inline(&h.text)),
+                None => inline(&h.text),
+            })
+            .collect();
+        body.list(&highlights);
+    }
+
+    body.heading(Role::Section, "Skills");
+    for group in &cv.skills {
+        body.heading(Role::Heading, &group.title);
+        let rows: Vec<Vec<String>> = group
+            .skills
+            .iter()
+            .map(|skill| vec![skill.name.clone(), format!("{} / 100", skill.level)])
+            .collect();
+        body.table(&["Skill", "Level"], &rows);
+    }
+    if !cv.tools.is_empty() {
+        body.heading(Role::Heading, "Others");
+        let rows: Vec<Vec<String>> = cv
+            .tools
+            .iter()
+            .map(|group| vec![group.title.clone(), group.items.join(", ")])
+            .collect();
+        body.table(&["Area", "Tools"], &rows);
+    }
+
+    body.heading(Role::Section, "Languages");
+    for language in &cv.languages {
+        body.paragraph(body_style, &labelled(&language.name, &escape(&language.level)));
+    }
+
+    body.heading(Role::Section, "Studies");
+    for study in &cv.studies {
+        body.heading(Role::Heading, &format!("{} - {}: {}", study.start, study.end, study.degree));
+        body.paragraph(body_style, &inline(&study.summary));
+    }
+
+    body.heading(Role::Section, "Courses");
+    for course in &cv.courses {
+        body.heading(Role::Heading, &course.heading());
+        body.paragraph(body_style, &inline(&course.summary));
+    }
+
+    body.heading(Role::Section, "What I am looking for");
+    body.paragraph(body_style, &span(Role::Emphasis, &cv.looking_for.headline));
+    body.paragraph(body_style, &inline(&cv.looking_for.intro));
+    let roles: Vec<String> = cv.looking_for.roles.iter().map(|r| inline(r)).collect();
+    body.list(&roles);
+
+    format!(
+        "{}<office:document-content {}>\n<office:body>\n<office:text>\n{}</office:text>\n</office:body>\n</office:document-content>",
+        DECLARATION, NAMESPACES, body.xml
+    )
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // The mimetype has to be the first entry and must not be compressed so
+    // the format can be detected from the first bytes of the file.
+    zip.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored))?;
+    zip.write_all(b"application/vnd.oasis.opendocument.text")?;
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let parts = [
+        ("META-INF/manifest.xml", format!("{}{}", DECLARATION, MANIFEST)),
+        ("meta.xml", meta_xml(cv)),
+        ("styles.xml", styles_xml()),
+        ("content.xml", content_xml(cv)),
+    ];
+    for &(name, ref content) in &parts {
+        zip.start_file(name, options)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    let archive = zip.finish()?;
+    out.write_all(&archive.into_inner())
+}
@@ -0,0 +1,131 @@
+//! vCard 4.0 export of the contact details (RFC 6350), the same card the
+//! Personal tab shows as a QR code.
+
+use std::io::{self, Write};
+
+use cv::{ContactKind, Cv};
+
+/// Content lines longer than this many octets are folded.
+const LINE_LIMIT: usize = 75;
+
+/// Escapes the characters with a meaning in property values.
+fn text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Ends a content line with CRLF, folding it so no line is longer than
+/// `LINE_LIMIT` octets without splitting a character.
+fn push_line(card: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LIMIT {
+            card.push_str("\r\n ");
+            length = 1;
+        }
+        card.push(c);
+        length += c.len_utf8();
+    }
+    card.push_str("\r\n");
+}
+
+/// The vCard of the curriculum, as one string with CRLF line endings.
+pub fn card(cv: &Cv) -> String {
+    let mut names = cv.name.splitn(2, ' ');
+    let given = names.next().unwrap_or("");
+    let family = names.next().unwrap_or("");
+
+    let mut card = String::new();
+    push_line(&mut card, "BEGIN:VCARD");
+    push_line(&mut card, "VERSION:4.0");
+    push_line(&mut card, &format!("FN:{}", text(&cv.name)));
+    push_line(&mut card, &format!("N:{};{};;;", text(family), text(given)));
+    if !cv.headline.is_empty() {
+        push_line(&mut card, &format!("TITLE:{}", text(&cv.headline)));
+    }
+    if let Some(ref date) = cv.birth_date {
+        let bday = match (date.month, date.day) {
+            (Some(month), Some(day)) => format!("{:04}{:02}{:02}", date.year, month, day),
+            (Some(month), None) => format!("{:04}-{:02}", date.year, month),
+            _ => format!("{:04}", date.year),
+        };
+        push_line(&mut card, &format!("BDAY:{}", bday));
+    }
+    if !cv.location.is_empty() {
+        // "City, Country" fills the locality and the country, anything else
+        // is taken as the country.
+        let (locality, country) = match cv.location.rfind(',') {
+            Some(i) => (cv.location[..i].trim(), cv.location[i + 1..].trim()),
+            None => ("", cv.location.trim()),
+        };
+        push_line(&mut card, &format!("ADR:;;;{};;;{}", text(locality), text(country)));
+    }
+    for contact in &cv.contacts {
+        let line = match contact.kind {
+            ContactKind::Email => format!("EMAIL:{}", text(&contact.value)),
+            ContactKind::Phone => format!("TEL;VALUE=uri;TYPE=\"voice,cell\":{}", contact.url()),
+            ContactKind::Website => format!("URL:{}", contact.url()),
+            kind => format!("URL;TYPE={}:{}", kind.label().to_lowercase(), contact.url()),
+        };
+        push_line(&mut card, &line);
+    }
+    push_line(&mut card, "END:VCARD");
+    card
+}
+
+pub fn write<W: Write>(cv: &Cv, out: &mut W) -> Result<(), io::Error> {
+    out.write_all(card(cv).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{EcLevel, QrCode};
+
+    #[test]
+    fn escaping() {
+        assert_eq!(text("a,b;c\\d\r\ne"), r"a\,b\;c\\d\ne");
+    }
+
+    #[test]
+    fn folding() {
+        let line = format!("NOTE:{}", "añ€".repeat(40));
+        let mut card = String::new();
+        push_line(&mut card, &line);
+        assert!(card.ends_with("\r\n"));
+        for folded in card.trim_end_matches("\r\n").split("\r\n") {
+            assert!(folded.len() <= LINE_LIMIT, "{} octets in {:?}", folded.len(), folded);
+        }
+        assert_eq!(card.trim_end_matches("\r\n").replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn qr_payload() {
+        let card = card(&Cv::default());
+        assert!(card.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+        assert!(card.ends_with("END:VCARD\r\n"));
+        for line in &[
+            "FN:Oscar Forner Martinez",
+            "N:Forner Martinez;Oscar;;;",
+            "BDAY:19880324",
+            "ADR:;;;;;;Spain",
+            "EMAIL:oscar.forner.martinez@gmail.com",
+            "URL;TYPE=github:https://github.com/maitesin",
+        ] {
+            assert!(card.split("\r\n").any(|l| l == *line), "no {} in:\n{}", line, card);
+        }
+        assert!(card.contains("\r\nTEL;VALUE=uri;TYPE=\"voice,cell\":tel:"), "no phone in:\n{}", card);
+        // The Personal tab draws the card at the lowest correction level.
+        assert!(QrCode::with_error_correction_level(&card, EcLevel::L).is_ok());
+    }
+}
@@ -0,0 +1,121 @@
+//! FIGlet fonts for the name banner of the Welcome tab.
+//!
+//! The fonts in `fonts/` are bundled in the binary. Letters are put side by
+//! side at full width; the fonts are made for it, so there is no smushing.
+
+use std::collections::HashMap;
+
+/// The bundled fonts, from the largest to the smallest.
+const FONTS: &[(&str, &str)] = &[
+    ("big", include_str!("../fonts/big.flf")),
+    ("block", include_str!("../fonts/block.flf")),
+    ("small", include_str!("../fonts/small.flf")),
+    ("mini", include_str!("../fonts/mini.flf")),
+];
+
+/// The characters every font has after the printable ASCII ones.
+const GERMAN: [char; 7] = ['Ä', 'Ö', 'Ü', 'ä', 'ö', 'ü', 'ß'];
+
+#[derive(Clone)]
+pub struct Font {
+    height: usize,
+    glyphs: HashMap<char, Vec<String>>,
+}
+
+impl Font {
+    /// Reads a font in the FIGlet 2 format.
+    fn parse(name: &'static str, source: &str) -> Result<Font, String> {
+        let error = |message: &str| format!("font '{}': {}", name, message);
+        let mut lines = source.lines();
+        let header: Vec<&str> = lines.next().ok_or_else(|| error("empty"))?.split_whitespace().collect();
+        if header.len() < 6 || !header[0].starts_with("flf2a") {
+            return Err(error("not a FIGlet font"));
+        }
+        let hardblank = header[0][5..].chars().next().ok_or_else(|| error("no hardblank"))?;
+        let number = |i: usize| header[i].parse::<usize>().map_err(|_| error("bad header"));
+        let height = number(1)?;
+        for _ in 0..number(5)? {
+            lines.next();
+        }
+
+        let glyph = |lines: &mut dyn Iterator<Item = &str>| -> Result<Vec<String>, String> {
+            (0..height)
+                .map(|_| {
+                    let line = lines.next().ok_or_else(|| error("truncated"))?;
+                    let endmark = line.chars().last().unwrap_or('@');
+                    Ok(line.trim_end_matches(endmark).replace(hardblank, " "))
+                })
+                .collect()
+        };
+        let mut glyphs = HashMap::new();
+        for code in 32u8..127 {
+            glyphs.insert(code as char, glyph(&mut lines)?);
+        }
+        for &c in GERMAN.iter() {
+            glyphs.insert(c, glyph(&mut lines)?);
+        }
+        // Code tagged characters: the code, in decimal, octal or hex, then
+        // the glyph.
+        while let Some(tag) = lines.next() {
+            let code = match tag.split_whitespace().next() {
+                Some(code) => code,
+                None => continue,
+            };
+            let value = if code.starts_with("0x") || code.starts_with("0X") {
+                u32::from_str_radix(&code[2..], 16)
+            } else if code.starts_with('0') && code.len() > 1 {
+                u32::from_str_radix(&code[1..], 8)
+            } else {
+                code.parse()
+            };
+            let c = value.ok().and_then(::std::char::from_u32).ok_or_else(|| error("bad character code"))?;
+            glyphs.insert(c, glyph(&mut lines)?);
+        }
+        Ok(Font { height, glyphs })
+    }
+
+    /// The lines of `text` in this font, or `None` when the font does not
+    /// have one of its characters.
+    pub fn render(&self, text: &str) -> Option<Vec<String>> {
+        let mut lines = vec![String::new(); self.height];
+        for c in text.chars() {
+            let glyph = self.glyphs.get(&c)?;
+            for (line, part) in lines.iter_mut().zip(glyph) {
+                line.push_str(part);
+            }
+        }
+        // The space after the last letter is part of its glyph.
+        let trailing = lines.iter().map(|l| l.len() - l.trim_end().len()).min().unwrap_or(0);
+        for line in &mut lines {
+            let width = line.len() - trailing;
+            line.truncate(width);
+        }
+        Some(lines)
+    }
+}
+
+fn names() -> Vec<&'static str> {
+    FONTS.iter().map(|&(name, _)| name).collect()
+}
+
+/// The fonts to try for the banner: `preferred` and the smaller ones after
+/// it, or all of them.
+pub fn fonts(preferred: Option<&str>) -> Result<Vec<Font>, String> {
+    let start = match preferred {
+        Some(name) => FONTS.iter().position(|&(n, _)| n == name).ok_or_else(|| {
+            format!("unknown banner font '{}', expected one of: {}", name, names().join(", "))
+        })?,
+        None => 0,
+    };
+    FONTS[start..].iter().map(|&(name, source)| Font::parse(name, source)).collect()
+}
+
+/// The banner of `text` in the largest of `fonts` that fits in `width`
+/// columns and `height` lines.
+pub fn banner(fonts: &[Font], text: &str, width: u16, height: u16) -> Option<Vec<String>> {
+    fonts
+        .iter()
+        .filter(|font| font.height <= height as usize)
+        .filter_map(|font| font.render(text))
+        .find(|lines| lines.iter().all(|l| l.chars().count() <= width as usize))
+}
@@ -0,0 +1,290 @@
+//! Pictures in the terminal: the photo of the Personal tab.
+//!
+//! Terminals with the kitty graphics protocol or sixel get the picture
+//! itself, written after tui has drawn the frame over cells left blank for
+//! it. Any other terminal gets half blocks in true colour, which are drawn
+//! like any other widget (see `widgets::Picture`).
+
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use image::{self, imageops, RgbaImage};
+use tui::layout::Rect;
+
+use base64;
+use term::Term;
+
+/// Pictures are never shown bigger than this, in pixels, so there is no
+/// point in keeping more.
+const MAX_SIDE: u32 = 512;
+
+/// The size of a cell in pixels when the terminal does not tell.
+const CELL: (u32, u32) = (10, 20);
+
+/// The id of the photo for the kitty protocol, there is only one.
+const KITTY_ID: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+    /// Unicode half blocks, two pixels per cell.
+    HalfBlocks,
+}
+
+/// How to show pictures, chosen with `--graphics`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsMode {
+    /// The best protocol the terminal is known to support.
+    Auto,
+    Kitty,
+    Sixel,
+    Blocks,
+    None,
+}
+
+impl GraphicsMode {
+    pub fn protocol(&self, term: &Term) -> Option<Protocol> {
+        match *self {
+            GraphicsMode::Auto => Some(detect(term)),
+            GraphicsMode::Kitty => Some(Protocol::Kitty),
+            GraphicsMode::Sixel => Some(Protocol::Sixel),
+            GraphicsMode::Blocks => Some(Protocol::HalfBlocks),
+            GraphicsMode::None => None,
+        }
+    }
+}
+
+impl FromStr for GraphicsMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GraphicsMode, String> {
+        match s {
+            "auto" => Ok(GraphicsMode::Auto),
+            "kitty" => Ok(GraphicsMode::Kitty),
+            "sixel" => Ok(GraphicsMode::Sixel),
+            "blocks" => Ok(GraphicsMode::Blocks),
+            "none" => Ok(GraphicsMode::None),
+            _ => Err(format!("unknown graphics mode '{}', expected one of: auto, kitty, sixel, blocks, none", s)),
+        }
+    }
+}
+
+/// The protocol of the terminal, from what it says about itself. tmux and
+/// screen let neither protocol through without help, so they get blocks.
+fn detect(term: &Term) -> Protocol {
+    let name = term.var("TERM");
+    if term.has("TMUX") || name.starts_with("screen") || name.starts_with("tmux") {
+        return Protocol::HalfBlocks;
+    }
+    let program = term.var("TERM_PROGRAM");
+    if term.has("KITTY_WINDOW_ID") || name.contains("kitty") || program == "WezTerm" || program == "ghostty" {
+        return Protocol::Kitty;
+    }
+    if ["sixel", "foot", "mlterm", "contour", "yaft"].iter().any(|t| name.contains(t)) || program == "iTerm.app" || program == "mintty" {
+        return Protocol::Sixel;
+    }
+    Protocol::HalfBlocks
+}
+
+/// The size of a cell in pixels, for a terminal of `columns` by `rows`
+/// that is `pixels` big, which is zero when it does not tell.
+pub fn cell_size(columns: u16, rows: u16, pixels: (u16, u16)) -> (u32, u32) {
+    let (width, height) = pixels;
+    if columns > 0 && rows > 0 && width > 0 && height > 0 {
+        (width as u32 / columns as u32, height as u32 / rows as u32)
+    } else {
+        CELL
+    }
+}
+
+/// Reads a picture, at most as big as it is ever shown.
+pub fn load(path: &str) -> Result<Arc<RgbaImage>, io::Error> {
+    let image = image::open(path)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))?
+        .thumbnail(MAX_SIDE, MAX_SIDE)
+        .to_rgba8();
+    Ok(Arc::new(image))
+}
+
+/// The largest part of `area` that shows a picture of `width` by `height`
+/// pixels without distorting it, centered, for cells of `cell` pixels.
+pub fn fit(width: u32, height: u32, area: &Rect, cell: (u32, u32)) -> Rect {
+    if width == 0 || height == 0 || area.width == 0 || area.height == 0 {
+        return Rect::new(area.x, area.y, 0, 0);
+    }
+    let (area_width, area_height) = (area.width as u32 * cell.0, area.height as u32 * cell.1);
+    let (columns, rows) = if width * area_height > height * area_width {
+        (area.width as u32, (area_width * height / width / cell.1).max(1))
+    } else {
+        ((area_height * width / height / cell.0).max(1), area.height as u32)
+    };
+    let (columns, rows) = (columns.min(area.width as u32) as u16, rows.min(area.height as u32) as u16);
+    Rect::new(area.x + (area.width - columns) / 2, area.y + (area.height - rows) / 2, columns, rows)
+}
+
+/// A picture on the screen of a session. The picture itself is shared by
+/// every session.
+pub struct Photo {
+    image: Arc<RgbaImage>,
+    protocol: Protocol,
+    /// Where the picture is on screen, for the protocols that put it there
+    /// outside of tui.
+    placed: Option<Rect>,
+}
+
+impl Photo {
+    pub fn new(image: Arc<RgbaImage>, protocol: Protocol) -> Photo {
+        Photo { image, protocol, placed: None }
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Whether tui draws the picture, as half blocks.
+    pub fn in_cells(&self) -> bool {
+        self.protocol == Protocol::HalfBlocks
+    }
+
+    pub fn placed(&self) -> Option<Rect> {
+        self.placed
+    }
+
+    /// Takes the picture off the screen. Sixel pictures are made of the
+    /// cells themselves, so the whole screen has to be drawn again when this
+    /// returns true.
+    pub fn remove<W: Write>(&mut self, out: &mut W) -> Result<bool, io::Error> {
+        if self.placed.take().is_none() {
+            return Ok(false);
+        }
+        match self.protocol {
+            Protocol::Kitty => {
+                write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_ID)?;
+                out.flush()?;
+                Ok(false)
+            }
+            Protocol::Sixel => Ok(true),
+            Protocol::HalfBlocks => Ok(false),
+        }
+    }
+
+    /// Puts the picture in `area`, as big as it fits in cells of `cell`
+    /// pixels.
+    pub fn place<W: Write>(&mut self, out: &mut W, area: Rect, cell: (u32, u32)) -> Result<(), io::Error> {
+        let target = fit(self.image.width(), self.image.height(), &area, cell);
+        if self.protocol == Protocol::HalfBlocks {
+            return Ok(());
+        }
+        self.placed = Some(area);
+        if target.width == 0 || target.height == 0 {
+            return Ok(());
+        }
+        let image = imageops::resize(
+            &*self.image,
+            target.width as u32 * cell.0,
+            target.height as u32 * cell.1,
+            imageops::FilterType::Triangle,
+        );
+        write!(out, "\x1b7\x1b[{};{}H", target.y + 1, target.x + 1)?;
+        match self.protocol {
+            Protocol::Kitty => kitty(out, &image, target)?,
+            Protocol::Sixel => sixel(out, &image)?,
+            Protocol::HalfBlocks => {}
+        }
+        write!(out, "\x1b8")?;
+        out.flush()
+    }
+}
+
+/// Sends the picture as raw RGBA in chunks, as the protocol asks, and shows
+/// it over `target` without moving the cursor.
+fn kitty<W: Write>(out: &mut W, image: &RgbaImage, target: Rect) -> Result<(), io::Error> {
+    let data = base64::encode(image.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={},C=1,q=2,m={};",
+                image.width(),
+                image.height(),
+                target.width,
+                target.height,
+                KITTY_ID,
+                more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// The colour of a pixel in a 6x6x6 cube, or `None` when it is
+/// transparent.
+fn palette_index(pixel: &image::Rgba<u8>) -> Option<usize> {
+    if pixel[3] < 128 {
+        return None;
+    }
+    let level = |c: u8| (c as usize * 5 + 127) / 255;
+    Some(level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]))
+}
+
+/// Writes the picture as sixel, with the colours of a 6x6x6 cube and
+/// transparent pixels left as they are.
+fn sixel<W: Write>(out: &mut W, image: &RgbaImage) -> Result<(), io::Error> {
+    let (width, height) = image.dimensions();
+    let mut data = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let (r, g, b) = (i / 36, i / 6 % 6, i % 6);
+        data.push_str(&format!("#{};2;{};{};{}", i, r * 20, g * 20, b * 20));
+    }
+    let indices: Vec<Option<usize>> = image.pixels().map(palette_index).collect();
+    for band in 0..(height + 5) / 6 {
+        let rows = band * 6..(band * 6 + 6).min(height);
+        let mut colours: Vec<usize> = rows
+            .clone()
+            .flat_map(|y| indices[(y * width) as usize..((y + 1) * width) as usize].iter().filter_map(|c| *c))
+            .collect();
+        colours.sort();
+        colours.dedup();
+        for colour in colours {
+            data.push_str(&format!("#{}", colour));
+            let mut run = (0u8, 0usize);
+            for x in 0..width {
+                let mut bits = 0u8;
+                for y in rows.clone() {
+                    if indices[(y * width + x) as usize] == Some(colour) {
+                        bits |= 1 << (y - band * 6);
+                    }
+                }
+                let symbol = 63 + bits;
+                if run.1 > 0 && run.0 != symbol {
+                    push_run(&mut data, run);
+                    run.1 = 0;
+                }
+                run = (symbol, run.1 + 1);
+            }
+            push_run(&mut data, run);
+            data.push('$');
+        }
+        data.push('-');
+    }
+    data.push_str("\x1b\\");
+    out.write_all(data.as_bytes())
+}
+
+fn push_run(data: &mut String, (symbol, count): (u8, usize)) {
+    if count > 3 {
+        data.push_str(&format!("!{}{}", count, symbol as char));
+    } else {
+        for _ in 0..count {
+            data.push(symbol as char);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! The guestbook visitors of the terminal UI sign, kept in `guestbook.log`
+//! in the log directory of the servers. Entries are JSON objects a line
+//! and are never rewritten: hiding one appends a line that says so, and
+//! entries are known by their place in the file.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{self, Value};
+
+use sanitize;
+
+const FILE: &str = "guestbook.log";
+/// The longest name, contact and message, in characters.
+pub const NAME: usize = 40;
+pub const CONTACT: usize = 80;
+pub const MESSAGE: usize = 500;
+/// How long a network waits to sign again, in seconds.
+const INTERVAL: u64 = 10 * 60;
+/// How many entries the whole guestbook takes in an hour.
+const PER_HOUR: usize = 30;
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+pub struct Entry {
+    /// Its place in the guestbook, from 1.
+    pub number: usize,
+    pub time: u64,
+    pub name: String,
+    pub contact: String,
+    pub message: String,
+    /// The network it was signed from, as the visits log has it.
+    network: String,
+    pub hidden: bool,
+}
+
+/// How long ago `time` was, roughly.
+pub fn ago(time: u64) -> String {
+    let seconds = now().saturating_sub(time);
+    let (count, unit) = match seconds {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 24 * 3600 => (s / 3600, "hour"),
+        s => (s / (24 * 3600), "day"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// Every entry of the guestbook of `dir`, hidden ones too, from the oldest.
+/// There are none before the first is signed.
+pub fn read(dir: &str) -> Result<Vec<Entry>, io::Error> {
+    let path = Path::new(dir).join(FILE);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(io::Error::new(err.kind(), format!("{}: {}", path.display(), err))),
+    };
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line: Value = match serde_json::from_str(&line?) {
+            Ok(line) => line,
+            Err(_) => continue,
+        };
+        if let Some(number) = line["hide"].as_u64() {
+            if let Some(entry) = entries.get_mut((number as usize).wrapping_sub(1)) {
+                entry.hidden = true;
+            }
+            continue;
+        }
+        let text = |name: &str| sanitize::clean(line[name].as_str().unwrap_or(""));
+        entries.push(Entry {
+            number: entries.len() + 1,
+            time: line["time"].as_u64().unwrap_or(0),
+            name: text("name"),
+            contact: text("contact"),
+            message: text("message"),
+            network: line["network"].as_str().unwrap_or("").to_string(),
+            hidden: false,
+        });
+    }
+    Ok(entries)
+}
+
+/// Appends `line` to the guestbook of `dir`, which only its owner can
+/// read and change.
+fn append(dir: &str, line: &Value) -> Result<(), io::Error> {
+    let path = Path::new(dir).join(FILE);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    // At once, so processes signing together do not mix their lines.
+    file.write_all(format!("{}\n", line).as_bytes())
+}
+
+/// A field as it is kept: on one line, without what terminals take as
+/// commands.
+fn field(text: &str) -> String {
+    sanitize::clean(&text.replace(&['\n', '\r', '\t'][..], " ")).trim().to_string()
+}
+
+/// Signs the guestbook of `dir` for a visitor from `network`, or says why
+/// it cannot be signed.
+pub fn sign(dir: &str, network: &str, name: &str, contact: &str, message: &str) -> Result<(), String> {
+    let (name, contact, message) = (field(name), field(contact), field(message));
+    if name.is_empty() || message.is_empty() {
+        return Err("A name and a message are needed".to_string());
+    }
+    if name.chars().count() > NAME || contact.chars().count() > CONTACT || message.chars().count() > MESSAGE {
+        return Err("That is longer than the guestbook takes".to_string());
+    }
+    let entries = read(dir).map_err(|err| format!("The guestbook cannot be read: {}", err))?;
+    let now = now();
+    let recent: Vec<&Entry> = entries.iter().filter(|entry| entry.time + 3600 > now).collect();
+    // The local UI has no network, and is not held back.
+    if !network.is_empty() && recent.iter().any(|entry| entry.network == network && entry.time + INTERVAL > now) {
+        return Err("You signed a few minutes ago, try again later".to_string());
+    }
+    if recent.len() >= PER_HOUR {
+        return Err("The guestbook is busy, try again later".to_string());
+    }
+    let line = json!({ "time": now, "name": name, "contact": contact, "message": message, "network": network });
+    append(dir, &line).map_err(|err| format!("The guestbook cannot be signed: {}", err))
+}
+
+/// Hides entry `number` of the guestbook of `dir` from visitors.
+pub fn hide(dir: &str, number: usize) -> Result<(), io::Error> {
+    if number == 0 || number > read(dir)?.len() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("there is no entry {} in the guestbook", number)));
+    }
+    append(dir, &json!({ "time": now(), "hide": number }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    /// An empty directory for the guestbook of test `name`.
+    fn scratch(name: &str) -> String {
+        let dir = env::temp_dir().join(format!("rust-cv-{}-{}", name, process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn one_entry_a_network_at_a_time() {
+        let dir = scratch("guestbook-network");
+        assert!(sign(&dir, "192.0.2.0/24", "Ada", "", "Hello").is_ok());
+        assert_eq!(sign(&dir, "192.0.2.0/24", "Ada", "", "Again").unwrap_err(), "You signed a few minutes ago, try again later");
+        assert!(sign(&dir, "198.51.100.0/24", "Grace", "", "Hello").is_ok());
+        // The local UI signs as often as it likes.
+        assert!(sign(&dir, "", "Owner", "", "One").is_ok());
+        assert!(sign(&dir, "", "Owner", "", "Two").is_ok());
+        assert_eq!(read(&dir).unwrap().len(), 4);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn networks_sign_again_later() {
+        let dir = scratch("guestbook-later");
+        let line = json!({ "time": now() - INTERVAL - 1, "name": "Ada", "contact": "", "message": "Hello", "network": "192.0.2.0/24" });
+        append(&dir, &line).unwrap();
+        assert!(sign(&dir, "192.0.2.0/24", "Ada", "", "Again").is_ok());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,166 @@
+//! Hyperlinks in the terminal with the OSC 8 escape sequence.
+//!
+//! The UI is drawn as usual and the URLs and emails on screen are found in
+//! the buffer afterwards, by a widget drawn over it, together with the
+//! contact details, which know their own links. Each cell of a link carries the sequence around its
+//! symbol, so redrawing part of a link keeps it whole. Terminals without
+//! OSC 8 show the text as it is, so the bare URL stays readable.
+
+use std::str::FromStr;
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::widgets::Widget;
+
+use cv::Contact;
+use term::Term;
+
+/// When to emit hyperlinks, chosen with `--links`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Only in terminals known to support them.
+    Auto,
+    Always,
+    Never,
+}
+
+impl LinkMode {
+    pub fn enabled(&self, term: &Term) -> bool {
+        match *self {
+            LinkMode::Auto => supported(term),
+            LinkMode::Always => true,
+            LinkMode::Never => false,
+        }
+    }
+}
+
+impl FromStr for LinkMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LinkMode, String> {
+        match s {
+            "auto" => Ok(LinkMode::Auto),
+            "always" => Ok(LinkMode::Always),
+            "never" => Ok(LinkMode::Never),
+            _ => Err(format!("unknown link mode '{}', expected one of: auto, always, never", s)),
+        }
+    }
+}
+
+/// Whether the terminal is one of those known to support OSC 8. Others may
+/// too, but some print the sequence instead of ignoring it.
+fn supported(term: &Term) -> bool {
+    if term.has("NO_HYPERLINKS") {
+        return false;
+    }
+    let name = term.var("TERM");
+    if name == "linux" || name == "dumb" {
+        return false;
+    }
+    if term.var("VTE_VERSION").parse::<u32>().map(|v| v >= 5000).unwrap_or(false) {
+        return true;
+    }
+    if term.has("KITTY_WINDOW_ID") || term.has("WT_SESSION") {
+        return true;
+    }
+    match term.var("TERM_PROGRAM") {
+        "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty" => return true,
+        _ => {}
+    }
+    ["kitty", "alacritty", "foot", "wezterm", "ghostty"].iter().any(|t| name.contains(t))
+}
+
+fn url_char(c: char) -> bool {
+    c.is_alphanumeric() || "-._~:/?#[]@!$&'*+,;=%".contains(c)
+}
+
+/// The link a word of text stands for, if any.
+fn target(word: &str) -> Option<String> {
+    let word = word.trim_end_matches(|c| ".,;:!?)]'".contains(c));
+    if word.starts_with("https://") || word.starts_with("http://") {
+        if word.len() > word.find("//").unwrap() + 2 {
+            return Some(word.to_string());
+        }
+        return None;
+    }
+    let mut parts = word.splitn(2, '@');
+    match (parts.next(), parts.next()) {
+        (Some(user), Some(domain))
+            if !user.is_empty() && !domain.contains('@') && domain.contains('.') && !domain.ends_with('.') =>
+        {
+            Some(format!("mailto:{}", word))
+        }
+        _ => None,
+    }
+}
+
+/// The runs of a line, as byte ranges, that are links, and where they go.
+fn find(line: &str, contacts: &[Contact]) -> Vec<(usize, usize, String)> {
+    let mut found = Vec::new();
+    for contact in contacts {
+        if contact.value.is_empty() {
+            continue;
+        }
+        let mut from = 0;
+        while let Some(i) = line[from..].find(contact.value.as_str()) {
+            let (start, end) = (from + i, from + i + contact.value.len());
+            if !found.iter().any(|&(a, b, _)| a < end && start < b) {
+                found.push((start, end, contact.url()));
+            }
+            from = end;
+        }
+    }
+    let mut start = None;
+    for (i, c) in line.char_indices().chain(Some((line.len(), ' '))) {
+        match (start, url_char(c)) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word = &line[s..i];
+                let covered = found.iter().any(|&(a, b, _)| a < i && s < b);
+                if let (Some(url), false) = (target(word), covered) {
+                    let trimmed = word.trim_end_matches(|c| ".,;:!?)]'".contains(c));
+                    found.push((s, s + trimmed.len(), url));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Turns the URLs, emails and contact details already drawn in an area
+/// into hyperlinks, so it is rendered after everything else.
+pub struct Links<'a> {
+    contacts: &'a [Contact],
+}
+
+impl<'a> Links<'a> {
+    pub fn new(contacts: &'a [Contact]) -> Links<'a> {
+        Links { contacts }
+    }
+}
+
+impl<'a> Widget for Links<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            // The line as text, and the cell each byte of it comes from.
+            let mut line = String::new();
+            let mut cells = Vec::new();
+            for x in area.left()..area.right() {
+                let symbol = &buf.get(x, y).symbol;
+                line.push_str(symbol);
+                cells.extend(symbol.bytes().map(|_| x));
+            }
+            for (start, end, url) in find(&line, self.contacts) {
+                // The id lets the terminal treat the cells as a single link.
+                let id = url.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+                let (first, last) = (cells[start], cells[end - 1]);
+                for x in first..last + 1 {
+                    let symbol = format!("\x1b]8;id={:x};{}\x1b\\{}\x1b]8;;\x1b\\", id, url, buf.get(x, y).symbol);
+                    buf.get_mut(x, y).set_symbol(&symbol);
+                }
+            }
+        }
+    }
+}
@@ -1,19 +1,78 @@
 extern crate tui;
 extern crate termion;
+extern crate zip;
+extern crate qrcode;
+extern crate image;
+extern crate ring;
+extern crate rustls;
+extern crate rcgen;
+extern crate libc;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
 
-use std::io;
+#[macro_use]
+mod note;
+mod anim;
+mod base64;
+mod clipboard;
+mod cli;
+mod contact;
+mod cv;
+mod export;
+mod figlet;
+mod graphics;
+mod guestbook;
+mod links;
+mod sanitize;
+mod serve;
+mod term;
+mod theme;
+mod visits;
+mod widgets;
+mod yank;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process;
 use std::thread;
-use std::time;
+use std::time::{Duration, Instant};
 use std::sync::mpsc;
+use std::sync::Arc;
+
+use image::RgbaImage;
 
 use termion::event;
 use termion::input::TermRead;
+use termion::raw::IntoRawMode;
 
 use tui::Terminal;
-use tui::backend::RawBackend;
-use tui::widgets::{Widget, Block, SelectableList, Gauge, Paragraph, Borders, Tabs};
+use tui::backend::{Backend, TermionBackend};
+use tui::buffer::Cell;
+use tui::widgets::{Widget, Block, SelectableList, Gauge, Paragraph, Borders, Tabs, BarChart, Sparkline};
 use tui::layout::{Group, Direction, Size, Rect};
-use tui::style::{Style, Color, Modifier};
+use tui::style::Style;
+
+use qrcode::{EcLevel, QrCode};
+
+use anim::{Animations, Side};
+use cli::{Command, Ui};
+use contact::Maildir;
+use cv::Cv;
+use figlet::Font;
+use graphics::{GraphicsMode, Photo, Protocol};
+use links::Links;
+use term::Term;
+use theme::Role;
+use visits::{Action, Stats, Visit};
+use guestbook::Entry;
+use widgets::{Banner, Input, Picture, Qr, TextInput};
+
+/// The titles of the tabs, in order.
+pub const TABS: &[&str] = &["Welcome", "Personal", "Skills", "Experience", "Courses", "Looking For"];
 
 pub struct MyTabs<'a> {
     pub titles: Vec<&'a str>,
@@ -36,81 +95,731 @@ impl<'a> MyTabs<'a> {
 
 struct App<'a> {
     size: Rect,
-    tabs: MyTabs<'a>
+    tabs: MyTabs<'a>,
+    cv: Cv,
+    /// The item of the tab that `y` copies, see `yank::items`.
+    focus: usize,
+    /// A message shown for a few ticks in the corner.
+    toast: Option<(String, u8)>,
+    /// Whether URLs and emails are drawn as hyperlinks.
+    links: bool,
+    photo: Option<Photo>,
+    /// The size of a cell in pixels, to place the photo.
+    cell: (u32, u32),
+    /// The fonts the name banner may use, from the largest.
+    fonts: Vec<Font>,
+    anim: Animations,
+    /// The QR code of the vCard while it is shown over the Personal tab.
+    card: Option<QrCode>,
+    /// What is typed after `/`, until Enter searches for it.
+    search: Option<Form>,
+    guestbook: Option<Guestbook>,
+    contact: Option<Contact>,
+    dashboard: Option<Dashboard>,
+}
+
+/// The fields of a form, typed in one at a time.
+struct Form {
+    fields: Vec<Input>,
+    /// The field being typed in.
+    field: usize,
+}
+
+/// What a key does to a form besides typing.
+enum Typed {
+    Nothing,
+    /// Enter in the last field.
+    Submit,
+    /// Esc.
+    Leave,
+}
+
+impl Form {
+    /// A form of fields that take as many characters as `limits` say.
+    fn new(limits: &[usize]) -> Form {
+        Form { fields: limits.iter().map(|&limit| Input::new(limit)).collect(), field: 0 }
+    }
+
+    fn text(&self, field: usize) -> &str {
+        self.fields[field].text()
+    }
+
+    fn clear(&mut self) {
+        for field in &mut self.fields {
+            field.clear();
+        }
+        self.field = 0;
+    }
+
+    fn key(&mut self, key: event::Key) -> Typed {
+        let count = self.fields.len();
+        let field = &mut self.fields[self.field];
+        match key {
+            event::Key::Esc => return Typed::Leave,
+            event::Key::Char('\t') | event::Key::Down => self.field = (self.field + 1) % count,
+            event::Key::Up => self.field = (self.field + count - 1) % count,
+            event::Key::Char('\n') if self.field + 1 < count => self.field += 1,
+            event::Key::Char('\n') => return Typed::Submit,
+            event::Key::Backspace | event::Key::Ctrl('h') => field.backspace(),
+            event::Key::Delete => field.delete(),
+            event::Key::Left => field.left(),
+            event::Key::Right => field.right(),
+            event::Key::Home => field.home(),
+            event::Key::End => field.end(),
+            event::Key::Char(c) => field.insert(c),
+            _ => {}
+        }
+        Typed::Nothing
+    }
+}
+
+/// The Guestbook tab: a form to sign it and the latest entries, read again
+/// every few seconds while it is shown.
+struct Guestbook {
+    dir: String,
+    /// The name, contact and message.
+    form: Form,
+    /// Whether keys go to the form rather than move around the UI.
+    editing: bool,
+    entries: Result<Vec<Entry>, String>,
+    read: Instant,
+}
+
+impl Guestbook {
+    fn new(dir: &str) -> Guestbook {
+        Guestbook {
+            dir: dir.to_string(),
+            form: Form::new(&[guestbook::NAME, guestbook::CONTACT, guestbook::MESSAGE]),
+            editing: false,
+            entries: guestbook::read(dir).map_err(|err| err.to_string()),
+            read: Instant::now(),
+        }
+    }
+
+    fn reread(&mut self) {
+        self.entries = guestbook::read(&self.dir).map_err(|err| err.to_string());
+        self.read = Instant::now();
+    }
+
+    /// Types `key` in the form, returning what to tell the visitor.
+    fn edit(&mut self, key: event::Key, visit: &Visit) -> Option<String> {
+        match self.form.key(key) {
+            Typed::Nothing => None,
+            Typed::Leave => {
+                self.editing = false;
+                None
+            }
+            Typed::Submit => {
+                let form = &self.form;
+                if let Err(message) = guestbook::sign(&self.dir, visit.network(), form.text(0), form.text(1), form.text(2)) {
+                    return Some(message);
+                }
+                self.form.clear();
+                self.editing = false;
+                self.reread();
+                visit.record(Action::Sign);
+                Some("Thank you for signing the guestbook".to_string())
+            }
+        }
+    }
+}
+
+/// The contact form over the Looking For tab, which delivers what visitors
+/// write to the Maildir of the owner.
+struct Contact {
+    maildir: Maildir,
+    /// The name, email address, subject and message.
+    form: Form,
+    open: bool,
+    /// How the last message went, shown above the form.
+    status: Option<String>,
+    /// The messages the session sent.
+    sent: usize,
+}
+
+/// How many messages a session sends.
+const CONTACT_MESSAGES: usize = 3;
+
+impl Contact {
+    fn new(maildir: &Maildir) -> Contact {
+        Contact {
+            maildir: maildir.clone(),
+            form: Form::new(&[contact::NAME, contact::EMAIL, contact::SUBJECT, contact::BODY]),
+            open: false,
+            status: None,
+            sent: 0,
+        }
+    }
+
+    /// Types `key` in the form, sending the message on Enter in the last
+    /// field.
+    fn edit(&mut self, key: event::Key, cv: &Cv, visit: &Visit) {
+        match self.form.key(key) {
+            Typed::Nothing => {}
+            Typed::Leave => self.open = false,
+            Typed::Submit if self.sent >= CONTACT_MESSAGES => {
+                self.status = Some("This session has sent all the messages it can".to_string());
+            }
+            Typed::Submit => {
+                let form = &self.form;
+                let message = match contact::Message::new(form.text(0), form.text(1), form.text(2), form.text(3)) {
+                    Ok(message) => message,
+                    Err(problem) => {
+                        self.status = Some(problem);
+                        return;
+                    }
+                };
+                self.status = Some(match self.maildir.deliver(&message, cv, visit.network()) {
+                    Ok(reference) => {
+                        self.sent += 1;
+                        self.form.clear();
+                        visit.record(Action::Contact);
+                        format!("Thank you, your message was delivered. Its reference is {}.", reference)
+                    }
+                    Err(err) => {
+                        note!("rust-cv: cannot deliver a message: {}", err);
+                        "Sorry, the message could not be delivered, try again later".to_string()
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// The Dashboard tab, the last tab, which sums up the log of the visits to
+/// the servers and reads it again every few seconds while it is shown.
+struct Dashboard {
+    dir: String,
+    stats: Result<Stats, String>,
+    read: Instant,
+}
+
+impl Dashboard {
+    fn new(dir: &str) -> Dashboard {
+        Dashboard { dir: dir.to_string(), stats: visits::read(dir).map_err(|err| err.to_string()), read: Instant::now() }
+    }
+}
+
+/// The text of the Welcome tab, as `Paragraph` markup.
+const WELCOME: &str = "\nUse {mod=bold;fg=yellow ←}  and {mod=bold;fg=yellow →}  to move between the tabs.\n\n\
+                       Use {mod=bold;fg=yellow ↑}  and {mod=bold;fg=yellow ↓}  to move between the panels of a tab, {mod=bold;fg=yellow y} to copy the highlighted one \
+                       and {mod=bold;fg=yellow Y} to copy the whole tab as Markdown.\n\n\
+                       Use {mod=bold;fg=yellow /} to search the panels of every tab, from the highlighted one on.\n\n\
+                       In the Personal tab use {mod=bold;fg=yellow c} to show my contact details as a QR code.\n\n\
+                       Any key skips the animations, and {mod=bold;fg=yellow q} exits the application.\n\n\
+                       I hope you like it!\n\n\
+                       {mod=bold;fg=yellow **Note:} Optimized resolution of the command line is 130x40 characters.{mod=bold;fg=yellow **}";
+
+/// How many characters a search takes.
+const SEARCH: usize = 40;
+
+/// How long a toast is shown, in ticks.
+const TOAST_TICKS: u8 = 10;
+
+const TICK: Duration = Duration::from_millis(200);
+
+/// How often the Dashboard and Guestbook tabs read their logs again.
+const REFRESH: Duration = Duration::from_secs(5);
+
+/// What every session of the UI shows, loaded once.
+pub struct Content {
+    pub cv: Cv,
+    pub photo: Option<Arc<RgbaImage>>,
+    /// The fonts the name banner may use, from the largest.
+    pub fonts: Vec<Font>,
+    /// The log directory the Dashboard tab sums up, only ever set for the
+    /// local UI.
+    pub dashboard: Option<String>,
+    /// The log directory the guestbook is kept in, for the servers that
+    /// have one.
+    pub guestbook: Option<String>,
+    /// Where the contact form delivers, when there is one.
+    pub maildir: Option<Maildir>,
+}
+
+impl Content {
+    /// Loads the content file, and the photo when sessions are going to
+    /// show it.
+    pub fn load(path: Option<String>, photo: bool) -> Result<Content, io::Error> {
+        let cv = load(path)?;
+        let photo = match cv.photo {
+            Some(ref path) if photo && !path.contains("://") => Some(graphics::load(path)?),
+            _ => None,
+        };
+        let fonts = figlet::fonts(cv.banner_font.as_deref())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Content { cv, photo, fonts, dashboard: None, guestbook: None, maildir: None })
+    }
+}
+
+/// What a session of the UI uses of its terminal.
+pub struct Settings {
+    pub links: bool,
+    pub graphics: Option<Protocol>,
+    pub motion: bool,
+}
+
+impl Settings {
+    pub fn new(ui: &Ui, term: &Term) -> Settings {
+        Settings { links: ui.links.enabled(term), graphics: ui.graphics.protocol(term), motion: ui.motion }
+    }
 }
 
-enum Event {
+pub enum Event {
     Input(event::Key),
-    Tick,
+    /// The terminal is now this big, with cells of this many pixels.
+    Resize(Rect, (u32, u32)),
+}
+
+/// The standard output, as a writer the UI can hand copies of to tui and to
+/// what writes around it.
+#[derive(Clone)]
+struct Stdout;
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
 }
 
 fn main() {
-    let mut app = App {
-        size: Rect::default(),
-        tabs: MyTabs {
-            titles: vec!["Welcome", "Personal", "Skills", "Experience", "Courses", "Looking For"],
-            selection: 0,
+    let command = match cli::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("rust-cv: {}\n\n{}", message, cli::USAGE);
+            process::exit(2);
         }
     };
+    let result = match command {
+        Command::Tui { content, ui, dashboard } => Content::load(content, ui.graphics != GraphicsMode::None).and_then(|mut content| {
+            content.dashboard = dashboard;
+            run_tui(&content, &ui)
+        }),
+        Command::Export { format, output, content } => load(content).and_then(|cv| export_to(&cv, format, output)),
+        Command::Import { from, input, output } => import_from(from, &input, output),
+        Command::Lint { content } => lint(&content),
+        Command::Guestbook { dir, hide } => moderate(&dir, hide),
+        Command::Serve { content, server, limits, sandbox, metrics } => serve::run(content, *server, limits, sandbox, metrics),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+    };
+    if let Err(err) = result {
+        note!("rust-cv: {}", err);
+        process::exit(1);
+    }
+}
+
+fn load(content: Option<String>) -> Result<Cv, io::Error> {
+    match content {
+        Some(path) => Cv::load(&path),
+        None => Ok(Cv::default()),
+    }
+}
+
+/// Opens the file to write to, `None` and `-` being the standard output.
+fn create(output: Option<String>) -> Result<Box<dyn Write>, io::Error> {
+    match output {
+        Some(ref path) if path != "-" => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        _ => Ok(Box::new(io::stdout())),
+    }
+}
+
+fn import_from(from: export::Format, input: &str, output: Option<String>) -> Result<(), io::Error> {
+    let (cv, changes) = export::import(from, BufReader::new(File::open(input)?))?.sanitized()?;
+    for change in &changes {
+        eprintln!("rust-cv: {}: {}", input, change.describe());
+    }
+    let mut out = create(output)?;
+    serde_json::to_writer_pretty(&mut out, &cv).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    writeln!(out)?;
+    out.flush()
+}
+
+/// Lists what is cleaned up in the text of a content file, an error when
+/// there is anything.
+fn lint(content: &str) -> Result<(), io::Error> {
+    let (_, changes) = Cv::read(content)?;
+    for change in &changes {
+        println!("{}: {}", content, change.describe());
+    }
+    match changes.iter().map(|change| change.count).sum::<usize>() {
+        0 => Ok(()),
+        n => Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: {} characters to clean up", content, n))),
+    }
+}
+
+/// Lists the entries of the guestbook kept in `dir`, or hides entry
+/// `hide`.
+fn moderate(dir: &str, hide: Option<usize>) -> Result<(), io::Error> {
+    if let Some(number) = hide {
+        return guestbook::hide(dir, number);
+    }
+    for entry in guestbook::read(dir)? {
+        let hidden = if entry.hidden { " (hidden)" } else { "" };
+        println!("{:>4}  {}, {}{}", entry.number, entry.name, guestbook::ago(entry.time), hidden);
+        if !entry.contact.is_empty() {
+            println!("      {}", entry.contact);
+        }
+        println!("      {}\n", entry.message);
+    }
+    Ok(())
+}
+
+fn export_to(cv: &Cv, format: export::Format, output: Option<String>) -> Result<(), io::Error> {
+    let mut out = create(output)?;
+    export::export(cv, format, &mut out)?;
+    out.flush()
+}
+
+/// The size of the terminal of the process, and of its cells.
+fn local_size() -> Result<(Rect, (u32, u32)), io::Error> {
+    let (columns, rows) = termion::terminal_size()?;
+    let pixels = termion::terminal_size_pixels().unwrap_or((0, 0));
+    Ok((Rect::new(0, 0, columns, rows), graphics::cell_size(columns, rows, pixels)))
+}
+
+fn run_tui(content: &Content, ui: &Ui) -> Result<(), io::Error> {
+    let settings = Settings::new(ui, &Term::local());
+    let (size, cell) = local_size()?;
     let (tx, rx) = mpsc::channel();
     let input_tx = tx.clone();
 
+    thread::spawn(move || forward_keys(io::stdin(), &input_tx));
+
+    // There is no event for it, so the size is checked on every tick.
     thread::spawn(move || {
-        let stdin = io::stdin();
-        for c in stdin.keys() {
-            let evt = c.unwrap();
-            input_tx.send(Event::Input(evt)).unwrap();
-            if evt == event::Key::Char('q') {
-                break;
+        let mut last = (size, cell);
+        loop {
+            thread::sleep(TICK);
+            match local_size() {
+                Ok(now) if now != last => {
+                    last = now;
+                    if tx.send(Event::Resize(now.0, now.1)).is_err() {
+                        break;
+                    }
+                }
+                _ => {}
             }
         }
     });
 
-    thread::spawn(move || {
-        let tx = tx.clone();
-        loop {
-            tx.send(Event::Tick).unwrap();
-            thread::sleep(time::Duration::from_millis(200));
+    let _raw = io::stdout().into_raw_mode()?;
+    run_session(content, settings, size, cell, Stdout, rx, &Visit::none())
+}
+
+/// Sends the keys read from `input` to a session, until the session or the
+/// input ends. `q` is typed in forms like any other key, so it is the
+/// session that tells when it quits.
+fn forward_keys<R: Read>(input: R, events: &mpsc::Sender<Event>) {
+    for key in input.keys() {
+        let key = match key {
+            Ok(key) => key,
+            Err(_) => break,
+        };
+        if events.send(Event::Input(key)).is_err() {
+            break;
         }
-    });
+    }
+}
+
+/// Moves the focus to the next panel that has `query`, on whatever tab it
+/// is.
+fn search(app: &mut App, query: &str, visit: &Visit) {
+    visit.record(Action::Search(query));
+    let (tab, focus) = match yank::find(&app.cv, query, app.tabs.selection, app.focus) {
+        Some(found) => found,
+        None => {
+            app.toast = Some((format!("Nothing has \"{}\"", query), TOAST_TICKS));
+            return;
+        }
+    };
+    if tab != app.tabs.selection {
+        let side = if tab > app.tabs.selection { Side::Right } else { Side::Left };
+        app.tabs.selection = tab;
+        app.card = None;
+        app.anim.open(tab, side);
+        visit.record(Action::Tab(app.tabs.titles[tab]));
+    }
+    app.focus = focus;
+    let title = yank::items(&app.cv, tab).into_iter().nth(focus).map(|item| item.title).unwrap_or_default();
+    app.toast = Some((format!("Found in {}: {}", app.tabs.titles[tab], title), TOAST_TICKS));
+}
+
+/// Runs a session of the UI on a terminal of `size` that `out` writes to,
+/// until `q` or the end of `events`, with what it does recorded in `visit`.
+fn run_session<W: Write + Clone>(
+    content: &Content,
+    settings: Settings,
+    size: Rect,
+    cell: (u32, u32),
+    mut out: W,
+    events: mpsc::Receiver<Event>,
+    visit: &Visit,
+) -> Result<(), io::Error> {
+    let mut titles = TABS.to_vec();
+    if content.guestbook.is_some() {
+        titles.push("Guestbook");
+    }
+    if content.dashboard.is_some() {
+        titles.push("Dashboard");
+    }
+    let photo = match (content.photo.as_ref(), settings.graphics) {
+        (Some(image), Some(protocol)) => Some(Photo::new(image.clone(), protocol)),
+        _ => None,
+    };
+    let mut app = App {
+        size,
+        tabs: MyTabs {
+            titles,
+            selection: 0,
+        },
+        cv: content.cv.clone(),
+        focus: 0,
+        toast: None,
+        links: settings.links,
+        photo,
+        cell,
+        fonts: content.fonts.clone(),
+        anim: Animations::new(settings.motion, WELCOME),
+        card: None,
+        search: None,
+        guestbook: content.guestbook.as_ref().map(|dir| Guestbook::new(dir)),
+        contact: content.maildir.as_ref().map(Contact::new),
+        dashboard: content.dashboard.as_ref().map(|dir| Dashboard::new(dir)),
+    };
+    visit.record(Action::Tab(app.tabs.titles[0]));
 
-    let backend = RawBackend::new().unwrap();
-    let mut terminal = Terminal::new(backend).unwrap();
-    terminal.clear().unwrap();
-    terminal.hide_cursor().unwrap();
+    let backend = SessionBackend { termion: TermionBackend::with_stdout(out.clone()), size };
+    let mut terminal = Terminal::new(backend)?;
+    terminal.resize(size)?;
+    terminal.clear()?;
+    terminal.hide_cursor()?;
 
+    let mut next_tick = Instant::now() + TICK;
     loop {
-        let size = terminal.size().unwrap();
-        if size != app.size {
-            terminal.resize(size).unwrap();
-            app.size = size;
-        }
-        draw(&mut terminal, &app).unwrap();
-        let evt = rx.recv().unwrap();
-        match evt {
-            Event::Input(input) => {
+        let started = Instant::now();
+        let photo_area = draw(&mut terminal, &app)?;
+        show_photo(&mut terminal, &mut app, &mut out, photo_area)?;
+        serve::rendered(started.elapsed());
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+        match events.recv_timeout(timeout) {
+            Ok(Event::Input(input)) => {
+                // Any key finishes the animations, and still does what it
+                // does.
+                app.anim.skip();
+                let tab = app.tabs.titles[app.tabs.selection];
+                let editing = tab == "Guestbook" && app.guestbook.as_ref().map_or(false, |guestbook| guestbook.editing);
+                let contacting = app.tabs.selection == 5 && app.contact.as_ref().map_or(false, |contact| contact.open);
                 match input {
+                    key if editing => {
+                        if let Some(message) = app.guestbook.as_mut().and_then(|guestbook| guestbook.edit(key, visit)) {
+                            app.toast = Some((message, TOAST_TICKS));
+                        }
+                    }
+                    key if contacting => {
+                        if let Some(ref mut contact) = app.contact {
+                            contact.edit(key, &app.cv, visit);
+                        }
+                    }
+                    key if app.search.is_some() => {
+                        let typed = app.search.as_mut().map(|search| search.key(key));
+                        match typed {
+                            Some(Typed::Submit) => {
+                                let query = app.search.take().map(|search| search.text(0).trim().to_string()).unwrap_or_default();
+                                if !query.is_empty() {
+                                    search(&mut app, &query, visit);
+                                }
+                            }
+                            Some(Typed::Leave) => app.search = None,
+                            _ => {}
+                        }
+                    }
+                    event::Key::Char('/') => {
+                        app.search = Some(Form::new(&[SEARCH]));
+                    }
+                    event::Key::Char('m') if app.tabs.selection == 5 && app.contact.is_some() => {
+                        if let Some(ref mut contact) = app.contact {
+                            contact.open = true;
+                            contact.status = None;
+                        }
+                    }
+                    event::Key::Char('\n') if tab == "Guestbook" => {
+                        if let Some(ref mut guestbook) = app.guestbook {
+                            guestbook.editing = true;
+                        }
+                    }
                     event::Key::Char('q') => {
                         break;
                     }
                     event::Key::Left => {
                         app.tabs.previous();
+                        app.focus = 0;
+                        app.card = None;
+                        app.anim.open(app.tabs.selection, Side::Left);
+                        visit.record(Action::Tab(app.tabs.titles[app.tabs.selection]));
                     }
                     event::Key::Right => {
                         app.tabs.next();
+                        app.focus = 0;
+                        app.card = None;
+                        app.anim.open(app.tabs.selection, Side::Right);
+                        visit.record(Action::Tab(app.tabs.titles[app.tabs.selection]));
+                    }
+                    event::Key::Down => {
+                        let items = yank::items(&app.cv, app.tabs.selection).len();
+                        if items > 0 {
+                            app.focus = (app.focus + 1) % items;
+                        }
+                    }
+                    event::Key::Up => {
+                        let items = yank::items(&app.cv, app.tabs.selection).len();
+                        if items > 0 {
+                            app.focus = (app.focus + items - 1) % items;
+                        }
+                    }
+                    event::Key::Char('y') => {
+                        let message = match yank::items(&app.cv, app.tabs.selection).into_iter().nth(app.focus) {
+                            Some(item) => clipboard::copy(&mut out, &item.text).map(|_| {
+                                visit.record(Action::Export(&item.title));
+                                format!("Copied {}", item.title)
+                            }),
+                            None => Ok("Nothing to copy here".to_string()),
+                        };
+                        app.toast = Some((message.unwrap_or_else(|err| format!("Could not copy: {}", err)), TOAST_TICKS));
+                    }
+                    event::Key::Char('Y') => {
+                        let title = app.tabs.titles[app.tabs.selection];
+                        let message = match yank::section(&app.cv, app.tabs.selection, title) {
+                            Some(markdown) => clipboard::copy(&mut out, &markdown).map(|_| {
+                                visit.record(Action::Export(&format!("{} as Markdown", title)));
+                                format!("Copied {} as Markdown", title)
+                            }),
+                            None => Ok("Nothing to copy here".to_string()),
+                        };
+                        app.toast = Some((message.unwrap_or_else(|err| format!("Could not copy: {}", err)), TOAST_TICKS));
+                    }
+                    event::Key::Char('c') if app.tabs.selection == 1 => {
+                        app.card = match app.card {
+                            Some(_) => None,
+                            // Low error correction keeps the code small
+                            // enough for the terminal, a screen does not
+                            // get smudged like paper.
+                            None => QrCode::with_error_correction_level(export::vcard::card(&app.cv), EcLevel::L).ok(),
+                        };
+                    }
+                    event::Key::Esc => {
+                        app.card = None;
                     }
                     _ => {}
                 }
             }
-            Event::Tick => {}
+            Ok(Event::Resize(size, cell)) => {
+                terminal.backend_mut().size = size;
+                terminal.resize(size)?;
+                app.size = size;
+                app.cell = cell;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                next_tick += TICK;
+                app.anim.tick();
+                app.toast = match app.toast.take() {
+                    Some((message, ticks)) if ticks > 1 => Some((message, ticks - 1)),
+                    _ => None,
+                };
+                let tab = app.tabs.titles[app.tabs.selection];
+                match app.dashboard {
+                    Some(ref mut dashboard) if tab == "Dashboard" && dashboard.read.elapsed() >= REFRESH => {
+                        *dashboard = Dashboard::new(&dashboard.dir);
+                    }
+                    _ => {}
+                }
+                match app.guestbook {
+                    Some(ref mut guestbook) if tab == "Guestbook" && guestbook.read.elapsed() >= REFRESH => guestbook.reread(),
+                    _ => {}
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break;
+            }
         }
     }
-    terminal.show_cursor().unwrap();
-    terminal.clear().unwrap();
+    if let Some(ref mut photo) = app.photo {
+        photo.remove(&mut out)?;
+    }
+    terminal.show_cursor()?;
+    terminal.clear()
+}
+
+/// The terminal of a session, whatever it writes to.
+type Screen<W> = Terminal<SessionBackend<W>>;
+
+/// Draws with termion, on a terminal of the size the session was told
+/// rather than that of the standard output, which a server has none of.
+struct SessionBackend<W: Write> {
+    termion: TermionBackend<W>,
+    size: Rect,
+}
+
+impl<W: Write> Backend for SessionBackend<W> {
+    fn draw<'a, I>(&mut self, content: I) -> Result<(), io::Error>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.termion.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), io::Error> {
+        self.termion.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> Result<(), io::Error> {
+        self.termion.show_cursor()
+    }
+
+    fn clear(&mut self) -> Result<(), io::Error> {
+        self.termion.clear()
+    }
+
+    fn size(&self) -> Result<Rect, io::Error> {
+        Ok(self.size)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.termion.flush()
+    }
+}
+
+/// Turns the `**` markers of free text into the markup of `Paragraph`.
+fn markup(text: &str) -> String {
+    cv::spans(text)
+        .iter()
+        .map(|span| if span.bold { Role::Emphasis.markup(span.text) } else { span.text.to_string() })
+        .collect()
+}
+
+fn label(name: &str) -> String {
+    Role::Label.markup(&format!("{}:", name))
 }
 
-fn draw(t: &mut Terminal<RawBackend>, app: &App) -> Result<(), io::Error> {
+/// The border of a panel, which tells whether it has the focus.
+fn border(focused: bool) -> Style {
+    if focused {
+        Role::Focus.style()
+    } else {
+        Style::default()
+    }
+}
 
+/// Draws the UI, returning where the photo goes when the terminal shows it
+/// outside of tui.
+fn draw<W: Write>(t: &mut Screen<W>, app: &App) -> Result<Option<Rect>, io::Error> {
+    let mut photo_area = None;
     Group::default()
         .direction(Direction::Vertical)
         .sizes(&[Size::Fixed(3), Size::Min(0)])
@@ -118,37 +827,86 @@ fn draw(t: &mut Terminal<RawBackend>, app: &App) -> Result<(), io::Error> {
             Tabs::default()
                 .block(Block::default().borders(Borders::ALL).title("Tabs"))
                 .titles(&app.tabs.titles)
-                .style(Style::default().fg(Color::Green))
-                .highlight_style(Style::default().fg(Color::Yellow))
+                .style(Role::Tab.style())
+                .highlight_style(Role::SelectedTab.style())
                 .select(app.tabs.selection)
                 .render(t, &chunks[0]);
+            // A tab that slides in starts narrower, on the side it comes
+            // from.
+            let content = chunks[1];
+            let content = match app.anim.slide(content.width) {
+                Some((offset, Side::Right)) => Rect::new(content.x + offset, content.y, content.width - offset, content.height),
+                Some((offset, Side::Left)) => Rect::new(content.x, content.y, content.width - offset, content.height),
+                None => content,
+            };
             match app.tabs.selection {
                 0 => {
-                    draw_welcome(t, &chunks[1]);
+                    draw_welcome(t, &app.cv, &app.fonts, &app.anim.typed(WELCOME), &content);
                 }
                 1 => {
-                    draw_personal(t, &chunks[1]);
+                    photo_area = draw_personal(t, &app.cv, app.photo.as_ref(), app.focus, &content);
                 }
                 2 => {
-                    draw_skills(t, &chunks[1]);
+                    draw_skills(t, &app.cv, &app.anim, app.focus, &content);
                 }
                 3 => {
-                    draw_experience(t, &chunks[1]);
+                    draw_experience(t, &app.cv, app.focus, &content);
                 }
                 4 => {
-                    draw_education(t, &chunks[1]);
+                    draw_education(t, &app.cv, app.focus, &content);
                 }
-                5 => {
-                    draw_looking_for(t, &chunks[1]);
-                }
-                _ => {}
+                5 => match app.contact {
+                    Some(ref contact) if contact.open => draw_contact(t, contact, &content),
+                    ref contact => draw_looking_for(t, &app.cv, contact.is_some(), app.focus, &content),
+                },
+                _ => match (app.tabs.titles[app.tabs.selection], &app.guestbook, &app.dashboard) {
+                    ("Guestbook", Some(guestbook), _) => draw_guestbook(t, guestbook, &content),
+                    (_, _, Some(dashboard)) => draw_dashboard(t, &dashboard.stats, &content),
+                    _ => {}
+                },
             };
+            if let Some((ref message, _)) = app.toast {
+                draw_toast(t, message, &app.size);
+            }
+            if let Some(ref search) = app.search {
+                draw_search(t, search, &app.size);
+            }
+            if let Some(ref code) = app.card {
+                draw_card(t, code, &app.size);
+                photo_area = None;
+            }
+            // The photo waits for the tab to stop moving.
+            if app.anim.sliding() {
+                photo_area = None;
+            }
         });
+    if app.links && app.card.is_none() {
+        Links::new(&app.cv.contacts).render(t, &app.size);
+    }
     t.draw()?;
+    Ok(photo_area)
+}
+
+/// Moves the photo to `area`, or takes it off the screen, when it is not
+/// there already.
+fn show_photo<W: Write>(t: &mut Screen<W>, app: &mut App, out: &mut W, area: Option<Rect>) -> Result<(), io::Error> {
+    let redraw = match app.photo {
+        Some(ref mut photo) if photo.placed() != area => photo.remove(out)?,
+        _ => return Ok(()),
+    };
+    if redraw {
+        t.clear()?;
+        draw(t, app)?;
+    }
+    if let (Some(photo), Some(area)) = (app.photo.as_mut(), area) {
+        photo.place(out, area, app.cell)?;
+    }
     Ok(())
 }
 
-fn draw_welcome(t: &mut Terminal<RawBackend>, area: &Rect) {
+fn draw_welcome<W: Write>(t: &mut Screen<W>, cv: &Cv, fonts: &[Font], text: &str, area: &Rect) {
+    let title = format!("Welcome to {}'s Curriculum Vitae", cv.short_name);
+    let name = [cv.short_name.clone()];
     Group::default()
         .direction(Direction::Vertical)
         .sizes(&[Size::Percent(10), Size::Percent(80), Size::Percent(10)])
@@ -157,23 +915,51 @@ fn draw_welcome(t: &mut Terminal<RawBackend>, area: &Rect) {
                 .direction(Direction::Horizontal)
                 .sizes(&[Size::Percent(10), Size::Percent(80), Size::Percent(10)])
                 .render(t, &chunks[1], |t, chunks| {
-                    Paragraph::default()
-                    .block(Block::default()
-                        .borders(Borders::ALL)
-                        .title("Welcome to Oscar Forner's Curriculum Vitae")
-                        .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
-                    .wrap(true)
-                    .text("\nUse {mod=bold;fg=yellow ←}  and {mod=bold;fg=yellow →}  to move between the tabs.\n\n\
-                           Use {mod=bold;fg=yellow q} to exit the application.\n\n\
-                           I hope you like it!\n\n\
-                           {mod=bold;fg=yellow **Note:} Optimized resolution of the command line is 130x40 characters.{mod=bold;fg=yellow **}\
-                           ")
-                    .render(t, &chunks[1]);
+                    // The banner takes up to a third of the height, and the
+                    // name is plain bold text when no font fits.
+                    let banner = figlet::banner(fonts, &cv.short_name, chunks[1].width, chunks[1].height / 3);
+                    let lines = banner.as_ref().map(|b| &b[..]).unwrap_or(&name);
+                    Group::default()
+                    .direction(Direction::Vertical)
+                    .sizes(&[Size::Fixed(lines.len() as u16 + 1), Size::Min(0)])
+                    .render(t, &chunks[1], |t, chunks| {
+                        Banner::new(lines).style(Role::Section.style()).render(t, &chunks[0]);
+                        Paragraph::default()
+                        .block(Block::default()
+                            .borders(Borders::ALL)
+                            .title(&title)
+                            .title_style(Role::Section.style()))
+                        .wrap(true)
+                        .text(text)
+                        .render(t, &chunks[1]);
+                    });
                 });
         });
 }
 
-fn draw_personal(t: &mut Terminal<RawBackend>, area: &Rect) {
+/// Draws the Personal tab, returning where the photo goes when tui does not
+/// draw it.
+fn draw_personal<W: Write>(t: &mut Screen<W>, cv: &Cv, photo: Option<&Photo>, focus: usize, area: &Rect) -> Option<Rect> {
+    let mut photo_area = None;
+    let information: String = Some(("Name", cv.name.clone()))
+        .into_iter()
+        .chain(cv.information())
+        .map(|(name, value)| format!("{} {}\n\n", label(name), value))
+        .collect();
+    let about: String = cv.about.iter().map(|p| format!("{}\n\n", markup(p))).collect();
+    let languages: String = cv.languages.iter().map(|l| format!("{} {}\n\n", label(&l.name), l.level)).collect();
+    let studies: String = cv.studies
+        .iter()
+        .map(|s| format!("{}\n{}\n\n", Role::Heading.markup(&format!("{} - {}: {}", s.start, s.end, s.degree)), markup(&s.summary)))
+        .collect();
+    let contacts: String = cv.contacts
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let value = if focus == 4 + i { Role::Focus.markup(&c.value) } else { c.value.clone() };
+            format!("{} {}\n\n", label(c.kind.label()), value)
+        })
+        .collect();
     Group::default()
         .direction(Direction::Vertical)
         .sizes(&[Size::Percent(50), Size::Percent(50)])
@@ -182,28 +968,46 @@ fn draw_personal(t: &mut Terminal<RawBackend>, area: &Rect) {
             .direction(Direction::Horizontal)
             .sizes(&[Size::Percent(35), Size::Percent(65)])
             .render(t, &chunks[0], |t, chunks| {
-                Paragraph::default()
-                .block(Block::default()
+                let block = Block::default()
                     .borders(Borders::ALL)
+                    .border_style(border(focus == 0))
                     .title("Information")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
-                .wrap(true)
-                .text("\n{mod=bold;fg=yellow Name:} Oscar Forner Martinez\n\n\
-                       {mod=bold;fg=yellow Date of Birth:} 24/03/1988\n\n\
-                       {mod=bold;fg=yellow Nationality:} Spanish\n\n\
-                       {mod=bold;fg=yellow Location:} Spain\n\n\
-                      ")
-                .render(t, &chunks[0]);
+                    .title_style(Role::Section.style());
+                match photo {
+                    None => {
+                        Paragraph::default()
+                        .block(block)
+                        .wrap(true)
+                        .text(&format!("\n{}", information))
+                        .render(t, &chunks[0]);
+                    }
+                    Some(photo) => {
+                        block.clone().render(t, &chunks[0]);
+                        Group::default()
+                        .direction(Direction::Horizontal)
+                        .margin(1)
+                        .sizes(&[Size::Percent(55), Size::Percent(45)])
+                        .render(t, &chunks[0], |t, chunks| {
+                            Paragraph::default()
+                            .wrap(true)
+                            .text(&format!("\n{}", information))
+                            .render(t, &chunks[0]);
+                            if photo.in_cells() {
+                                Picture::new(photo.image()).render(t, &chunks[1]);
+                            } else {
+                                photo_area = Some(chunks[1]);
+                            }
+                        });
+                    }
+                }
                 Paragraph::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
+                    .border_style(border(focus == 1))
                     .title("About me")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                    .title_style(Role::Section.style()))
                 .wrap(true)
-                .text("\nI am a Senior Software Engineer interested in {mod=bold Backend development} and {mod=bold Systems Programming}.\n\n\
-                       I am a regular attendee of the {mod=bold C++} and {mod=bold Go} MeetUps in London (Virtually).\n\n\
-                       I enjoy playing {mod=bold handball} and all sorts of {mod=bold board games}, including {mod=bold Magic the Gathering}, and {mod=bold videogames}.\n\n\
-                      ")
+                .text(&format!("\n{}", about))
                 .render(t, &chunks[1]);
             });
             Group::default()
@@ -213,287 +1017,194 @@ fn draw_personal(t: &mut Terminal<RawBackend>, area: &Rect) {
                 Paragraph::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
+                    .border_style(border(focus == 2))
                     .title("Languages")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                    .title_style(Role::Section.style()))
                 .wrap(true)
-                .text("\n{mod=bold;fg=yellow Spanish:} Native\n\n\
-                       {mod=bold;fg=yellow Catalan:} Native\n\n\
-                       {mod=bold;fg=yellow English:} Fluent\n\n\
-                      ")
+                .text(&format!("\n{}", languages))
                 .render(t, &chunks[0]);
                 Paragraph::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
+                    .border_style(border(focus == 3))
                     .title("Studies")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                    .title_style(Role::Section.style()))
                 .wrap(true)
-                .text("\n{mod=bold;fg=yellow 2010 - 2013: Bachelor of Engineering in Computer Science}\n\
-                        Advanced Data Structures, Compilers and Interpreters,\n\
-                        Advanced Operating Systems, and Distributed Systems.\n\n\
-                       {mod=bold;fg=yellow 2006 - 2010: Associate Degree in Computer Science}\n\
-                        Operating Systems, Real Time Operating Systems and\n\
-                        Embedded Systems.\n\n\
-                      ")
+                .text(&format!("\n{}", studies))
                 .render(t, &chunks[1]);
                 Paragraph::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
-                    .title("Contact")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                    .border_style(border(focus >= 4))
+                    .title("Contact (c: QR code)")
+                    .title_style(Role::Section.style()))
                 .wrap(true)
-                .text("\n{mod=bold;fg=yellow Email:} oscar.forner.martinez@gmail.com\n\n\
-                       {mod=bold;fg=yellow Phone:} +34 617 60 60 12\n\n\
-                       {mod=bold;fg=yellow Website:} https://oscarforner.com/\n\n\
-                       {mod=bold;fg=yellow Twitter:} https://twitter.com/oscar_forner\n\n\
-                       {mod=bold;fg=yellow GitHub:} https://github.com/maitesin\n\n\
-                       {mod=bold;fg=yellow LinkedIn:} http://linkedin.com/in/oscarforner\n\n\
-                      ")
+                .text(&format!("\n{}", contacts))
                 .render(t, &chunks[2]);
             });
         });
+    photo_area
+}
+
+/// Shows a message in the bottom right corner.
+fn draw_toast<W: Write>(t: &mut Screen<W>, message: &str, area: &Rect) {
+    let width = (message.chars().count() as u16 + 2).min(area.width);
+    let height = 3.min(area.height);
+    let toast = Rect::new(area.right() - width, area.bottom() - height, width, height);
+    Paragraph::default()
+        .block(Block::default().borders(Borders::ALL).border_style(Role::Focus.style()))
+        .text(message)
+        .render(t, &toast);
+}
+
+/// Shows what is being searched for in the bottom left corner.
+fn draw_search<W: Write>(t: &mut Screen<W>, search: &Form, area: &Rect) {
+    let width = (SEARCH as u16 + 3).min(area.width);
+    let height = 3.min(area.height);
+    let box_area = Rect::new(area.left(), area.bottom() - height, width, height);
+    // Spaces cover what the box is drawn over.
+    Paragraph::default().text(&" ".repeat(width as usize)).render(t, &Rect::new(box_area.x, box_area.y + 1, width, 1));
+    draw_field(t, search, 0, "Search", "", true, &box_area);
+}
+
+/// Covers the whole terminal with the QR code of the vCard, which needs
+/// all the room it can get to scan.
+fn draw_card<W: Write>(t: &mut Screen<W>, code: &QrCode, area: &Rect) {
+    Qr::new(code)
+        .caption("Scan to save my contact details, c or Esc to go back")
+        .render(t, area);
 }
 
-fn draw_skills(t: &mut Terminal<RawBackend>, area: &Rect) {
+fn draw_skills<W: Write>(t: &mut Screen<W>, cv: &Cv, anim: &Animations, focus: usize, area: &Rect) {
+    let mut sizes: Vec<Size> = cv.skills.iter().map(|_| Size::Percent(70 / cv.skills.len().max(1) as u16)).collect();
+    sizes.push(Size::Percent(30));
     Group::default()
         .direction(Direction::Vertical)
-        .sizes(&[Size::Percent(35), Size::Percent(35), Size::Percent(30)])
+        .sizes(&sizes)
         .render(t, area, |t, chunks| {
+            for (i, (group, chunk)) in cv.skills.iter().zip(chunks).enumerate() {
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border(focus == i))
+                    .title(&group.title)
+                    .title_style(Role::Section.style())
+                    .render(t, chunk);
+                let gauges: Vec<Size> = group.skills.iter().map(|_| Size::Fixed(2)).collect();
+                Group::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .sizes(&gauges)
+                    .render(t, chunk, |t, chunks| {
+                    for (skill, chunk) in group.skills.iter().zip(chunks) {
+                        Gauge::default()
+                            .block(Block::default().title(&format!("{}:", skill.name)).title_style(Role::Heading.style()))
+                            .style(Role::Gauge.style())
+                            .label(&format!("{} / 100", anim.gauge(skill.level)))
+                            .percent(anim.gauge(skill.level))
+                            .render(t, chunk);
+                    }
+                });
+            }
+            let others = &chunks[cv.skills.len()];
             Block::default()
                 .borders(Borders::ALL)
-                .title("Programming Languages")
-                .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold))
-                .render(t, &chunks[0]);
-            Group::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .sizes(&[Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2)])
-                .render(t, &chunks[0], |t, chunks| {
-                Gauge::default()
-                    .block(Block::default().title("Go").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("95 / 100"))
-                    .percent(95)
-                    .render(t, &chunks[0]);
-                Gauge::default()
-                    .block(Block::default().title("Python:").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("85 / 100"))
-                    .percent(85)
-                    .render(t, &chunks[1]);
-                Gauge::default()
-                    .block(Block::default().title("C++(11/14):").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("80 / 100"))
-                    .percent(80)
-                    .render(t, &chunks[2]);
-                Gauge::default()
-                    .block(Block::default().title("C:").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("70 / 100"))
-                    .percent(70)
-                    .render(t, &chunks[3]);
-            });
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Operating Systems")
-                .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold))
-                .render(t, &chunks[1]);
-            Group::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .sizes(&[Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2),Size::Fixed(2)])
-                .render(t, &chunks[1], |t, chunks| {
-                Gauge::default()
-                    .block(Block::default().title("GNU/Linux:").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("95 / 100"))
-                    .percent(95)
-                    .render(t, &chunks[0]);
-                Gauge::default()
-                    .block(Block::default().title("FreeBSD:").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("80 / 100"))
-                    .percent(80)
-                    .render(t, &chunks[1]);
-                Gauge::default()
-                    .block(Block::default().title("OpenBSD:").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                    .style(Style::default()
-                            .fg(Color::Magenta)
-                            .bg(Color::Black)
-                            .modifier(Modifier::Italic))
-                    .label(&format!("70 / 100"))
-                    .percent(70)
-                    .render(t, &chunks[2]);
-            });
-            Block::default()
-                .borders(Borders::ALL)
+                .border_style(border(focus == cv.skills.len()))
                 .title("Others")
-                .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold))
-                .render(t, &chunks[2]);
+                .title_style(Role::Section.style())
+                .render(t, others);
+            // Every list is as wide as its longest entry, with gaps of two
+            // columns around them.
+            let mut columns = vec![Size::Fixed(2)];
+            for group in &cv.tools {
+                let widest = group.items.iter().chain(Some(&group.title)).map(|s| s.chars().count()).max().unwrap_or(0);
+                columns.push(Size::Fixed(widest as u16 + 4));
+                columns.push(Size::Fixed(2));
+            }
             Group::default()
                 .direction(Direction::Horizontal)
                 .margin(1)
-                .sizes(&[Size::Fixed(2), Size::Fixed(18),Size::Fixed(2),Size::Fixed(15),Size::Fixed(2),Size::Fixed(13),Size::Fixed(2),Size::Fixed(19),Size::Fixed(2),Size::Fixed(15),Size::Fixed(2),Size::Fixed(16),Size::Fixed(2),Size::Fixed(17),Size::Fixed(2)])
-                .render(t, &chunks[2], |t, chunks| {
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Orchestration").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["Kubernetes", "Helm", "Istio", "Docker Compose", "Heroku", "GKE"])
-                        .render(t, &chunks[1]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Build Systems").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["CMake", "Meson", "Make", "Ninja"])
-                        .render(t, &chunks[3]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("CI/CD").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["Gitlab CI", "Jenkins", "GitHub Actions", "Travis"])
-                        .render(t, &chunks[5]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Analysis").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["Golint", "Go vet", "Clang-sanitizer", "Perf", "Valgrind"])
-                        .render(t, &chunks[7]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Databases").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["MongoDB", "PostgreSQL", "SQLite", "CockroachDB", "MariaDB", "MySQL"])
-                        .render(t, &chunks[9]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Message Broker").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["RabbitMQ", "PubSub"])
-                        .render(t, &chunks[11]);
-                    SelectableList::default()
-                        .block(Block::default().borders(Borders::ALL).title("Hypervisors").title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-                        .items(&vec!["KVM", "VirtualBox", "VMWare", "OpenBSD's VMM"])
-                        .render(t, &chunks[13]);
+                .sizes(&columns)
+                .render(t, others, |t, chunks| {
+                    for (i, group) in cv.tools.iter().enumerate() {
+                        let items: Vec<&str> = group.items.iter().map(|s| s.as_str()).collect();
+                        SelectableList::default()
+                            .block(Block::default().borders(Borders::ALL).title(&group.title).title_style(Role::Heading.style()))
+                            .items(&items)
+                            .render(t, &chunks[2 * i + 1]);
+                    }
             });
     });
 }
 
-fn draw_experience(t: &mut Terminal<RawBackend>, area: &Rect) {
+fn draw_experience<W: Write>(t: &mut Screen<W>, cv: &Cv, focus: usize, area: &Rect) {
+    // Content files, and resumes imported without work, may have none.
+    if cv.experience.is_empty() {
+        return;
+    }
+    let sizes: Vec<Size> = cv.experience.iter().map(|_| Size::Percent(100 / cv.experience.len().max(1) as u16)).collect();
     Group::default()
         .direction(Direction::Vertical)
-        .sizes(&[Size::Percent(30), Size::Percent(30),Size::Percent(20), Size::Percent(20)])
+        .sizes(&sizes)
         .render(t, area, |t, chunks| {
+            for (i, (job, chunk)) in cv.experience.iter().zip(chunks).enumerate() {
+                let text: String = job.highlights
+                    .iter()
+                    .map(|h| match h.name {
+                        Some(ref name) => format!("{} {}\n\n", label(name), markup(&h.text)),
+                        None => format!("{}\n\n", markup(&h.text)),
+                    })
+                    .collect();
                 Paragraph::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
-                    .title("2020 - Present: Lead Software Engineer at Paack")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                    .border_style(border(focus == i))
+                    .title(&job.title())
+                    .title_style(Role::Section.style()))
                 .wrap(true)
-                .text("\nDuring my time in Paack I have worked in multiple projects, such as **Warehouse Management System**, **Business Rules Engine**, **New Retailer API**, and **Communications Service**. All of them were developed in **Go** using **PostgreSQL** as the database to store the information of the services and they were deployed in **Kubernetes** using **Helm** and **Istio**.\n\n\
-                As part of my role as Lead Software Engineer I mentored several people in my team and I was heavily involved in the hiring process of the company.\n\n\
-                       ")
-                .render(t, &chunks[0]);
-                Paragraph::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("2018 - 2020: Senior Software Engineer at GlobalSign")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
-                .wrap(true)
-                .text("\n{mod=bold;fg=yellow High Performance Platform:} Implemented new functionalities that served thousands of certificates per second. Lead the migration of backend storage from MongoDB to PostgreSQL.\n\n\
-                       {mod=bold;fg=yellow Kubernetes prototype:} Implemented a prototype of the system used in production to test the feasibility of a future migration.\n\n\
-                       {mod=bold;fg=yellow Others:} Mentored multiple new-hires and had a highly involved role in the hiring process. As a side project I developed an static analysis tool to enforce the code style used in the company.\n\n\
-                      ")
-                .render(t, &chunks[1]);
-                Paragraph::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("2016 - 2018: Software Engineer at VCA Technology")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
-                .wrap(true)
-                .text("\n{mod=bold;fg=yellow Network library:} Allows the user to interact with Boost ASIO through the std::stream interface.\n\n\
-                       {mod=bold;fg=yellow Tools:} Improve the toolchain used internally to allow the usage of clang-tidy for the linting of our code. Another tool allows to switch between several versions of the toolchain without having to change the environment.\n\n\
-                       {mod=bold;fg=yellow Backend development:} Several activities regarding the extension and implementation of new features in the backend of the product such as Authentication and GStreamer sinks.\n\n\
-                      ")
-                .render(t, &chunks[2]);
-                Paragraph::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("2015 - 2016: Software Developer at Programming Research")
-                    .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
-                .wrap(true)
-                .text("\n{mod=bold;fg=yellow Static analysis of code:} Checks if the code has some patterns that have an undefined behaviour, unspecified in the Standard, and/or implementation-defined.\n\n\
-                       {mod=bold;fg=yellow Dataflow analysis of code:} Checks the complexity of methods, pointer problems, memory handling, etc.\n\n\
-                       Took over two projects to refactor, maintain and add new features.\n\n\
-                      ")
-                .render(t, &chunks[3]);
+                .text(&format!("\n{}", text))
+                .render(t, chunk);
+            }
     });
 }
 
-fn draw_education(t: &mut Terminal<RawBackend>, area: &Rect) {
+fn draw_education<W: Write>(t: &mut Screen<W>, cv: &Cv, focus: usize, area: &Rect) {
+    // Nor courses, which are the certificates of JSON Resume.
+    if cv.courses.is_empty() {
+        return;
+    }
+    let sizes: Vec<Size> = cv.courses.iter().map(|_| Size::Percent(100 / cv.courses.len().max(1) as u16)).collect();
     Group::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .sizes(&[Size::Percent(18), Size::Percent(18),Size::Percent(19), Size::Percent(15),Size::Percent(15),Size::Percent(15)])
+        .sizes(&sizes)
         .render(t, area, |t, chunks| {
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("LFS258: Kubernetes Fundamentals (from Linux Foundation) - August 2019")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nLearnt how to setup, mantain and use a Kubernetes cluster, including how to deploy a containerized application and manipulating resources via the API.")
-        .render(t, &chunks[0]);
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("LFD331: Developing Linux Device Drivers (from Linux Foundation) - April 2016")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nLearnt about the different Linux device drivers, APIs and methods through which devices interface with the kernel.")
-        .render(t, &chunks[1]);
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("LFD320: Linux Kernel Internals and Debugging (from Linux Foundation) - March 2016")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nLearnt how Linux is architected, the basic methods for developing on the kernel, and how to work with the community.")
-        .render(t, &chunks[2]);
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("Agile for Developers (from Accelebrate) - August 2015")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nTeaches intermediate and advanced object-oriented developers the practices of Agile and Scrum.")
-        .render(t, &chunks[3]);
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("Algorithms, Part II (from Coursera) - November 2014")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nFocuses on graph, and string processing algorithms.")
-        .render(t, &chunks[4]);
-        Paragraph::default()
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("Algorithms, Part I (from Coursera) - September 2014")
-            .title_style(Style::default().fg(Color::Yellow).modifier(Modifier::Bold)))
-        .wrap(true)
-        .text("\nCovers elementary data structures, sorting, and searching algorithms.")
-        .render(t, &chunks[5]);
+        for (i, (course, chunk)) in cv.courses.iter().zip(chunks).enumerate() {
+            Paragraph::default()
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(border(focus == i))
+                .title(&course.heading())
+                .title_style(Role::Heading.style()))
+            .wrap(true)
+            .text(&format!("\n{}", markup(&course.summary)))
+            .render(t, chunk);
+        }
     });
 }
 
-fn draw_looking_for(t: &mut Terminal<RawBackend>, area: &Rect) {
+/// The Looking For tab, which says how to write when there is a contact
+/// form.
+fn draw_looking_for<W: Write>(t: &mut Screen<W>, cv: &Cv, contact: bool, focus: usize, area: &Rect) {
+    let roles: String = cv.looking_for.roles.iter().map(|r| format!("\t* {}\n", markup(r))).collect();
+    let mut text = format!(
+        " \n{}\n\n\n {}\n\n{}",
+        Role::Heading.markup(&cv.looking_for.headline),
+        markup(&cv.looking_for.intro),
+        roles.trim_end()
+    );
+    if contact {
+        text.push_str(&format!("\n\n\n Press {} to write to me.", Role::Label.markup("m")));
+    }
     Group::default()
         .direction(Direction::Vertical)
         .sizes(&[Size::Percent(10), Size::Percent(80), Size::Percent(10)])
@@ -505,21 +1216,218 @@ fn draw_looking_for(t: &mut Terminal<RawBackend>, area: &Rect) {
                     Paragraph::default()
                     .block(Block::default()
                         .borders(Borders::ALL)
+                        .border_style(border(focus == 0))
                         .title("What I am looking for?")
-                        .title_style(Style::default().fg(Color::Green).modifier(Modifier::Bold)))
+                        .title_style(Role::Section.style()))
                     .wrap(true)
-                    .text(" \
-                           \n{mod=bold;fg=yellow I am currently looking for new opportunities}\n\n\n \
-                           My ideal roles involve a combination of the following:\n\n\
-                           \t* Write low level libraries and/or components.\n\
-                           \t* Design, develop and maintain a high performance and reliable systems.\n\
-                           \t* Create and integrate APIs to expose and extend the functionality.\n\
-                           \t* Create and improve the tools used during the development process.\n\
-                           \t* Work in the internals of Operating Systems such as GNU/Linux and FreeBSD.\n\
-                           \t* Work on compilers and/or interpreters and designing programming languages.\n\
-                           \t* Contribute to Open Source software.\
-                           ")
+                    .text(&text)
                     .render(t, &chunks[1]);
                 });
         });
 }
+
+/// Draws field `i` of `form`, with the cursor in it when it is the one
+/// being typed in.
+fn draw_field<W: Write>(t: &mut Screen<W>, form: &Form, i: usize, title: &str, placeholder: &str, editing: bool, area: &Rect) {
+    let focused = editing && form.field == i;
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(border(focused))
+        .title(title)
+        .title_style(Role::Section.style())
+        .render(t, area);
+    Group::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .sizes(&[Size::Min(0)])
+        .render(t, area, |t, chunks| {
+            TextInput::new(&form.fields[i]).focused(focused).placeholder(placeholder).render(t, &chunks[0]);
+        });
+}
+
+/// The title of a field of `limit` characters, with how many are used.
+fn counted(title: &str, form: &Form, i: usize, limit: usize) -> String {
+    format!("{} ({}/{})", title, form.text(i).chars().count(), limit)
+}
+
+/// The fields of the guestbook, and what they show while empty.
+const GUESTBOOK_FIELDS: [(&str, &str); 3] = [
+    ("Name", "Who you are"),
+    ("Contact (optional)", "An email or a website, if you would like an answer"),
+    ("Message", "Say hi"),
+];
+
+/// How many entries the Guestbook tab lists, the latest.
+const GUESTBOOK_ENTRIES: usize = 50;
+
+fn draw_guestbook<W: Write>(t: &mut Screen<W>, guestbook: &Guestbook, area: &Rect) {
+    let help = if guestbook.editing {
+        format!(
+            "{} and {} move between the fields, {} in the message signs and {} stops typing",
+            Role::Label.markup("Tab"),
+            Role::Label.markup("↓"),
+            Role::Label.markup("Enter"),
+            Role::Label.markup("Esc")
+        )
+    } else {
+        format!("Press {} to sign the guestbook", Role::Label.markup("Enter"))
+    };
+    // Entries are what visitors typed, so they are shown as they are rather
+    // than taken as markup.
+    let entries = match guestbook.entries {
+        Ok(ref entries) => {
+            let shown: Vec<String> = entries
+                .iter()
+                .rev()
+                .filter(|entry| !entry.hidden)
+                .take(GUESTBOOK_ENTRIES)
+                .map(|entry| {
+                    let contact = if entry.contact.is_empty() { String::new() } else { format!(" <{}>", entry.contact) };
+                    format!("{}{}, {}\n{}\n", entry.name, contact, guestbook::ago(entry.time), entry.message)
+                })
+                .collect();
+            if shown.is_empty() {
+                "No one has signed yet.".to_string()
+            } else {
+                shown.join("\n")
+            }
+        }
+        Err(ref err) => format!("Cannot read the guestbook: {}", err),
+    };
+    Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&[Size::Fixed(2), Size::Fixed(3), Size::Fixed(3), Size::Fixed(3), Size::Min(0)])
+        .render(t, area, |t, chunks| {
+            Paragraph::default().text(&help).render(t, &chunks[0]);
+            for (i, &(title, placeholder)) in GUESTBOOK_FIELDS.iter().enumerate() {
+                let title = if i == 2 { counted(title, &guestbook.form, i, guestbook::MESSAGE) } else { title.to_string() };
+                draw_field(t, &guestbook.form, i, &title, placeholder, guestbook.editing, &chunks[i + 1]);
+            }
+            Paragraph::default()
+                .block(Block::default().borders(Borders::ALL).title("Entries").title_style(Role::Section.style()))
+                .raw(true)
+                .wrap(true)
+                .text(&entries)
+                .render(t, &chunks[4]);
+        });
+}
+
+/// The fields of the contact form, and what they show while empty.
+const CONTACT_FIELDS: [(&str, &str); 4] = [
+    ("Name", "Who you are"),
+    ("Email", "Where the answer goes"),
+    ("Subject", "What it is about"),
+    ("Message", "What you would like to tell me"),
+];
+
+fn draw_contact<W: Write>(t: &mut Screen<W>, contact: &Contact, area: &Rect) {
+    let help = format!(
+        "{} and {} move between the fields, {} in the message sends it and {} goes back",
+        Role::Label.markup("Tab"),
+        Role::Label.markup("↓"),
+        Role::Label.markup("Enter"),
+        Role::Label.markup("Esc")
+    );
+    Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&[Size::Fixed(2), Size::Fixed(3), Size::Fixed(3), Size::Fixed(3), Size::Fixed(3), Size::Min(0)])
+        .render(t, area, |t, chunks| {
+            Paragraph::default().text(&help).render(t, &chunks[0]);
+            for (i, &(title, placeholder)) in CONTACT_FIELDS.iter().enumerate() {
+                let title = if i == 3 { counted(title, &contact.form, i, contact::BODY) } else { title.to_string() };
+                draw_field(t, &contact.form, i, &title, placeholder, true, &chunks[i + 1]);
+            }
+            // It may quote what the visitor typed, which is no markup.
+            if let Some(ref status) = contact.status {
+                Paragraph::default()
+                    .block(Block::default().borders(Borders::ALL).border_style(Role::Focus.style()))
+                    .raw(true)
+                    .wrap(true)
+                    .text(status)
+                    .render(t, &chunks[5]);
+            }
+        });
+}
+
+/// `seconds` the way people say it, `2h 05m` or `3m 20s`.
+fn duration(seconds: u64) -> String {
+    match seconds {
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    }
+}
+
+fn draw_dashboard<W: Write>(t: &mut Screen<W>, stats: &Result<Stats, String>, area: &Rect) {
+    let stats = match *stats {
+        Ok(ref stats) => stats,
+        Err(ref err) => {
+            Paragraph::default()
+                .block(Block::default().borders(Borders::ALL).title("Visits").title_style(Role::Section.style()))
+                .wrap(true)
+                .text(&format!("\nCannot read the visits: {}", err))
+                .render(t, area);
+            return;
+        }
+    };
+    let average = stats.seconds.checked_div(stats.ended).unwrap_or(0);
+    let mut totals = format!(
+        "\n{} {}\n\n{} {}\n\n{} {}\n\n{} {}\n\n{} {}\n\n",
+        label("Sessions"),
+        stats.sessions,
+        label("Networks"),
+        stats.networks,
+        label("Average session"),
+        duration(average),
+        label("Exports"),
+        stats.exports,
+        label("Searches"),
+        stats.searches
+    );
+    for &(ref protocol, sessions) in &stats.protocols {
+        totals.push_str(&format!("{} {}\n", label(protocol), sessions));
+    }
+    let times: String = stats.tabs.iter().map(|tab| format!("{} {}\n", label(&tab.title), duration(tab.seconds))).collect();
+    let views: Vec<(&str, u64)> = stats.tabs.iter().map(|tab| (tab.title.as_str(), tab.views)).collect();
+    Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&[Size::Percent(60), Size::Percent(40)])
+        .render(t, area, |t, chunks| {
+            Group::default()
+            .direction(Direction::Horizontal)
+            .sizes(&[Size::Percent(25), Size::Percent(20), Size::Percent(55)])
+            .render(t, &chunks[0], |t, chunks| {
+                Paragraph::default()
+                .block(Block::default().borders(Borders::ALL).title("Totals").title_style(Role::Section.style()))
+                .wrap(true)
+                .text(&totals)
+                .render(t, &chunks[0]);
+                Paragraph::default()
+                .block(Block::default().borders(Borders::ALL).title("Time on tabs").title_style(Role::Section.style()))
+                .wrap(true)
+                .text(&format!("\n{}", times))
+                .render(t, &chunks[1]);
+                // Bars are as wide as the longest title, or as the room
+                // they have.
+                let room = chunks[2].width.saturating_sub(2) / views.len().max(1) as u16;
+                let widest = views.iter().map(|&(title, _)| title.chars().count() as u16).max().unwrap_or(1);
+                BarChart::default()
+                .block(Block::default().borders(Borders::ALL).title("Tabs opened").title_style(Role::Section.style()))
+                .data(&views)
+                .bar_width(widest.min(room.saturating_sub(1)).max(1))
+                .bar_gap(1)
+                .style(Role::Gauge.style())
+                .value_style(Role::Emphasis.style())
+                .label_style(Role::Heading.style())
+                .render(t, &chunks[2]);
+            });
+            // The latest hours that fit, the current one last.
+            let hours = (chunks[1].width.saturating_sub(2) as usize).min(stats.hourly.len());
+            let title = format!("Sessions an hour, the last {} hours", hours);
+            Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(&title).title_style(Role::Section.style()))
+                .data(&stats.hourly[stats.hourly.len() - hours..])
+                .style(Role::Focus.style())
+                .render(t, &chunks[1]);
+        });
+}
@@ -0,0 +1,28 @@
+//! What the program has to say about itself, on the standard error. Under
+//! inetd the standard error is the connection of the client as well, so a
+//! server started by it says nothing at all rather than talk in the middle
+//! of the protocol.
+
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SILENT: AtomicBool = AtomicBool::new(false);
+
+/// Drops whatever is said from now on, the messages of panics included.
+pub fn silence() {
+    SILENT.store(true, Ordering::SeqCst);
+    panic::set_hook(Box::new(|_| {}));
+}
+
+pub fn silent() -> bool {
+    SILENT.load(Ordering::SeqCst)
+}
+
+/// `eprintln!`, unless the program was silenced.
+macro_rules! note {
+    ($($arg:tt)*) => {
+        if !::note::silent() {
+            eprintln!($($arg)*);
+        }
+    };
+}
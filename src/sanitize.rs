@@ -0,0 +1,165 @@
+//! Text that reaches a terminal without being trusted: content files,
+//! imported resumes and what clients send. Control characters in it would
+//! be commands to the terminal of whoever reads it, able to change the
+//! title of the window or write to the clipboard, so they are shown as the
+//! symbols for them instead. Bidirectional overrides, which make text read
+//! other than it is, are removed.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+/// A character of a string that was made visible or removed, and how many
+/// times.
+pub struct Change {
+    /// Where the string is in the content file, `experience[0].title`.
+    pub path: String,
+    pub character: char,
+    pub count: usize,
+}
+
+impl Change {
+    pub fn describe(&self) -> String {
+        let what = match replacement(self.character) {
+            Some(symbol) => format!("shown as {}", symbol),
+            None => "removed".to_string(),
+        };
+        let times = if self.count > 1 { format!(" {} times", self.count) } else { String::new() };
+        format!("{}: {} {}{}", self.path, name(self.character), what, times)
+    }
+}
+
+/// The names of the characters that are most often found.
+fn name(c: char) -> String {
+    let name = match c {
+        '\u{0}' => "NUL",
+        '\u{7}' => "BEL",
+        '\u{8}' => "BS",
+        '\r' => "CR",
+        '\u{1b}' => "ESC",
+        '\u{7f}' => "DEL",
+        '\u{9b}' => "CSI",
+        '\u{202a}' => "LRE",
+        '\u{202b}' => "RLE",
+        '\u{202c}' => "PDF",
+        '\u{202d}' => "LRO",
+        '\u{202e}' => "RLO",
+        '\u{2066}' => "LRI",
+        '\u{2067}' => "RLI",
+        '\u{2068}' => "FSI",
+        '\u{2069}' => "PDI",
+        _ => return format!("U+{:04X}", c as u32),
+    };
+    format!("{} (U+{:04X})", name, c as u32)
+}
+
+fn bidi_override(c: char) -> bool {
+    match c {
+        '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' => true,
+        _ => false,
+    }
+}
+
+/// Whether `c` is left as it is. Line breaks and tabs are text.
+fn safe(c: char) -> bool {
+    c == '\n' || c == '\t' || !(c.is_control() || bidi_override(c))
+}
+
+/// What `c` is shown as, `None` when it is removed: the control pictures
+/// of Unicode for C0 characters and DEL, and the replacement character for
+/// the C1 ones.
+fn replacement(c: char) -> Option<char> {
+    match c as u32 {
+        code @ 0..=0x1f => ::std::char::from_u32(0x2400 + code),
+        0x7f => Some('\u{2421}'),
+        0x80..=0x9f => Some('\u{fffd}'),
+        _ => None,
+    }
+}
+
+/// `text` as it can be shown, with what was changed counted in `found`.
+fn clean_counting(text: &str, found: &mut BTreeMap<char, usize>) -> String {
+    let mut clean = String::with_capacity(text.len());
+    for c in text.chars() {
+        if safe(c) {
+            clean.push(c);
+            continue;
+        }
+        *found.entry(c).or_insert(0) += 1;
+        if let Some(symbol) = replacement(c) {
+            clean.push(symbol);
+        }
+    }
+    clean
+}
+
+/// `text` as it can be shown.
+pub fn clean(text: &str) -> String {
+    if text.chars().all(safe) {
+        return text.to_string();
+    }
+    clean_counting(text, &mut BTreeMap::new())
+}
+
+fn walk(value: &mut Value, path: &str, changes: &mut Vec<Change>) {
+    match *value {
+        Value::String(ref mut text) => {
+            if text.chars().all(safe) {
+                return;
+            }
+            let mut found = BTreeMap::new();
+            *text = clean_counting(text, &mut found);
+            for (character, count) in found {
+                changes.push(Change { path: path.to_string(), character, count });
+            }
+        }
+        Value::Array(ref mut items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                walk(item, &format!("{}[{}]", path, i), changes);
+            }
+        }
+        Value::Object(ref mut fields) => {
+            for (name, field) in fields.iter_mut() {
+                let path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                walk(field, &path, changes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Cleans every string of a content file, as JSON, and says what changed.
+pub fn json(value: &mut Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    walk(value, "", &mut changes);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_characters_are_shown() {
+        assert_eq!(clean("a\u{1b}]0;title\u{7}b"), "a\u{241b}]0;title\u{2407}b");
+        assert_eq!(clean("\u{0}\r\u{7f}"), "\u{2400}\u{240d}\u{2421}");
+        assert_eq!(clean("\u{9b}31m\u{85}"), "\u{fffd}31m\u{fffd}");
+        assert_eq!(clean("lines\n\tand tabs"), "lines\n\tand tabs");
+    }
+
+    #[test]
+    fn bidi_overrides_are_removed() {
+        assert_eq!(clean("invoice\u{202e}fdp.exe"), "invoicefdp.exe");
+        assert_eq!(clean("\u{2066}a\u{2069} \u{202a}b\u{202c}"), "a b");
+        // Marks that do not override anything stay.
+        assert_eq!(clean("a\u{200f}b"), "a\u{200f}b");
+    }
+
+    #[test]
+    fn changes_are_described() {
+        let mut value = json!({"name": "Ada\u{202e}", "experience": [{"title": "\u{1b}\u{1b}"}]});
+        let changes: Vec<String> = json(&mut value).iter().map(Change::describe).collect();
+        assert_eq!(changes, ["experience[0].title: ESC (U+001B) shown as \u{241b} 2 times", "name: RLO (U+202E) removed"]);
+        assert_eq!(value, json!({"name": "Ada", "experience": [{"title": "\u{241b}\u{241b}"}]}));
+    }
+}
@@ -0,0 +1,214 @@
+//! The curriculum over Finger (RFC 1288), a summary for `finger user@host`
+//! and the whole of it for `finger -l`, which sends `/W`.
+//!
+//! With a roster, a directory of content files, every file is a user named
+//! after it: `alice.json` answers `finger alice@host`, and an empty query
+//! lists them. Without one the content answers to any name. Queries for
+//! other hosts are refused, as the RFC recommends.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use cli::Listen;
+use cv::Cv;
+use export::ats;
+use sanitize;
+
+use super::limits::{Guard, Refusal};
+use super::Shared;
+
+/// Queries are a user name and a switch, anything longer is refused.
+const MAX_QUERY: usize = 512;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Host {
+    roster: Option<PathBuf>,
+    cv: Arc<Shared<Cv>>,
+}
+
+pub fn serve(listen: &Listen, roster: Option<String>, guard: Guard, cv: Arc<Shared<Cv>>) -> Result<(), io::Error> {
+    if let Some(ref roster) = roster {
+        if !Path::new(roster).is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: not a directory", roster)));
+        }
+    }
+    let host = Host { roster: roster.map(PathBuf::from), cv };
+    super::serve(listen, "finger", "Finger", guard, move |stream, _| connection(&host, stream), refuse)
+}
+
+fn refuse(mut stream: TcpStream, refusal: &Refusal) -> Result<(), io::Error> {
+    write!(stream, "finger: {}\r\n", refusal.message())
+}
+
+struct Query {
+    verbose: bool,
+    user: String,
+    /// Whether it is for another host, `user@host`.
+    forward: bool,
+}
+
+/// Parses `[/W] [user][@host...]`.
+fn parse(line: &str) -> Query {
+    let mut words = line.split_whitespace().peekable();
+    let verbose = match words.peek() {
+        Some(word) if word.eq_ignore_ascii_case("/W") => {
+            words.next();
+            true
+        }
+        _ => false,
+    };
+    let rest: Vec<&str> = words.collect();
+    let target = rest.join(" ");
+    let (user, forward) = match target.find('@') {
+        Some(i) => (target[..i].to_string(), true),
+        None => (target, false),
+    };
+    Query { verbose, user, forward }
+}
+
+/// User names are file names in the roster, so nothing that could leave it.
+fn valid(user: &str) -> bool {
+    !user.is_empty()
+        && !user.starts_with('.')
+        && user.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+impl Host {
+    /// The users of the roster, sorted.
+    fn users(&self, roster: &Path) -> Result<Vec<String>, io::Error> {
+        let mut users = Vec::new();
+        for entry in fs::read_dir(roster)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |e| e == "json") {
+                if let Some(user) = path.file_stem().and_then(|s| s.to_str()).filter(|u| valid(u)) {
+                    users.push(user.to_string());
+                }
+            }
+        }
+        users.sort();
+        Ok(users)
+    }
+
+    fn answer(&self, query: &Query) -> Result<String, io::Error> {
+        if query.forward {
+            return Ok("Finger forwarding service denied.\n".to_string());
+        }
+        let roster = match self.roster {
+            Some(ref roster) => roster,
+            None => return Ok(answer(&self.cv.get(), &query.user, query.verbose)),
+        };
+        if query.user.is_empty() {
+            let mut list = String::new();
+            for user in self.users(roster)? {
+                // A file that cannot be read is still listed.
+                let headline = Cv::load(&roster.join(format!("{}.json", user)).to_string_lossy())
+                    .map(|cv| format!("{}, {}", cv.name, cv.headline))
+                    .unwrap_or_default();
+                list.push_str(&format!("{:<16} {}\n", user, headline));
+            }
+            return Ok(list);
+        }
+        let path = roster.join(format!("{}.json", query.user));
+        if !valid(&query.user) || !path.is_file() {
+            return Ok(format!("finger: {}: no such user.\n", query.user));
+        }
+        let cv = Cv::load(&path.to_string_lossy())?;
+        Ok(answer(&cv, &query.user, query.verbose))
+    }
+}
+
+/// A summary of the curriculum, or the whole of it as plain text.
+fn answer(cv: &Cv, user: &str, verbose: bool) -> String {
+    let login = if user.is_empty() { cv.short_name.to_lowercase().replace(' ', ".") } else { user.to_string() };
+    let mut text = format!("Login: {:<24} Name: {}\n", login, cv.name);
+    if verbose {
+        let mut full = Vec::new();
+        ats::write(cv, &mut full).expect("writing to memory does not fail");
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&full));
+        return text;
+    }
+    if !cv.headline.is_empty() {
+        text.push_str(&format!("{}\n", cv.headline));
+    }
+    if !cv.location.is_empty() {
+        text.push_str(&format!("Location: {}\n", cv.location));
+    }
+    text.push('\n');
+    for contact in &cv.contacts {
+        text.push_str(&format!("{}: {}\n", contact.kind.label(), contact.value));
+    }
+    if !cv.looking_for.headline.is_empty() {
+        text.push_str(&format!("\nPlan:\n{}\n", cv.looking_for.headline));
+    }
+    text.push_str("\nUse finger -l for the whole curriculum.\n");
+    text
+}
+
+/// Lines end with CRLF, and control characters are made visible so no
+/// answer can drive the terminal of the client (RFC 1288, section 3.3).
+fn network_text(text: &str) -> Vec<u8> {
+    sanitize::clean(text).replace('\n', "\r\n").into_bytes()
+}
+
+fn connection(host: &Host, mut stream: TcpStream) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line = Vec::new();
+    let mut buf = [0u8; 256];
+    while !line.contains(&b'\n') {
+        if line.len() > MAX_QUERY {
+            return stream.write_all(b"finger: query too long\r\n");
+        }
+        match stream.read(&mut buf)? {
+            0 => break,
+            n => line.extend_from_slice(&buf[..n]),
+        }
+    }
+    let end = line.iter().position(|&b| b == b'\r' || b == b'\n').unwrap_or(line.len());
+    let query = parse(&String::from_utf8_lossy(&line[..end]));
+    let started = Instant::now();
+    let answer = match host.answer(&query) {
+        Ok(answer) => answer,
+        Err(err) => {
+            stream.write_all(b"finger: the curriculum cannot be read\r\n")?;
+            return Err(err);
+        }
+    };
+    super::rendered(started.elapsed());
+    stream.write_all(&network_text(&answer))?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+
+    fn host(roster: Option<&str>) -> Host {
+        Host { roster: roster.map(PathBuf::from), cv: Arc::new(Shared { current: RwLock::new(Arc::new(Cv::default())) }) }
+    }
+
+    #[test]
+    fn parses_forwarding() {
+        let query = parse("/W alice@example.com");
+        assert!(query.verbose && query.forward);
+        assert_eq!(query.user, "alice");
+        assert!(parse("@a@b").forward);
+        assert!(!parse("alice").forward);
+    }
+
+    #[test]
+    fn refuses_forwarding() {
+        // The roster is not even looked at.
+        for host in &[host(None), host(Some("/nonexistent"))] {
+            for line in &["alice@example.com", "/W @example.com", "alice@localhost@example.com"] {
+                assert_eq!(host.answer(&parse(line)).unwrap(), "Finger forwarding service denied.\n");
+            }
+        }
+        assert!(host(None).answer(&parse("alice")).unwrap().starts_with("Login: alice "));
+    }
+}
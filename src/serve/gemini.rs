@@ -0,0 +1,180 @@
+//! The curriculum over Gemini, as gemtext: `/` has all of it and
+//! `/experience` and the like a single section.
+//!
+//! Gemini clients trust the certificate they see first, so a self-signed
+//! one does; it is created with its key when there is none, and has to be
+//! kept for clients to keep trusting the server.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rcgen;
+use rustls::internal::pemfile;
+use rustls::{NoClientAuth, ServerConfig, ServerSession, Session, StreamOwned};
+
+use cv::Cv;
+use export::{gemtext, Section};
+
+use super::limits::{Guard, Refusal};
+use super::Shared;
+
+/// Requests are a URL of at most 1024 bytes and CRLF.
+const MAX_REQUEST: usize = 1026;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Host {
+    /// Requests for other hosts are refused, when it is known.
+    hostname: Option<String>,
+    cv: Arc<Shared<Cv>>,
+}
+
+fn invalid(path: &str, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, message))
+}
+
+/// Creates a self-signed certificate for `hostname` and its key, the key
+/// readable by its owner only.
+fn create(certificate: &str, key: &str, hostname: &str) -> Result<(), io::Error> {
+    let generated = rcgen::generate_simple_self_signed(vec![hostname.to_string()])
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("could not create a certificate: {}", err)))?;
+    let pem = generated
+        .serialize_pem()
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("could not create a certificate: {}", err)))?;
+    let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(key)?;
+    file.write_all(generated.serialize_private_key_pem().as_bytes())?;
+    File::create(certificate)?.write_all(pem.as_bytes())
+}
+
+/// The TLS configuration with the certificate and key in these files,
+/// which are created when neither exists.
+fn config(certificate: &str, key: &str, hostname: &str) -> Result<ServerConfig, io::Error> {
+    match (Path::new(certificate).exists(), Path::new(key).exists()) {
+        (false, false) => create(certificate, key, hostname)?,
+        (true, false) => return Err(invalid(key, "missing key of the certificate")),
+        (false, true) => return Err(invalid(certificate, "missing certificate of the key")),
+        (true, true) => {}
+    }
+    let chain = pemfile::certs(&mut BufReader::new(File::open(certificate)?)).map_err(|_| invalid(certificate, "not a PEM certificate"))?;
+    if chain.is_empty() {
+        return Err(invalid(certificate, "no certificate"));
+    }
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(key)?)).map_err(|_| invalid(key, "not a PEM key"))?;
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(key)?)).map_err(|_| invalid(key, "not a PEM key"))?;
+    }
+    let key = keys.into_iter().next().ok_or_else(|| invalid(key, "no private key"))?;
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(chain, key).map_err(|err| invalid(certificate, &err.to_string()))?;
+    Ok(config)
+}
+
+pub fn serve(
+    listen: &str,
+    certificate: &str,
+    key: &str,
+    hostname: Option<String>,
+    guard: Guard,
+    cv: Arc<Shared<Cv>>,
+) -> Result<(), io::Error> {
+    let config = Arc::new(config(certificate, key, hostname.as_ref().map_or("localhost", |h| h.as_str()))?);
+    let listener = super::listener(listen)?;
+    note!("rust-cv: serving Gemini on {}", listener.local_addr()?);
+    let host = Host { hostname, cv };
+    let refusing = config.clone();
+    super::accept(
+        listener,
+        "gemini",
+        guard,
+        move |stream, _| connection(&config, stream, |url| host.answer(url)),
+        move |stream, refusal| connection(&refusing, stream, |_| refuse(refusal)),
+    )
+}
+
+impl Host {
+    /// The header and body of the answer to `request`.
+    fn answer(&self, request: &str) -> (String, Vec<u8>) {
+        let rest = match request.find("://") {
+            Some(i) if request[..i].eq_ignore_ascii_case("gemini") => &request[i + 3..],
+            Some(_) => return ("53 Proxy request refused".to_string(), Vec::new()),
+            None => return ("59 Bad request".to_string(), Vec::new()),
+        };
+        let rest = rest.split(|c| c == '?' || c == '#').next().unwrap_or("");
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let host = authority.rsplit('@').next().unwrap_or("");
+        let host = match host.rfind(':') {
+            Some(i) if !host.ends_with(']') => &host[..i],
+            _ => host,
+        };
+        if let Some(ref hostname) = self.hostname {
+            if !host.eq_ignore_ascii_case(hostname) {
+                return ("53 Proxy request refused".to_string(), Vec::new());
+            }
+        }
+
+        let (sections, links) = match path.trim_matches('/') {
+            "" => {
+                let links: Vec<(String, &str)> = Section::all().iter().map(|s| (format!("/{}", s.name()), s.title())).collect();
+                (Section::all().to_vec(), links)
+            }
+            name => match name.parse::<Section>() {
+                Ok(section) => (vec![section], vec![("/".to_string(), "Everything")]),
+                Err(_) => return ("51 Not found".to_string(), Vec::new()),
+            },
+        };
+        let started = Instant::now();
+        let mut body = Vec::new();
+        gemtext::write(&self.cv.get(), &sections, &links, &mut body).expect("writing to memory does not fail");
+        super::rendered(started.elapsed());
+        ("20 text/gemini; charset=utf-8".to_string(), body)
+    }
+}
+
+/// The header of the answer to any request that is refused.
+fn refuse(refusal: &Refusal) -> (String, Vec<u8>) {
+    let header = match (refusal, refusal.retry()) {
+        (&Refusal::Busy, _) => format!("41 {}", refusal.message()),
+        (_, Some(seconds)) => format!("44 {}", seconds),
+        (_, None) => format!("50 {}", refusal.message()),
+    };
+    (header, Vec::new())
+}
+
+/// Reads the request and sends what `answer` has for its URL.
+fn connection<A>(config: &Arc<ServerConfig>, stream: TcpStream, answer: A) -> Result<(), io::Error>
+where
+    A: Fn(&str) -> (String, Vec<u8>),
+{
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut tls = StreamOwned::new(ServerSession::new(config), stream);
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.ends_with(b"\r\n") {
+        if request.len() > MAX_REQUEST {
+            break;
+        }
+        match tls.read(&mut buf)? {
+            0 => return Ok(()),
+            n => request.extend_from_slice(&buf[..n]),
+        }
+    }
+    let (header, body) = match request.len() {
+        length if length > MAX_REQUEST => ("59 Request too long".to_string(), Vec::new()),
+        length => match String::from_utf8(request[..length - 2].to_vec()) {
+            Ok(url) => answer(&url),
+            Err(_) => ("59 Bad request".to_string(), Vec::new()),
+        },
+    };
+    write!(tls, "{}\r\n", header)?;
+    tls.write_all(&body)?;
+    // Gemini takes the end of TLS as the end of the body.
+    tls.sess.send_close_notify();
+    tls.flush()
+}
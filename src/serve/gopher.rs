@@ -0,0 +1,173 @@
+//! The curriculum as a Gopher hole (RFC 1436): a menu with an item per tab
+//! of the terminal UI, each a text file, and the contact details as URL
+//! items (`URL:` selectors, as most clients take them).
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use cv::ContactKind;
+use export::Section;
+use figlet;
+use sanitize;
+use yank;
+use Content;
+use TABS;
+
+use super::limits::{Guard, Refusal};
+use super::Shared;
+
+/// Selectors are short paths, anything longer is refused.
+const MAX_SELECTOR: usize = 255;
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Gopher clients are assumed to be as wide as the menus they show.
+const WIDTH: u16 = 70;
+
+struct Host {
+    /// The host name and port that menus link to.
+    name: String,
+    port: u16,
+    content: Arc<Shared<Content>>,
+}
+
+pub fn serve(listen: &str, hostname: Option<String>, guard: Guard, content: Arc<Shared<Content>>) -> Result<(), io::Error> {
+    let listener = super::listener(listen)?;
+    let address = listener.local_addr()?;
+    note!("rust-cv: serving Gopher on {}", address);
+    let name = hostname.unwrap_or_else(|| match address.ip() {
+        ip if ip.is_unspecified() => "localhost".to_string(),
+        ip => ip.to_string(),
+    });
+    let host = Arc::new(Host { name, port: address.port(), content });
+    let refusing = host.clone();
+    super::accept(
+        listener,
+        "gopher",
+        guard,
+        move |stream, _| connection(&host, stream),
+        move |stream, refusal| refuse(&refusing, stream, refusal),
+    )
+}
+
+/// The selector of every tab, the names of the sections after the Welcome
+/// tab.
+fn selector(tab: usize) -> String {
+    match tab {
+        0 => "/welcome".to_string(),
+        _ => format!("/{}", Section::all()[tab - 1].name()),
+    }
+}
+
+/// Menu lines have no tabs of their own, and no line breaks.
+fn field(text: &str) -> String {
+    text.chars().map(|c| if c == '\t' || c == '\r' || c == '\n' { ' ' } else { c }).collect()
+}
+
+impl Host {
+    fn item(&self, kind: char, display: &str, selector: &str) -> String {
+        format!("{}{}\t{}\t{}\t{}\r\n", kind, field(display), field(selector), self.name, self.port)
+    }
+
+    /// A line of text in a menu.
+    fn info(&self, text: &str) -> String {
+        format!("i{}\t\t{}\t{}\r\n", field(text), self.name, self.port)
+    }
+
+    fn menu(&self, content: &Content) -> String {
+        let cv = &content.cv;
+        let mut menu = String::new();
+        menu.push_str(&self.info(&cv.name));
+        if !cv.headline.is_empty() {
+            menu.push_str(&self.info(&cv.headline));
+        }
+        menu.push_str(&self.info(""));
+        for (tab, title) in TABS.iter().enumerate() {
+            menu.push_str(&self.item('0', title, &selector(tab)));
+        }
+        if !cv.contacts.is_empty() {
+            menu.push_str(&self.info(""));
+            menu.push_str(&self.info("Contact"));
+        }
+        for contact in &cv.contacts {
+            let label = format!("{}: {}", contact.kind.label(), contact.value);
+            match contact.kind {
+                // A phone number is no URL that clients can open.
+                ContactKind::Phone => menu.push_str(&self.info(&label)),
+                _ => menu.push_str(&self.item('h', &label, &format!("URL:{}", contact.url()))),
+            }
+        }
+        menu.push_str(".\r\n");
+        menu
+    }
+
+    /// The Welcome tab is the name banner; the others are what `y` copies
+    /// of them, panel after panel.
+    fn text(content: &Content, tab: usize) -> String {
+        let cv = &content.cv;
+        let mut text = String::new();
+        if tab == 0 {
+            let name = vec![cv.short_name.clone()];
+            let banner = figlet::banner(&content.fonts, &cv.short_name, WIDTH, 12).unwrap_or(name);
+            for line in banner {
+                text.push_str(line.trim_end());
+                text.push('\n');
+            }
+            text.push_str(&format!("\n{}\n{}\n", cv.name, cv.headline));
+            return text;
+        }
+        for (i, item) in yank::items(cv, tab).iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(&format!("{}\n{}\n\n{}\n", item.title, "=".repeat(item.title.chars().count()), item.text));
+        }
+        text
+    }
+}
+
+/// A text file as Gopher sends it: lines end with CRLF, those starting
+/// with a dot get another one, and a line with a dot ends it.
+fn text_file(text: &str) -> String {
+    let mut file = String::with_capacity(text.len() + 16);
+    for line in text.lines() {
+        if line.starts_with('.') {
+            file.push('.');
+        }
+        file.push_str(line);
+        file.push_str("\r\n");
+    }
+    file.push_str(".\r\n");
+    file
+}
+
+/// An error item, whatever the selector.
+fn refuse(host: &Host, mut stream: TcpStream, refusal: &Refusal) -> Result<(), io::Error> {
+    write!(stream, "3{}\t\t{}\t{}\r\n.\r\n", refusal.message(), host.name, host.port)
+}
+
+fn connection(host: &Host, mut stream: TcpStream) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line = Vec::new();
+    let mut buf = [0u8; 256];
+    while !line.contains(&b'\n') && line.len() <= MAX_SELECTOR {
+        match stream.read(&mut buf)? {
+            0 => break,
+            n => line.extend_from_slice(&buf[..n]),
+        }
+    }
+    let end = line.iter().position(|&b| b == b'\r' || b == b'\n' || b == b'\t').unwrap_or(line.len());
+    let selector = String::from_utf8_lossy(&line[..end]).into_owned();
+    let content = host.content.get();
+    let started = Instant::now();
+    let answer = match selector.as_str() {
+        "" | "/" => host.menu(&content),
+        selector => match (0..TABS.len()).find(|&tab| self::selector(tab) == selector) {
+            Some(tab) => text_file(&Host::text(&content, tab)),
+            None => format!("3'{}' does not exist\t\t{}\t{}\r\n.\r\n", field(&sanitize::clean(selector)), host.name, host.port),
+        },
+    };
+    super::rendered(started.elapsed());
+    stream.write_all(answer.as_bytes())?;
+    stream.flush()
+}
@@ -0,0 +1,246 @@
+//! The curriculum over HTTP, in what suits the client: coloured text for
+//! `curl`, `wget` and HTTPie, JSON when it is asked for and an HTML page for
+//! everything else, browsers included.
+//!
+//! `/` has the whole curriculum and `/experience` and the like a single
+//! section. Text is as wide as `?width=` says.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{self, Map, Value};
+
+use cli::Listen;
+use cv::Cv;
+use export::{ansi, html, Section};
+use sanitize;
+use visits::{Action, Visit};
+use TABS;
+
+use super::limits::{Guard, Refusal};
+use super::Shared;
+
+/// Requests are a line and a few headers, anything longer is refused.
+const MAX_REQUEST: usize = 16 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_WIDTH: usize = 20;
+const MAX_WIDTH: usize = 500;
+
+/// Clients whose `User-Agent` starts with one of these show what they get
+/// in a terminal.
+const TERMINAL_CLIENTS: &[&str] = &["curl/", "wget/", "httpie/"];
+
+pub fn serve(listen: &Listen, guard: Guard, cv: Arc<Shared<Cv>>) -> Result<(), io::Error> {
+    super::serve(listen, "http", "HTTP", guard, move |stream, visit| connection(&cv.get(), stream, visit), refuse)
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> &str {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map_or("", |(_, v)| v.as_str())
+    }
+
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.query.split('&').filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(n), value) if n == name => Some(value.unwrap_or("")),
+                _ => None,
+            }
+        }).next()
+    }
+}
+
+/// Reads the request line and the headers, `None` when they are not HTTP.
+fn read_request(stream: &mut TcpStream) -> Result<Option<Request>, io::Error> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 2048];
+    while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+        if data.len() > MAX_REQUEST {
+            return Ok(None);
+        }
+        match stream.read(&mut buf)? {
+            0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the request ended early")),
+            n => data.extend_from_slice(&buf[..n]),
+        }
+    }
+    let text = String::from_utf8_lossy(&data);
+    let mut lines = text.split("\r\n");
+    let line: Vec<&str> = lines.next().unwrap_or("").split(' ').collect();
+    let (method, target) = match line[..] {
+        [method, target, version] if version.starts_with("HTTP/1.") => (method, target),
+        _ => return Ok(None),
+    };
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[..i], &target[i + 1..]),
+        None => (target, ""),
+    };
+    let headers = lines
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| {
+            let mut parts = l.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => Some((name.trim().to_string(), value.trim().to_string())),
+                _ => None,
+            }
+        })
+        .collect();
+    Ok(Some(Request { method: method.to_string(), path: path.to_string(), query: query.to_string(), headers }))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ansi,
+    Html,
+    Json,
+}
+
+impl Kind {
+    fn of(request: &Request) -> Kind {
+        let agent = request.header("User-Agent").to_lowercase();
+        if request.header("Accept").contains("application/json") {
+            Kind::Json
+        } else if TERMINAL_CLIENTS.iter().any(|c| agent.starts_with(c)) {
+            Kind::Ansi
+        } else {
+            Kind::Html
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match *self {
+            Kind::Ansi => "text/plain; charset=utf-8",
+            Kind::Html => "text/html; charset=utf-8",
+            Kind::Json => "application/json",
+        }
+    }
+}
+
+/// The part of the content file with what a section shows, or what they
+/// all show together. The rest of the file, such as where the photo is or
+/// what imports kept, is not for visitors.
+fn json(cv: &Cv, sections: &[Section]) -> Value {
+    match *sections {
+        [Section::Personal] => json!({
+            "name": cv.name,
+            "short_name": cv.short_name,
+            "headline": cv.headline,
+            "birth_date": cv.birth_date,
+            "nationality": cv.nationality,
+            "location": cv.location,
+            "about": cv.about,
+            "contacts": cv.contacts,
+            "languages": cv.languages,
+            "studies": cv.studies,
+        }),
+        [Section::Skills] => json!({ "skills": cv.skills, "tools": cv.tools }),
+        [Section::Experience] => json!({ "experience": cv.experience }),
+        [Section::Courses] => json!({ "courses": cv.courses }),
+        [Section::LookingFor] => json!({ "looking_for": cv.looking_for }),
+        _ => {
+            let mut all = Map::new();
+            for &section in sections {
+                if let Value::Object(fields) = json(cv, &[section]) {
+                    all.extend(fields);
+                }
+            }
+            Value::Object(all)
+        }
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head: bool) -> Result<(), io::Error> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nVary: User-Agent, Accept\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+fn error(stream: &mut TcpStream, status: &str, message: &str, head: bool) -> Result<(), io::Error> {
+    respond(stream, status, "text/plain; charset=utf-8", format!("{}\n", sanitize::clean(message)).as_bytes(), head)
+}
+
+/// Answers the request, whatever it is, with why it is refused.
+fn refuse(mut stream: TcpStream, refusal: &Refusal) -> Result<(), io::Error> {
+    read_request(&mut stream)?;
+    let status = match *refusal {
+        Refusal::Busy => "503 Service Unavailable",
+        Refusal::TooFast(_) => "429 Too Many Requests",
+        Refusal::Banned(_) => "403 Forbidden",
+    };
+    let body = format!("{}\n", refusal.message());
+    let retry = refusal.retry().map_or(String::new(), |seconds| format!("Retry-After: {}\r\n", seconds));
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        retry,
+        body
+    )?;
+    stream.flush()
+}
+
+fn connection(cv: &Cv, mut stream: TcpStream, visit: &Visit) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let request = match read_request(&mut stream)? {
+        Some(request) => request,
+        None => return error(&mut stream, "400 Bad Request", "Bad request", false),
+    };
+    let head = request.method == "HEAD";
+    if request.method != "GET" && !head {
+        return error(&mut stream, "405 Method Not Allowed", "Only GET and HEAD are allowed", false);
+    }
+
+    let sections = match request.path.trim_matches('/') {
+        "" => Section::all().to_vec(),
+        name => match name.parse::<Section>() {
+            Ok(section) => vec![section],
+            Err(err) => return error(&mut stream, "404 Not Found", &err, head),
+        },
+    };
+    let width = match request.parameter("width") {
+        Some(width) => match width.parse::<usize>() {
+            Ok(width) => width.max(MIN_WIDTH).min(MAX_WIDTH),
+            Err(_) => return error(&mut stream, "400 Bad Request", "'width' takes a number of columns", head),
+        },
+        None => ansi::WIDTH,
+    };
+
+    let kind = Kind::of(&request);
+    // The whole curriculum is what the Welcome tab leads to.
+    visit.record(Action::Tab(match sections[..] {
+        [section] => section.title(),
+        _ => TABS[0],
+    }));
+    if kind == Kind::Json {
+        visit.record(Action::Export("JSON"));
+    }
+    let started = Instant::now();
+    let mut body = Vec::new();
+    match kind {
+        Kind::Ansi => ansi::write(cv, &sections, width, &mut body)?,
+        Kind::Html => html::write(cv, &sections, &mut body)?,
+        Kind::Json => {
+            serde_json::to_writer_pretty(&mut body, &json(cv, &sections)).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            body.push(b'\n');
+        }
+    }
+    super::rendered(started.elapsed());
+    respond(&mut stream, "200 OK", kind.content_type(), &body, head)
+}
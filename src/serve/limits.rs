@@ -0,0 +1,392 @@
+//! What keeps a public server up: a cap on the connections handled at once,
+//! a token bucket of connections for every address, and bans, from a list
+//! or for addresses that keep connecting too fast.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cli::Limits;
+use visits::{Log, Visit};
+
+use super::sandbox::Sandbox;
+
+/// The connections refused in a row for coming too fast after which an
+/// address is banned.
+const STRIKES: u32 = 5;
+/// How many addresses are remembered before the ones that have nothing to
+/// remember are forgotten.
+const REMEMBERED: usize = 4096;
+/// What busy servers ask clients to wait.
+const BUSY_RETRY: Duration = Duration::from_secs(60);
+/// How often what keeps a server up to date runs.
+pub const REFRESH: Duration = Duration::from_secs(2);
+
+/// Why a connection is refused.
+pub enum Refusal {
+    Busy,
+    /// The address connects too fast, and has a connection again after
+    /// this long.
+    TooFast(Duration),
+    /// The address is banned, for this long unless it is in the ban list.
+    Banned(Option<Duration>),
+}
+
+/// Whole seconds, rounded up.
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + if duration.subsec_nanos() > 0 { 1 } else { 0 }
+}
+
+impl Refusal {
+    /// What the client is told, a sentence.
+    pub fn message(&self) -> String {
+        match *self {
+            Refusal::Busy => "The server is busy, please try again in a few minutes.".to_string(),
+            Refusal::TooFast(wait) => {
+                format!("Too many connections from your address, please try again in {} seconds.", seconds(wait))
+            }
+            Refusal::Banned(Some(wait)) => {
+                format!("Your address connected too often, it is refused for {} seconds.", seconds(wait))
+            }
+            Refusal::Banned(None) => "Connections from your address are not accepted.".to_string(),
+        }
+    }
+
+    /// The seconds to wait before trying again, when it is worth it.
+    pub fn retry(&self) -> Option<u64> {
+        match *self {
+            Refusal::Busy => Some(seconds(BUSY_RETRY)),
+            Refusal::TooFast(wait) | Refusal::Banned(Some(wait)) => Some(cmp::max(seconds(wait), 1)),
+            Refusal::Banned(None) => None,
+        }
+    }
+}
+
+/// An address or a network of the ban list, `192.0.2.0/24` or `2001:db8::/32`.
+struct Network {
+    address: IpAddr,
+    prefix: u32,
+}
+
+/// Whether `a` and `b` have the same first `prefix` bits.
+fn same_prefix(a: u128, b: u128, bits: u32, prefix: u32) -> bool {
+    prefix == 0 || (a ^ b) >> (bits - prefix) == 0
+}
+
+impl Network {
+    fn parse(text: &str) -> Option<Network> {
+        let mut parts = text.splitn(2, '/');
+        let address: IpAddr = parts.next()?.parse().ok()?;
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix.parse().ok().filter(|&p| p <= bits)?,
+            None => bits,
+        };
+        Some(Network { address, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                same_prefix(u32::from(network).into(), u32::from(ip).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => same_prefix(u128::from(network), u128::from(ip), 128, self.prefix),
+            _ => false,
+        }
+    }
+}
+
+fn ban_list(path: &str) -> Result<Vec<Network>, io::Error> {
+    let mut networks = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let network = Network::parse(line).ok_or_else(|| {
+            let message = format!("{}:{}: '{}' is not an address or a network", path, number + 1, line);
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })?;
+        networks.push(network);
+    }
+    Ok(networks)
+}
+
+/// IPv4 clients of servers listening on IPv6 have mapped addresses, which
+/// are taken for what they are.
+fn unmapped(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, high, low] => {
+                IpAddr::V4(Ipv4Addr::new((high >> 8) as u8, high as u8, (low >> 8) as u8, low as u8))
+            }
+            _ => IpAddr::V6(v6),
+        },
+        ip => ip,
+    }
+}
+
+/// What is known of an address.
+struct Peer {
+    /// The connections it may make now, filled up at the rate.
+    tokens: f64,
+    updated: Instant,
+    strikes: u32,
+    banned_until: Option<Instant>,
+}
+
+struct State {
+    sessions: usize,
+    refusals: usize,
+    peers: HashMap<IpAddr, Peer>,
+}
+
+/// What runs beside the connections of a server.
+type Work = Box<dyn FnOnce() + Send>;
+/// What keeps a server up to date.
+type Refresh = Box<dyn FnMut() + Send>;
+
+/// The limits of a server, its sandbox and the log of its visits, shared
+/// by all its connections.
+#[derive(Clone)]
+pub struct Guard {
+    limits: Limits,
+    banned: Arc<Vec<Network>>,
+    state: Arc<Mutex<State>>,
+    sandbox: Arc<Sandbox>,
+    log: Option<Arc<Log>>,
+    /// Waits for the sandbox, which only holds for the threads started
+    /// after it.
+    beside: Arc<Mutex<Vec<Work>>>,
+    refresh: Arc<Mutex<Vec<Refresh>>>,
+}
+
+/// A connection being handled, or refused; it is counted until dropped.
+pub struct Permit {
+    state: Arc<Mutex<State>>,
+    refusal: bool,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if self.refusal {
+            state.refusals -= 1;
+        } else {
+            state.sessions -= 1;
+        }
+    }
+}
+
+impl Guard {
+    pub fn new(limits: &Limits, sandbox: Sandbox, log: Option<Log>) -> Result<Guard, io::Error> {
+        let banned = match limits.ban_list {
+            Some(ref path) => ban_list(path)?,
+            None => Vec::new(),
+        };
+        let state = State { sessions: 0, refusals: 0, peers: HashMap::new() };
+        Ok(Guard {
+            limits: limits.clone(),
+            banned: Arc::new(banned),
+            state: Arc::new(Mutex::new(state)),
+            sandbox: Arc::new(sandbox),
+            log: log.map(Arc::new),
+            beside: Arc::new(Mutex::new(Vec::new())),
+            refresh: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Has `work` run on a thread of its own once the server is sandboxed.
+    pub fn beside<F: FnOnce() + Send + 'static>(&self, work: F) {
+        self.beside.lock().unwrap().push(Box::new(work));
+    }
+
+    /// Has `work` run every `REFRESH` once the server runs: in the process
+    /// that forks connections when there is one, as it is where they get
+    /// what they have from.
+    pub fn refresh<F: FnMut() + Send + 'static>(&self, work: F) {
+        self.refresh.lock().unwrap().push(Box::new(work));
+    }
+
+    /// Runs what keeps the server up to date.
+    pub fn refreshed(&self) {
+        for work in self.refresh.lock().unwrap().iter_mut() {
+            work();
+        }
+    }
+
+    /// Enters the sandbox, and starts what runs beside the connections.
+    pub fn enter(&self) -> Result<(), io::Error> {
+        self.sandbox.enter(false)?;
+        for work in self.beside.lock().unwrap().drain(..) {
+            thread::spawn(work);
+        }
+        if !self.sandbox.forks() && !self.refresh.lock().unwrap().is_empty() {
+            let guard = self.clone();
+            thread::spawn(move || loop {
+                thread::sleep(REFRESH);
+                guard.refreshed();
+            });
+        }
+        Ok(())
+    }
+
+    pub fn sandbox(&self) -> Arc<Sandbox> {
+        self.sandbox.clone()
+    }
+
+    /// Starts the visit of a client at `ip`.
+    pub fn visit(&self, protocol: &'static str, ip: IpAddr) -> Visit {
+        Visit::begin(&self.log, protocol, unmapped(ip))
+    }
+
+    /// The file of the log, which processes of their own keep.
+    pub fn log_fd(&self) -> Option<RawFd> {
+        self.log.as_ref().map(|log| log.as_raw_fd())
+    }
+
+    /// How long any connection may last.
+    pub fn session(&self) -> Duration {
+        self.limits.session
+    }
+
+    /// The tokens of `peer` now.
+    fn refill(&self, peer: &Peer, now: Instant) -> f64 {
+        let elapsed = now.duration_since(peer.updated);
+        let earned = (elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9) * f64::from(self.limits.rate) / 60.0;
+        (peer.tokens + earned).min(f64::from(self.limits.burst))
+    }
+
+    /// Takes a connection from `ip`, or says why it is refused.
+    pub fn admit(&self, ip: IpAddr) -> Result<Permit, Refusal> {
+        let ip = unmapped(ip);
+        if self.banned.iter().any(|network| network.contains(ip)) {
+            return Err(Refusal::Banned(None));
+        }
+        let now = Instant::now();
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let burst = f64::from(self.limits.burst);
+        if state.peers.len() >= REMEMBERED {
+            state.peers.retain(|_, peer| peer.banned_until.map_or(false, |until| until > now) || self.refill(peer, now) < burst);
+        }
+
+        let peer = state.peers.entry(ip).or_insert(Peer { tokens: burst, updated: now, strikes: 0, banned_until: None });
+        if let Some(until) = peer.banned_until {
+            if until > now {
+                return Err(Refusal::Banned(Some(until - now)));
+            }
+            peer.banned_until = None;
+            peer.strikes = 0;
+        }
+        peer.tokens = self.refill(peer, now);
+        peer.updated = now;
+        if peer.tokens < 1.0 {
+            peer.strikes += 1;
+            if peer.strikes >= STRIKES && self.limits.ban_time > Duration::from_secs(0) {
+                peer.banned_until = Some(now + self.limits.ban_time);
+                note!("rust-cv: banning {} for {} seconds", ip, seconds(self.limits.ban_time));
+                return Err(Refusal::Banned(Some(self.limits.ban_time)));
+            }
+            let wait = (1.0 - peer.tokens) * 60.0 / f64::from(self.limits.rate);
+            return Err(Refusal::TooFast(Duration::from_millis((wait * 1000.0) as u64)));
+        }
+        peer.tokens -= 1.0;
+        peer.strikes = 0;
+        if state.sessions >= self.limits.sessions {
+            return Err(Refusal::Busy);
+        }
+        state.sessions += 1;
+        Ok(Permit { state: self.state.clone(), refusal: false })
+    }
+
+    /// Room to tell a client why it is refused. Refusals are counted apart
+    /// from sessions, and there are no more of them at once than there can
+    /// be sessions; beyond that connections are closed without a word.
+    pub fn refusal(&self) -> Option<Permit> {
+        let mut state = self.state.lock().unwrap();
+        if state.refusals >= self.limits.sessions {
+            return None;
+        }
+        state.refusals += 1;
+        Some(Permit { state: self.state.clone(), refusal: true })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cli;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+    /// A connection a second, two in a row, and bans of ten seconds.
+    fn guard() -> Guard {
+        let limits = Limits {
+            sessions: 10,
+            rate: 60,
+            burst: 2,
+            idle: Duration::from_secs(60),
+            session: Duration::from_secs(60),
+            ban_list: None,
+            ban_time: Duration::from_secs(10),
+        };
+        let options = cli::Sandbox { user: None, log_dir: None, fork: false, unsandboxed: true };
+        Guard::new(&limits, Sandbox::new(&options, Vec::new(), Vec::new(), Duration::from_secs(1)), None).unwrap()
+    }
+
+    /// Moves what is known of the address `by` into the past.
+    fn rewind(guard: &Guard, by: Duration) {
+        let mut state = guard.state.lock().unwrap();
+        let peer = state.peers.get_mut(&IP).unwrap();
+        peer.updated -= by;
+        peer.banned_until = peer.banned_until.map(|until| until - by);
+    }
+
+    #[test]
+    fn bucket_refills() {
+        let guard = guard();
+        assert!(guard.admit(IP).is_ok());
+        assert!(guard.admit(IP).is_ok());
+        match guard.admit(IP) {
+            Err(Refusal::TooFast(wait)) => assert!(wait <= Duration::from_secs(1), "{:?}", wait),
+            _ => panic!("a third connection in a row is admitted"),
+        }
+        rewind(&guard, Duration::from_millis(1100));
+        assert!(guard.admit(IP).is_ok());
+        assert!(guard.admit(IP).is_err());
+
+        // No more than the burst is saved up.
+        rewind(&guard, Duration::from_secs(3600));
+        let state = guard.state.lock().unwrap();
+        assert_eq!(guard.refill(&state.peers[&IP], Instant::now()), 2.0);
+    }
+
+    #[test]
+    fn ban_expires() {
+        let guard = guard();
+        while guard.admit(IP).is_ok() {}
+        let mut refusal = guard.admit(IP);
+        for _ in 2..STRIKES {
+            refusal = guard.admit(IP);
+        }
+        match refusal {
+            Err(Refusal::Banned(Some(wait))) => assert_eq!(wait, Duration::from_secs(10)),
+            _ => panic!("not banned after {} strikes", STRIKES),
+        }
+        rewind(&guard, Duration::from_secs(5));
+        match guard.admit(IP) {
+            Err(Refusal::Banned(Some(wait))) => assert!(wait <= Duration::from_secs(5), "{:?}", wait),
+            _ => panic!("the ban is over too early"),
+        }
+        rewind(&guard, Duration::from_secs(6));
+        assert!(guard.admit(IP).is_ok());
+        assert_eq!(guard.state.lock().unwrap().peers[&IP].strikes, 0);
+    }
+}
@@ -0,0 +1,191 @@
+//! The counters of a server, served to Prometheus in its text format on an
+//! address of their own.
+//!
+//! They are kept in memory shared with the processes that `--fork-sessions`
+//! starts, so what happens in those is counted too, and are only kept at
+//! all when `--metrics` is given.
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use std::time::Duration;
+
+use libc;
+
+use super::limits::Refusal;
+
+/// The upper bounds of the buckets of render times, in seconds.
+const BUCKETS: [f64; 8] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25];
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What is counted. Zeroed memory is a valid value, with every count at 0.
+#[repr(C)]
+struct Counters {
+    sessions: AtomicU64,
+    connections: AtomicU64,
+    /// By reason: busy, too fast and banned.
+    refused: [AtomicU64; 3],
+    /// Renders that took up to every bound of `BUCKETS`, and longer.
+    renders: [AtomicU64; 9],
+    render_microseconds: AtomicU64,
+    sent: AtomicU64,
+    reloads: AtomicU64,
+}
+
+static COUNTERS: AtomicPtr<Counters> = AtomicPtr::new(ptr::null_mut());
+
+fn counters() -> Option<&'static Counters> {
+    // Set once, and never unmapped.
+    unsafe { COUNTERS.load(Ordering::Acquire).as_ref() }
+}
+
+/// Starts counting, in memory the processes forked from now on share.
+pub fn enable() -> Result<(), io::Error> {
+    let memory = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            mem::size_of::<Counters>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if memory == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    COUNTERS.store(memory as *mut Counters, Ordering::Release);
+    Ok(())
+}
+
+pub fn connected() {
+    if let Some(counters) = counters() {
+        counters.connections.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn refused(refusal: &Refusal) {
+    if let Some(counters) = counters() {
+        let reason = match *refusal {
+            Refusal::Busy => 0,
+            Refusal::TooFast(_) => 1,
+            Refusal::Banned(_) => 2,
+        };
+        counters.refused[reason].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A session starts, `false` when it ends.
+pub fn session(started: bool) {
+    if let Some(counters) = counters() {
+        if started {
+            counters.sessions.fetch_add(1, Ordering::Relaxed);
+        } else {
+            counters.sessions.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A screen of the UI or an answer took `time` to render.
+pub fn rendered(time: Duration) {
+    if let Some(counters) = counters() {
+        let seconds = time.as_secs() as f64 + f64::from(time.subsec_nanos()) / 1e9;
+        let bucket = BUCKETS.iter().position(|&bound| seconds <= bound).unwrap_or(BUCKETS.len());
+        counters.renders[bucket].fetch_add(1, Ordering::Relaxed);
+        counters.render_microseconds.fetch_add(time.as_secs() * 1_000_000 + u64::from(time.subsec_micros()), Ordering::Relaxed);
+    }
+}
+
+/// Counts what the client of `stream` got, as the kernel knows it, before
+/// the connection is closed.
+pub fn sent(stream: &TcpStream) {
+    let counters = match counters() {
+        Some(counters) => counters,
+        None => return,
+    };
+    let mut info: libc::tcp_info = unsafe { mem::zeroed() };
+    let mut length = mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(stream.as_raw_fd(), libc::IPPROTO_TCP, libc::TCP_INFO, &mut info as *mut _ as *mut libc::c_void, &mut length)
+    };
+    if result == 0 {
+        counters.sent.fetch_add(info.tcpi_bytes_acked, Ordering::Relaxed);
+    }
+}
+
+pub fn reloaded() {
+    if let Some(counters) = counters() {
+        counters.reloads.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The counters in the text format of Prometheus.
+fn text(counters: &Counters, protocol: &str) -> String {
+    let count = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let mut text = String::new();
+    let metric = |text: &mut String, name: &str, kind: &str, help: &str| {
+        write!(text, "# HELP rust_cv_{} {}\n# TYPE rust_cv_{} {}\n", name, help, name, kind).ok();
+    };
+    metric(&mut text, "sessions", "gauge", "Sessions going on.");
+    writeln!(text, "rust_cv_sessions{{protocol=\"{}\"}} {}", protocol, count(&counters.sessions)).ok();
+    metric(&mut text, "connections_total", "counter", "Connections accepted.");
+    writeln!(text, "rust_cv_connections_total{{protocol=\"{}\"}} {}", protocol, count(&counters.connections)).ok();
+    metric(&mut text, "refused_connections_total", "counter", "Connections refused, by reason.");
+    for (reason, counter) in ["busy", "too_fast", "banned"].iter().zip(&counters.refused) {
+        writeln!(text, "rust_cv_refused_connections_total{{protocol=\"{}\",reason=\"{}\"}} {}", protocol, reason, count(counter)).ok();
+    }
+    metric(&mut text, "render_seconds", "histogram", "Time to render a screen of the UI or an answer.");
+    let mut renders = 0;
+    for (i, counter) in counters.renders.iter().enumerate() {
+        renders += count(counter);
+        let bound = BUCKETS.get(i).map_or("+Inf".to_string(), |bound| bound.to_string());
+        writeln!(text, "rust_cv_render_seconds_bucket{{protocol=\"{}\",le=\"{}\"}} {}", protocol, bound, renders).ok();
+    }
+    let seconds = count(&counters.render_microseconds) as f64 / 1e6;
+    writeln!(text, "rust_cv_render_seconds_sum{{protocol=\"{}\"}} {}", protocol, seconds).ok();
+    writeln!(text, "rust_cv_render_seconds_count{{protocol=\"{}\"}} {}", protocol, renders).ok();
+    metric(&mut text, "sent_bytes_total", "counter", "Bytes sent to clients, as they acknowledged them.");
+    writeln!(text, "rust_cv_sent_bytes_total{{protocol=\"{}\"}} {}", protocol, count(&counters.sent)).ok();
+    metric(&mut text, "content_reloads_total", "counter", "Times the content file was read again after it changed.");
+    writeln!(text, "rust_cv_content_reloads_total {}", count(&counters.reloads)).ok();
+    text
+}
+
+/// Answers a scrape, or anything else with 404.
+fn scrape(stream: TcpStream, protocol: &str) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    reader.by_ref().take(8 * 1024).read_line(&mut line)?;
+    // The headers say nothing that changes the answer, but are read so
+    // the client gets it rather than a reset.
+    let mut header = String::new();
+    while reader.by_ref().take(8 * 1024).read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let target = line.split(' ').nth(1).unwrap_or("");
+    let (status, body) = match (counters(), target.split('?').next()) {
+        (Some(counters), Some("/metrics")) => ("200 OK", text(counters, protocol)),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    (&stream).flush()
+}
+
+/// Answers scrapes on `listener`, one at a time.
+pub fn serve(listener: TcpListener, protocol: &'static str) {
+    for stream in listener.incoming().flatten() {
+        scrape(stream, protocol).ok();
+    }
+}
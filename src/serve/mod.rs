@@ -0,0 +1,408 @@
+//! Serving the curriculum over the network.
+
+mod finger;
+mod gemini;
+mod gopher;
+mod http;
+mod limits;
+mod metrics;
+mod sandbox;
+mod ssh;
+mod telnet;
+
+use std::cmp;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::path::Path;
+use std::process;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use cli::{self, Limits, Listen, Server, Ui};
+use contact::Maildir;
+use graphics::GraphicsMode;
+use note;
+use sanitize;
+use visits::{Log, Visit};
+use Content;
+
+use self::limits::{Guard, Refusal, REFRESH};
+use self::sandbox::Sandbox;
+
+pub use self::metrics::rendered;
+
+/// How long refused clients are given to hear why.
+const REFUSAL_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a client that stops reading is waited for, after which what
+/// is being sent to it fails and its connection ends.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The directory of a file, which is the current one for bare file names.
+fn directory(file: &str) -> String {
+    match Path::new(file).parent() {
+        Some(parent) if parent != Path::new("") => parent.to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    }
+}
+
+pub fn run(
+    content: Option<String>,
+    server: Server,
+    limits: Limits,
+    sandbox: cli::Sandbox,
+    metrics: Option<String>,
+) -> Result<(), io::Error> {
+    // The standard error of inetd servers is the connection of the client.
+    match server {
+        Server::Telnet { listen: Listen::Inetd, .. }
+        | Server::Http { listen: Listen::Inetd }
+        | Server::Finger { listen: Listen::Inetd, .. } => note::silence(),
+        _ => {}
+    }
+    // Everything else is read before the sandbox is entered.
+    let mut read: Vec<String> = content.iter().map(|content| directory(content)).collect();
+    if let Server::Finger { roster: Some(ref roster), .. } = server {
+        read.push(roster.clone());
+    }
+    // And written nowhere but in the log directory and the Maildir.
+    let mut write: Vec<String> = sandbox.log_dir.iter().cloned().collect();
+    let maildir = match server {
+        Server::Ssh { maildir: Some(ref dir), .. } | Server::Telnet { maildir: Some(ref dir), .. } => {
+            write.push(dir.clone());
+            Some(Maildir::open(dir)?)
+        }
+        _ => None,
+    };
+    let log = match sandbox.log_dir {
+        Some(ref dir) => Some(Log::open(dir)?),
+        None => None,
+    };
+    let guard = Guard::new(&limits, Sandbox::new(&sandbox, read, write, limits.session), log)?;
+    if let Some(address) = metrics {
+        let protocol = match server {
+            Server::Ssh { .. } => "ssh",
+            Server::Telnet { .. } => "telnet",
+            Server::Http { .. } => "http",
+            Server::Finger { .. } => "finger",
+            Server::Gopher { .. } => "gopher",
+            Server::Gemini { .. } => "gemini",
+        };
+        metrics::enable()?;
+        let listener = TcpListener::bind(&address)?;
+        note!("rust-cv: serving metrics on {}", listener.local_addr()?);
+        guard.beside(move || metrics::serve(listener, protocol));
+    }
+    match server {
+        Server::Ssh { listen, host_key, allow, ui, guestbook, .. } => {
+            let guestbook = if guestbook { sandbox.log_dir } else { None };
+            let content = terminal_content(&guard, content, &ui, guestbook, maildir)?;
+            ssh::serve(&listen, &host_key, allow, ui, limits.idle, guard, content)
+        }
+        Server::Telnet { listen, ui, guestbook, .. } => {
+            let guestbook = if guestbook { sandbox.log_dir } else { None };
+            let content = terminal_content(&guard, content, &ui, guestbook, maildir)?;
+            telnet::serve(&listen, limits.idle, ui, guard, content)
+        }
+        Server::Http { listen } => {
+            let cv = shared(&guard, content, ::load)?;
+            http::serve(&listen, guard, cv)
+        }
+        Server::Finger { listen, roster } => {
+            let cv = shared(&guard, content, ::load)?;
+            finger::serve(&listen, roster, guard, cv)
+        }
+        Server::Gopher { listen, hostname } => {
+            let content = shared(&guard, content, |path| Content::load(path, false))?;
+            gopher::serve(&listen, hostname, guard, content)
+        }
+        Server::Gemini { listen, certificate, key, hostname } => {
+            let cv = shared(&guard, content, ::load)?;
+            gemini::serve(&listen, &certificate, &key, hostname, guard, cv)
+        }
+    }
+}
+
+/// The content of a server, which is replaced when its file changes.
+pub struct Shared<T> {
+    current: RwLock<Arc<T>>,
+}
+
+impl<T> Shared<T> {
+    /// The content as it is now, which a connection keeps to the end.
+    pub fn get(&self) -> Arc<T> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Loads the content file `path` with `load`, and again whenever it
+/// changes once the server runs, as the guard checks every couple of
+/// seconds. What cannot be read is logged and the content stays as it was.
+fn shared<T, F>(guard: &Guard, path: Option<String>, load: F) -> Result<Arc<Shared<T>>, io::Error>
+where
+    T: Send + Sync + 'static,
+    F: Fn(Option<String>) -> Result<T, io::Error> + Send + 'static,
+{
+    let shared = Arc::new(Shared { current: RwLock::new(Arc::new(load(path.clone())?)) });
+    if let Some(path) = path {
+        let reloaded = shared.clone();
+        let mut last = modified(&path);
+        guard.refresh(move || {
+            let now = modified(&path);
+            if now == last {
+                return;
+            }
+            last = now;
+            match load(Some(path.clone())) {
+                Ok(content) => {
+                    *reloaded.current.write().unwrap() = Arc::new(content);
+                    metrics::reloaded();
+                    note!("rust-cv: {} changed, reloaded it", path);
+                }
+                Err(err) => note!("rust-cv: cannot reload {}: {}", path, err),
+            }
+        });
+    }
+    Ok(shared)
+}
+
+/// The content of the terminal UI, with the photo when sessions show it,
+/// the guestbook of `guestbook` and the contact form delivering to
+/// `maildir` when they have them.
+fn terminal_content(
+    guard: &Guard,
+    path: Option<String>,
+    ui: &Ui,
+    guestbook: Option<String>,
+    maildir: Option<Maildir>,
+) -> Result<Arc<Shared<Content>>, io::Error> {
+    let photo = ui.graphics != GraphicsMode::None;
+    shared(guard, path, move |path| {
+        Content::load(path, photo).map(|mut content| {
+            content.guestbook = guestbook.clone();
+            content.maildir = maildir.clone();
+            content
+        })
+    })
+}
+
+/// The first socket systemd passes (sd_listen_fds(3)).
+const LISTEN_FDS_START: RawFd = 3;
+
+/// The socket to accept connections on: the one systemd opened when the
+/// server is started by socket activation, or a new one bound to `address`.
+fn listener(address: &str) -> Result<TcpListener, io::Error> {
+    let ours = env::var("LISTEN_PID").ok().and_then(|pid| pid.parse().ok()) == Some(process::id());
+    let count: u32 = env::var("LISTEN_FDS").ok().and_then(|count| count.parse().ok()).unwrap_or(0);
+    // They are not for the processes this one starts, if any.
+    for name in &["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        env::remove_var(name);
+    }
+    if !ours || count == 0 {
+        return TcpListener::bind(address);
+    }
+    // systemd keeps the descriptor open for this process, which owns it.
+    let listener = unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START) };
+    listener
+        .local_addr()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the socket systemd passed is not a TCP socket"))?;
+    Ok(listener)
+}
+
+/// The connection inetd accepted, which is the standard input.
+fn inetd() -> Result<TcpStream, io::Error> {
+    // inetd hands the descriptor over, and nothing else reads from it.
+    let stream = unsafe { TcpStream::from_raw_fd(0) };
+    stream
+        .peer_addr()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the standard input is not a TCP connection"))?;
+    Ok(stream)
+}
+
+/// Handles the connections of `listen` with `handle`: every connection to
+/// its address, or the single one inetd accepted. Under inetd the standard
+/// error is the connection too, so nothing is logged, and inetd has limits
+/// of its own.
+fn serve<F, R>(listen: &Listen, protocol: &'static str, name: &str, guard: Guard, handle: F, refuse: R) -> Result<(), io::Error>
+where
+    F: Fn(TcpStream, &Visit) -> Result<(), io::Error> + Send + Sync + 'static,
+    R: Fn(TcpStream, &Refusal) -> Result<(), io::Error> + Send + Sync + 'static,
+{
+    match *listen {
+        Listen::Inetd => {
+            let stream = inetd()?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            let visit = guard.visit(protocol, stream.peer_addr()?.ip());
+            guard.sandbox().enter(true)?;
+            handle(stream, &visit).ok();
+            visit.end();
+            Ok(())
+        }
+        Listen::Address(ref address) => {
+            let listener = listener(address)?;
+            note!("rust-cv: serving {} on {}", name, listener.local_addr()?);
+            accept(listener, protocol, guard, handle, refuse)
+        }
+    }
+}
+
+/// Enters the sandbox of `guard` and handles every connection to
+/// `listener` on a thread of its own, or in a process of its own that the
+/// thread waits for, within its limits; the others are told why with
+/// `refuse`. Errors are logged with the name of the protocol, except for
+/// clients that just went away.
+fn accept<F, R>(listener: TcpListener, protocol: &'static str, guard: Guard, handle: F, refuse: R) -> Result<(), io::Error>
+where
+    F: Fn(TcpStream, &Visit) -> Result<(), io::Error> + Send + Sync + 'static,
+    R: Fn(TcpStream, &Refusal) -> Result<(), io::Error> + Send + Sync + 'static,
+{
+    let handle = Arc::new(handle);
+    let refuse = Arc::new(refuse);
+    let session = guard.session();
+    // Before any thread is started.
+    let forker = if guard.sandbox().forks() {
+        let keep: Vec<RawFd> = guard.log_fd().into_iter().collect();
+        let (refreshing, visiting, handle) = (guard.clone(), guard.clone(), handle.clone());
+        let forker = guard.sandbox().forker(&keep, protocol, REFRESH, move || refreshing.refreshed(), move |stream, peer| {
+            let visit = visiting.visit(protocol, peer.ip());
+            let handle = handle.clone();
+            move || connection(stream, peer, protocol, session, visit, &*handle)
+        })?;
+        Some(Arc::new(forker))
+    } else {
+        None
+    };
+    guard.enter()?;
+    for stream in listener.incoming() {
+        let (stream, peer) = match stream.and_then(|stream| stream.peer_addr().map(|peer| (stream, peer))) {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                note!("rust-cv: {}: {}", protocol, err);
+                continue;
+            }
+        };
+        let permit = match guard.admit(peer.ip()) {
+            Ok(permit) => permit,
+            Err(refusal) => {
+                metrics::refused(&refusal);
+                if let Some(permit) = guard.refusal() {
+                    let refuse = refuse.clone();
+                    thread::spawn(move || {
+                        stream.set_read_timeout(Some(REFUSAL_TIMEOUT)).ok();
+                        stream.set_write_timeout(Some(REFUSAL_TIMEOUT)).ok();
+                        if let Ok(clone) = stream.try_clone() {
+                            refuse(clone, &refusal).ok();
+                        }
+                        // What the client sent is read before closing, or
+                        // the message could be lost to a reset.
+                        stream.shutdown(Shutdown::Write).ok();
+                        io::copy(&mut (&stream).take(64 * 1024), &mut io::sink()).ok();
+                        drop(permit);
+                    });
+                }
+                continue;
+            }
+        };
+        if let Err(err) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+            note!("rust-cv: {}: {}: {}", protocol, peer, err);
+            continue;
+        }
+        let work: Box<dyn FnOnce() + Send> = match forker {
+            Some(ref forker) => {
+                let forker = forker.clone();
+                Box::new(move || {
+                    if let Err(err) = forker.handle(stream) {
+                        note!("rust-cv: {}: {}: {}", protocol, peer, err);
+                    }
+                })
+            }
+            None => {
+                let handle = handle.clone();
+                let visit = guard.visit(protocol, peer.ip());
+                Box::new(move || connection(stream, peer, protocol, session, visit, &*handle))
+            }
+        };
+        metrics::connected();
+        thread::spawn(move || {
+            metrics::session(true);
+            work();
+            metrics::session(false);
+            drop(permit);
+        });
+    }
+    Ok(())
+}
+
+/// Handles `stream`, for `session` at most.
+fn connection<F>(stream: TcpStream, peer: SocketAddr, protocol: &str, session: Duration, visit: Visit, handle: &F)
+where
+    F: Fn(TcpStream, &Visit) -> Result<(), io::Error>,
+{
+    // Whatever the connection is up to, it ends when it is shut down,
+    // and what it then fails to do is no error.
+    let (done, ended) = mpsc::channel::<()>();
+    let watchdog = stream.try_clone().map(|stream| {
+        thread::spawn(move || {
+            let expired = ended.recv_timeout(session) == Err(RecvTimeoutError::Timeout);
+            if expired {
+                stream.shutdown(Shutdown::Both).ok();
+            }
+            expired
+        })
+    });
+    let counted = stream.try_clone();
+    let result = handle(stream, &visit);
+    if let Ok(ref stream) = counted {
+        metrics::sent(stream);
+    }
+    visit.end();
+    drop(done);
+    let expired = watchdog.ok().map_or(false, |watchdog| watchdog.join().unwrap_or(false));
+    match result {
+        Err(ref err) if err.kind() != io::ErrorKind::UnexpectedEof && !expired => {
+            // Errors may quote what the client sent.
+            note!("rust-cv: {}: {}: {}", protocol, peer, sanitize::clean(&err.to_string()))
+        }
+        _ => {}
+    }
+}
+
+/// What a client sends, as the input of its session. It ends when the
+/// sender is dropped.
+struct Pipe {
+    data: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    read: usize,
+}
+
+impl Pipe {
+    fn new(data: Receiver<Vec<u8>>) -> Pipe {
+        Pipe { data, chunk: Vec::new(), read: 0 }
+    }
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.read == self.chunk.len() {
+            match self.data.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.read = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let length = cmp::min(buf.len(), self.chunk.len() - self.read);
+        buf[..length].copy_from_slice(&self.chunk[self.read..self.read + length]);
+        self.read += length;
+        Ok(length)
+    }
+}
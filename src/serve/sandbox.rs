@@ -0,0 +1,699 @@
+//! What a server is kept from doing once it listens, so that a bug found by
+//! what strangers send can do little: it runs as a user of no importance,
+//! Landlock keeps it to the files it needs and a seccomp filter to the
+//! system calls, and connections can be handled by processes of their own
+//! with resource limits, forked by a process that has no threads.
+//!
+//! Landlock and seccomp are set up by hand, with the calls of the kernel
+//! and a filter written in classic BPF.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, TcpStream};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::raw::{c_int, c_long, c_uint, c_ulong};
+use std::os::unix::net::{UnixDatagram, UnixStream};
+use std::process;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use libc;
+
+use cli;
+
+/// Landlock is the same on every architecture.
+const SYS_LANDLOCK_CREATE_RULESET: c_long = 444;
+const SYS_LANDLOCK_ADD_RULE: c_long = 445;
+const SYS_LANDLOCK_RESTRICT_SELF: c_long = 446;
+const LANDLOCK_CREATE_RULESET_VERSION: c_ulong = 1;
+const LANDLOCK_RULE_PATH_BENEATH: c_int = 1;
+
+const ACCESS_FS_EXECUTE: u64 = 1;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+/// Moving files between directories, from the second version on; with the
+/// first it is never allowed.
+const ACCESS_FS_REFER: u64 = 1 << 13;
+
+const READ: u64 = ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+const WRITE: u64 = READ
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_REMOVE_DIR
+    | ACCESS_FS_REMOVE_FILE
+    | ACCESS_FS_MAKE_DIR
+    | ACCESS_FS_MAKE_REG;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+const SECCOMP_SET_MODE_FILTER: c_ulong = 1;
+/// The filter is for every thread of the process, not just the caller.
+const SECCOMP_FILTER_FLAG_TSYNC: c_ulong = 1;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_RET_K: u16 = 0x06;
+/// Where the system call and the architecture are in `seccomp_data`.
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+const SECCOMP_DATA_ARGS: u32 = 16;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+#[cfg(target_arch = "x86")]
+const AUDIT_ARCH: u32 = 0x4000_0003;
+
+/// The calls of i386 that libc has no number for, those of 64-bit time
+/// that newer C libraries use and `rseq`.
+#[cfg(target_arch = "x86")]
+mod i386 {
+    use std::os::raw::c_long;
+
+    pub const SYS_RSEQ: c_long = 386;
+    pub const SYS_CLOCK_GETTIME64: c_long = 403;
+    pub const SYS_CLOCK_NANOSLEEP_TIME64: c_long = 407;
+    pub const SYS_PPOLL_TIME64: c_long = 414;
+    pub const SYS_FUTEX_TIME64: c_long = 422;
+
+    /// The calls of `socketcall` a server makes, by its first argument.
+    pub const SOCKETCALLS: [u32; 12] = [
+        5,  // accept
+        6,  // getsockname
+        7,  // getpeername
+        8,  // socketpair
+        11, // sendto
+        12, // recvfrom
+        13, // shutdown
+        14, // setsockopt
+        15, // getsockopt
+        16, // sendmsg
+        17, // recvmsg
+        18, // accept4
+    ];
+}
+
+/// The memory and files of a connection handled by a process of its own.
+const MEMORY: libc::rlim_t = 1 << 30;
+const FILES: libc::rlim_t = 64;
+
+/// The system calls of a server: threads, memory, sockets it has, and
+/// files Landlock lets it open.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "x86"))]
+fn syscalls() -> Vec<c_long> {
+    let mut syscalls = vec![
+        libc::SYS_read,
+        libc::SYS_write,
+        libc::SYS_readv,
+        libc::SYS_writev,
+        libc::SYS_pread64,
+        libc::SYS_openat,
+        libc::SYS_close,
+        libc::SYS_close_range,
+        libc::SYS_fstat,
+        libc::SYS_statx,
+        libc::SYS_lseek,
+        libc::SYS_fsync,
+        libc::SYS_renameat,
+        libc::SYS_renameat2,
+        libc::SYS_unlinkat,
+        libc::SYS_getdents64,
+        libc::SYS_fcntl,
+        libc::SYS_mmap,
+        libc::SYS_munmap,
+        libc::SYS_mprotect,
+        libc::SYS_mremap,
+        libc::SYS_madvise,
+        libc::SYS_brk,
+        libc::SYS_rt_sigaction,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        libc::SYS_ppoll,
+        libc::SYS_futex,
+        libc::SYS_clone,
+        libc::SYS_clone3,
+        libc::SYS_wait4,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        libc::SYS_set_robust_list,
+        libc::SYS_gettid,
+        libc::SYS_getpid,
+        libc::SYS_tgkill,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_nanosleep,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_clock_gettime,
+        libc::SYS_gettimeofday,
+        libc::SYS_getrandom,
+        libc::SYS_prlimit64,
+        libc::SYS_accept4,
+        libc::SYS_getsockname,
+        libc::SYS_getpeername,
+        libc::SYS_setsockopt,
+        libc::SYS_getsockopt,
+        libc::SYS_shutdown,
+        libc::SYS_sendto,
+        libc::SYS_recvfrom,
+        libc::SYS_sendmsg,
+        libc::SYS_recvmsg,
+        libc::SYS_socketpair,
+    ];
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    syscalls.extend_from_slice(&[libc::SYS_newfstatat, libc::SYS_accept, libc::SYS_rseq]);
+    #[cfg(target_arch = "x86_64")]
+    syscalls.extend_from_slice(&[libc::SYS_open, libc::SYS_stat, libc::SYS_lstat, libc::SYS_poll, libc::SYS_rename, libc::SYS_unlink]);
+    // The 64-bit variants of the calls on files and memory, and the old
+    // calls the C library may still make.
+    #[cfg(target_arch = "x86")]
+    syscalls.extend_from_slice(&[
+        libc::SYS_fstat64,
+        libc::SYS_fstatat64,
+        libc::SYS_stat64,
+        libc::SYS_lstat64,
+        libc::SYS__llseek,
+        libc::SYS_fcntl64,
+        libc::SYS_mmap2,
+        libc::SYS_open,
+        libc::SYS_poll,
+        libc::SYS_rename,
+        libc::SYS_unlink,
+        i386::SYS_RSEQ,
+        i386::SYS_CLOCK_GETTIME64,
+        i386::SYS_CLOCK_NANOSLEEP_TIME64,
+        i386::SYS_PPOLL_TIME64,
+        i386::SYS_FUTEX_TIME64,
+    ]);
+    syscalls
+}
+
+/// The system calls allowed with some values of an argument only, by the
+/// number of the argument. Of `ioctl`, asking a terminal for its size and
+/// settings, and setting the flags of a descriptor; a session takes its
+/// size from the client, but termion and the standard library still ask.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "x86"))]
+fn arguments() -> Vec<(c_long, u32, Vec<u32>)> {
+    let ioctls = vec![libc::TIOCGWINSZ as u32, libc::TCGETS as u32, libc::FIONBIO as u32, libc::FIOCLEX as u32];
+    #[cfg_attr(not(target_arch = "x86"), allow(unused_mut))]
+    let mut arguments = vec![(libc::SYS_ioctl, 1, ioctls)];
+    #[cfg(target_arch = "x86")]
+    arguments.push((libc::SYS_socketcall, 0, i386::SOCKETCALLS.to_vec()));
+    arguments
+}
+
+fn instruction(code: u16, jt: u8, jf: u8, k: u32) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+/// The filter: processes of other architectures are killed, the calls of
+/// `syscalls` allowed, those of `arguments` when the argument is one of
+/// the values, and the others fail with `EPERM`.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "x86"))]
+fn filter() -> Result<Vec<libc::sock_filter>, io::Error> {
+    let syscalls = syscalls();
+    let arguments = arguments();
+    // Every jump to the end allows the call, so the distance to it is that
+    // of the instructions still to come.
+    let rest = arguments.iter().map(|(_, _, values)| 3 + values.len()).sum::<usize>() + 1;
+    let mut filter = vec![
+        instruction(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARCH),
+        instruction(BPF_JEQ_K, 1, 0, AUDIT_ARCH),
+        instruction(BPF_RET_K, 0, 0, SECCOMP_RET_KILL_PROCESS),
+        instruction(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_NR),
+    ];
+    for (i, &syscall) in syscalls.iter().enumerate() {
+        let allow = syscalls.len() - i - 1 + rest;
+        filter.push(instruction(BPF_JEQ_K, allow as u8, 0, syscall as u32));
+    }
+    let mut rest = rest;
+    for &(syscall, argument, ref values) in &arguments {
+        // Another call skips the block, with its number still loaded.
+        let block = 2 + values.len();
+        rest -= block + 1;
+        filter.push(instruction(BPF_JEQ_K, 0, block as u8, syscall as u32));
+        // The low half of the argument, which is all `ioctl` and
+        // `socketcall` look at.
+        filter.push(instruction(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARGS + 8 * argument));
+        for (i, &value) in values.iter().enumerate() {
+            let allow = values.len() - i + rest;
+            filter.push(instruction(BPF_JEQ_K, allow as u8, 0, value));
+        }
+        filter.push(instruction(BPF_RET_K, 0, 0, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+    }
+    filter.push(instruction(BPF_RET_K, 0, 0, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+    filter.push(instruction(BPF_RET_K, 0, 0, SECCOMP_RET_ALLOW));
+    Ok(filter)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "x86")))]
+fn filter() -> Result<Vec<libc::sock_filter>, io::Error> {
+    Err(io::Error::new(io::ErrorKind::Other, "there is no filter for this architecture"))
+}
+
+/// The result of a call that returns -1 on errors.
+fn check(result: c_long) -> Result<c_long, io::Error> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+/// A part of the sandbox, set up by `enter`.
+type Step = fn(&Sandbox) -> Result<(), io::Error>;
+
+/// A sandbox, set up for the whole process by `enter`.
+pub struct Sandbox {
+    user: Option<String>,
+    /// What the server reads, and where it writes.
+    read: Vec<String>,
+    write: Vec<String>,
+    fork: bool,
+    unsandboxed: bool,
+    /// The processor time of a connection with a process of its own.
+    processor: Duration,
+}
+
+impl Sandbox {
+    pub fn new(options: &cli::Sandbox, read: Vec<String>, write: Vec<String>, processor: Duration) -> Sandbox {
+        Sandbox {
+            user: options.user.clone(),
+            read,
+            write,
+            fork: options.fork,
+            unsandboxed: options.unsandboxed,
+            processor,
+        }
+    }
+
+    pub fn forks(&self) -> bool {
+        self.fork
+    }
+
+    /// Sets the sandbox up, after which there is no way out. What cannot be
+    /// set up stops the server, or with `--allow-unsandboxed` is left out,
+    /// and said so unless `quiet`.
+    pub fn enter(&self, quiet: bool) -> Result<(), io::Error> {
+        let steps: [(&str, Step); 3] =
+            [("changing the user", Sandbox::become_user), ("Landlock", Sandbox::landlock), ("seccomp", Sandbox::seccomp)];
+        for &(name, step) in &steps {
+            if let Err(err) = step(self) {
+                let message = format!("cannot set up the sandbox, {}: {}", name, err);
+                if !self.unsandboxed {
+                    return Err(io::Error::new(err.kind(), message));
+                }
+                if !quiet {
+                    note!("rust-cv: {}; going on without it", message);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Becomes `--user` when started as root, nobody without it.
+    fn become_user(&self) -> Result<(), io::Error> {
+        let root = unsafe { libc::geteuid() } == 0;
+        let name = match (root, &self.user) {
+            (false, None) => return Ok(()),
+            (false, Some(name)) => {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("only root can become {}", name)))
+            }
+            (true, user) => user.as_ref().map_or("nobody", |u| u.as_str()),
+        };
+        let c_name = CString::new(name)?;
+        let entry = unsafe { libc::getpwnam(c_name.as_ptr()) };
+        if entry.is_null() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("there is no user {}", name)));
+        }
+        let (uid, gid) = unsafe { ((*entry).pw_uid, (*entry).pw_gid) };
+        if uid == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is root", name)));
+        }
+        unsafe {
+            check(libc::setgroups(1, &gid).into())?;
+            check(libc::setgid(gid).into())?;
+            check(libc::setuid(uid).into())?;
+        }
+        Ok(())
+    }
+
+    fn landlock(&self) -> Result<(), io::Error> {
+        let version = check(unsafe {
+            libc::syscall(SYS_LANDLOCK_CREATE_RULESET, ptr::null::<RulesetAttr>(), 0, LANDLOCK_CREATE_RULESET_VERSION)
+        })?;
+        let refer = if version >= 2 { ACCESS_FS_REFER } else { 0 };
+        let attr = RulesetAttr {
+            handled_access_fs: WRITE
+                | ACCESS_FS_EXECUTE
+                | ACCESS_FS_MAKE_CHAR
+                | ACCESS_FS_MAKE_SOCK
+                | ACCESS_FS_MAKE_FIFO
+                | ACCESS_FS_MAKE_BLOCK
+                | ACCESS_FS_MAKE_SYM
+                | refer,
+        };
+        let ruleset = check(unsafe {
+            libc::syscall(SYS_LANDLOCK_CREATE_RULESET, &attr as *const RulesetAttr, mem::size_of::<RulesetAttr>(), 0)
+        })?;
+        let ruleset = unsafe { File::from_raw_fd(ruleset as RawFd) };
+        let rules = self.read.iter().map(|path| (path, READ)).chain(self.write.iter().map(|path| (path, WRITE | refer)));
+        for (path, access) in rules {
+            let directory = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH | libc::O_DIRECTORY)
+                .open(path)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)))?;
+            let rule = PathBeneathAttr { allowed_access: access, parent_fd: directory.as_raw_fd() };
+            check(unsafe {
+                libc::syscall(
+                    SYS_LANDLOCK_ADD_RULE,
+                    ruleset.as_raw_fd(),
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                )
+            })?;
+        }
+        unsafe {
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0).into())?;
+            check(libc::syscall(SYS_LANDLOCK_RESTRICT_SELF, ruleset.as_raw_fd(), 0))?;
+        }
+        Ok(())
+    }
+
+    fn seccomp(&self) -> Result<(), io::Error> {
+        let mut filter = filter()?;
+        let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_mut_ptr() };
+        unsafe {
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0).into())?;
+            let thread = check(libc::syscall(
+                libc::SYS_seccomp,
+                SECCOMP_SET_MODE_FILTER,
+                SECCOMP_FILTER_FLAG_TSYNC,
+                &program as *const libc::sock_fprog,
+            ))?;
+            if thread != 0 {
+                return Err(io::Error::new(io::ErrorKind::Other, format!("thread {} cannot be filtered", thread)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes every file but the standard ones and `keep`, and limits the
+    /// resources of the process.
+    fn confine(&self, keep: &[RawFd]) -> Result<(), io::Error> {
+        let mut keep: Vec<c_uint> = keep.iter().map(|&fd| fd as c_uint).collect();
+        keep.sort();
+        let mut first = 3;
+        for fd in keep {
+            if fd > first {
+                check(unsafe { libc::syscall(libc::SYS_close_range, first, fd - 1, 0) })?;
+            }
+            first = cmp::max(first, fd + 1);
+        }
+        check(unsafe { libc::syscall(libc::SYS_close_range, first, c_uint::max_value(), 0) })?;
+        let limits = [
+            (libc::RLIMIT_AS, MEMORY),
+            (libc::RLIMIT_CPU, self.processor.as_secs() as libc::rlim_t),
+            (libc::RLIMIT_NOFILE, FILES),
+            (libc::RLIMIT_CORE, 0),
+        ];
+        for &(resource, limit) in &limits {
+            let limit = libc::rlimit { rlim_cur: limit, rlim_max: limit };
+            check(unsafe { libc::setrlimit(resource, &limit) }.into())?;
+        }
+        Ok(())
+    }
+
+    /// Starts the process that forks those of connections. A process
+    /// forked from one with threads can find a lock taken for good by a
+    /// thread it does not have, so this has to be done while the server has
+    /// none, and that process has none either. It enters the sandbox, and
+    /// for every connection it is passed runs `prepare` and forks a process
+    /// that only has the files `keep` and the connection to run what
+    /// `prepare` returned. It also runs `tick` every `every`.
+    pub fn forker<T, P, W>(&self, keep: &[RawFd], protocol: &str, every: Duration, tick: T, prepare: P) -> Result<Forker, io::Error>
+    where
+        T: FnMut(),
+        P: Fn(TcpStream, SocketAddr) -> W,
+        W: FnOnce(),
+    {
+        let (ours, theirs) = packet_pair()?;
+        match check(unsafe { libc::fork() }.into())? {
+            0 => {
+                drop(ours);
+                match self.enter(true) {
+                    Ok(()) => theirs.send(&[0]).ok(),
+                    Err(err) => theirs.send(err.to_string().as_bytes()).ok(),
+                };
+                self.fork_connections(&theirs, keep, protocol, every, tick, prepare)
+            }
+            _ => {
+                drop(theirs);
+                let mut answer = [0; 512];
+                let length = ours.recv(&mut answer)?;
+                match &answer[..length] {
+                    [0] => Ok(Forker { control: ours }),
+                    [] => Err(io::Error::new(io::ErrorKind::Other, "the process forking connections stopped")),
+                    error => Err(io::Error::new(io::ErrorKind::Other, String::from_utf8_lossy(error).into_owned())),
+                }
+            }
+        }
+    }
+
+    /// What the process that forks connections does until the server is
+    /// gone.
+    fn fork_connections<T, P, W>(&self, control: &UnixDatagram, keep: &[RawFd], protocol: &str, every: Duration, mut tick: T, prepare: P) -> !
+    where
+        T: FnMut(),
+        P: Fn(TcpStream, SocketAddr) -> W,
+        W: FnOnce(),
+    {
+        let mut children = HashMap::new();
+        let mut next = Instant::now() + every;
+        loop {
+            reap(&mut children, protocol);
+            let wait = next.saturating_duration_since(Instant::now());
+            let mut poll = libc::pollfd { fd: control.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            if unsafe { libc::poll(&mut poll, 1, wait.as_millis() as c_int) } <= 0 {
+                if Instant::now() >= next {
+                    tick();
+                    next = Instant::now() + every;
+                }
+                continue;
+            }
+            let fds = match receive_fds(control.as_raw_fd(), 2) {
+                Ok(Some(fds)) => fds,
+                // The server is gone.
+                Ok(None) => process::exit(0),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    note!("rust-cv: {}: cannot take a connection: {}", protocol, err);
+                    continue;
+                }
+            };
+            let stream = unsafe { TcpStream::from_raw_fd(fds[0]) };
+            // Closed when the process of the connection ends, which tells
+            // the server.
+            let done = unsafe { UnixStream::from_raw_fd(fds[1]) };
+            let peer = match stream.peer_addr() {
+                Ok(peer) => peer,
+                Err(_) => continue,
+            };
+            let work = prepare(stream, peer);
+            match unsafe { libc::fork() } {
+                -1 => note!("rust-cv: {}: {}: {}", protocol, peer, io::Error::last_os_error()),
+                0 => {
+                    let keep: Vec<RawFd> = keep.iter().chain(&fds).cloned().collect();
+                    match self.confine(&keep) {
+                        Ok(()) => work(),
+                        Err(ref err) if !self.unsandboxed => note!("rust-cv: cannot limit the connection: {}", err),
+                        Err(_) => work(),
+                    }
+                    process::exit(0);
+                }
+                child => {
+                    children.insert(child, peer);
+                }
+            }
+            drop(done);
+        }
+    }
+}
+
+/// Waits for the processes of connections that ended, saying which were
+/// killed.
+fn reap(children: &mut HashMap<libc::pid_t, SocketAddr>, protocol: &str) {
+    loop {
+        let mut status = 0;
+        let child = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+        if child <= 0 {
+            return;
+        }
+        if let Some(peer) = children.remove(&child) {
+            if libc::WIFSIGNALED(status) {
+                let signal = libc::WTERMSIG(status);
+                note!("rust-cv: {}: {}: the process of the connection got signal {}", protocol, peer, signal);
+            }
+        }
+    }
+}
+
+/// A pair of connected sequenced-packet sockets, which std has no type
+/// for. They are used as the datagram sockets they are like, except that
+/// the end of one is told when the other is closed.
+fn packet_pair() -> Result<(UnixDatagram, UnixDatagram), io::Error> {
+    let mut fds = [0; 2];
+    check(unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC, 0, fds.as_mut_ptr()) }.into())?;
+    Ok(unsafe { (UnixDatagram::from_raw_fd(fds[0]), UnixDatagram::from_raw_fd(fds[1])) })
+}
+
+/// Room for the control message that passes `count` files.
+fn control_buffer(count: usize) -> Vec<u64> {
+    let space = unsafe { libc::CMSG_SPACE((count * mem::size_of::<RawFd>()) as u32) } as usize;
+    // In words, as the headers are aligned.
+    vec![0; (space + 7) / 8]
+}
+
+/// Sends `fds` over the Unix socket `socket`, with a byte to carry them.
+fn send_fds(socket: RawFd, fds: &[RawFd]) -> Result<(), io::Error> {
+    let size = mem::size_of_val(fds);
+    let mut control = control_buffer(fds.len());
+    let mut byte = [1u8];
+    let mut iov = libc::iovec { iov_base: byte.as_mut_ptr() as *mut libc::c_void, iov_len: 1 };
+    let mut message: libc::msghdr = unsafe { mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    message.msg_controllen = (control.len() * 8) as _;
+    unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(size as u32) as _;
+        ptr::copy_nonoverlapping(fds.as_ptr() as *const u8, libc::CMSG_DATA(header), size);
+        check(libc::sendmsg(socket, &message, 0) as c_long)?;
+    }
+    Ok(())
+}
+
+/// Receives the `count` files sent with `send_fds`, or nothing once the
+/// other end is closed.
+fn receive_fds(socket: RawFd, count: usize) -> Result<Option<Vec<RawFd>>, io::Error> {
+    let size = count * mem::size_of::<RawFd>();
+    let mut control = control_buffer(count);
+    let mut byte = [0u8];
+    let mut iov = libc::iovec { iov_base: byte.as_mut_ptr() as *mut libc::c_void, iov_len: 1 };
+    let mut message: libc::msghdr = unsafe { mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    message.msg_controllen = (control.len() * 8) as _;
+    unsafe {
+        if check(libc::recvmsg(socket, &mut message, libc::MSG_CMSG_CLOEXEC) as c_long)? == 0 {
+            return Ok(None);
+        }
+        let header = libc::CMSG_FIRSTHDR(&message);
+        if header.is_null()
+            || (*header).cmsg_type != libc::SCM_RIGHTS
+            || (*header).cmsg_len as usize != libc::CMSG_LEN(size as u32) as usize
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the connection did not come with its files"));
+        }
+        let mut fds = vec![0; count];
+        ptr::copy_nonoverlapping(libc::CMSG_DATA(header), fds.as_mut_ptr() as *mut u8, size);
+        Ok(Some(fds))
+    }
+}
+
+/// The end the server has of the process that forks connections.
+pub struct Forker {
+    control: UnixDatagram,
+}
+
+impl Forker {
+    /// Has `stream` handled by a process of its own, and waits for it to
+    /// end.
+    pub fn handle(&self, stream: TcpStream) -> Result<(), io::Error> {
+        let (ours, theirs) = UnixStream::pair()?;
+        send_fds(self.control.as_raw_fd(), &[stream.as_raw_fd(), theirs.as_raw_fd()])?;
+        // The connection is the process's now, and closes with it.
+        drop(stream);
+        drop(theirs);
+        io::copy(&mut (&ours), &mut io::sink())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the filter lets `ioctl` ask `request` of a socket, in a child
+    /// process that has it. A socket is no terminal, so whatever gets past
+    /// the filter fails otherwise than with `EPERM`, if at all.
+    fn ioctl_allowed(request: c_ulong) -> bool {
+        let mut filter = filter().unwrap();
+        let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_mut_ptr() };
+        let (socket, _other) = UnixStream::pair().unwrap();
+        let mut argument = [0u8; 64];
+        unsafe {
+            match libc::fork() {
+                -1 => panic!("cannot fork: {}", io::Error::last_os_error()),
+                0 => {
+                    libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+                    if libc::syscall(libc::SYS_seccomp, SECCOMP_SET_MODE_FILTER, 0, &program as *const libc::sock_fprog) != 0 {
+                        libc::_exit(2);
+                    }
+                    let result = libc::ioctl(socket.as_raw_fd(), request as _, argument.as_mut_ptr());
+                    let refused = result == -1 && io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+                    libc::_exit(refused as c_int);
+                }
+                child => {
+                    let mut status = 0;
+                    libc::waitpid(child, &mut status, 0);
+                    assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) < 2, "the filter was not set up");
+                    libc::WEXITSTATUS(status) == 0
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ioctls() {
+        assert!(ioctl_allowed(libc::TIOCGWINSZ as c_ulong));
+        assert!(ioctl_allowed(libc::TCGETS as c_ulong));
+        assert!(ioctl_allowed(libc::FIONBIO as c_ulong));
+        // Typing into a terminal is not something a server does.
+        assert!(!ioctl_allowed(libc::TIOCSTI as c_ulong));
+        assert!(!ioctl_allowed(libc::TIOCSWINSZ as c_ulong));
+    }
+}
@@ -0,0 +1,614 @@
+//! The terminal UI over SSH, for anyone who connects.
+//!
+//! Every connection has a thread reading what the client sends and one
+//! writing to it, and every shell a session of the UI of its own, which
+//! writes through the writer. Clients are not asked for a password or a
+//! key; the user name only counts when there is a list of allowed ones.
+
+mod key;
+mod transport;
+mod wire;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, BufReader, Write};
+use std::mem;
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tui::layout::Rect;
+
+use cli::Ui;
+use graphics;
+use term::Term;
+use visits::Visit;
+use {Content, Event, Settings};
+
+use self::key::HostKey;
+use self::transport::{Exchange, PacketReader, PacketWriter, Sealing};
+use self::wire::{Message, Reader};
+use super::limits::{Guard, Refusal};
+use super::{Pipe, Shared};
+
+const MSG_DISCONNECT: u8 = 1;
+const MSG_IGNORE: u8 = 2;
+const MSG_UNIMPLEMENTED: u8 = 3;
+const MSG_DEBUG: u8 = 4;
+const MSG_SERVICE_REQUEST: u8 = 5;
+const MSG_SERVICE_ACCEPT: u8 = 6;
+const MSG_USERAUTH_REQUEST: u8 = 50;
+const MSG_USERAUTH_FAILURE: u8 = 51;
+const MSG_USERAUTH_SUCCESS: u8 = 52;
+const MSG_GLOBAL_REQUEST: u8 = 80;
+const MSG_REQUEST_FAILURE: u8 = 82;
+const MSG_CHANNEL_OPEN: u8 = 90;
+const MSG_CHANNEL_OPEN_CONFIRMATION: u8 = 91;
+const MSG_CHANNEL_OPEN_FAILURE: u8 = 92;
+const MSG_CHANNEL_WINDOW_ADJUST: u8 = 93;
+const MSG_CHANNEL_DATA: u8 = 94;
+const MSG_CHANNEL_EOF: u8 = 96;
+const MSG_CHANNEL_CLOSE: u8 = 97;
+const MSG_CHANNEL_REQUEST: u8 = 98;
+const MSG_CHANNEL_SUCCESS: u8 = 99;
+const MSG_CHANNEL_FAILURE: u8 = 100;
+
+const DISCONNECT_HOST_NOT_ALLOWED_TO_CONNECT: u32 = 1;
+const DISCONNECT_BY_APPLICATION: u32 = 11;
+const DISCONNECT_TOO_MANY_CONNECTIONS: u32 = 12;
+
+const OPEN_ADMINISTRATIVELY_PROHIBITED: u32 = 1;
+const OPEN_UNKNOWN_CHANNEL_TYPE: u32 = 3;
+
+/// How much a client may send before it is given room again. It only
+/// sends keys.
+const WINDOW: u32 = 64 * 1024;
+/// The most data sent in a packet.
+const MAX_DATA: u32 = 32 * 1024;
+/// How long a client that is disconnected has to take the message.
+const GOODBYE: Duration = Duration::from_secs(5);
+
+/// What every connection shares.
+struct Host {
+    key: HostKey,
+    allow: Vec<String>,
+    ui: Ui,
+    /// How long a client may go without sending a key.
+    idle: Duration,
+    content: Arc<Shared<Content>>,
+}
+
+pub fn serve(
+    listen: &str,
+    host_key: &str,
+    allow: Vec<String>,
+    ui: Ui,
+    idle: Duration,
+    guard: Guard,
+    content: Arc<Shared<Content>>,
+) -> Result<(), io::Error> {
+    let key = HostKey::open(host_key)?;
+    let listener = super::listener(listen)?;
+    note!("rust-cv: serving SSH on {}, host key {}", listener.local_addr()?, key.fingerprint());
+    let host = Arc::new(Host { key, allow, ui, idle, content });
+    super::accept(listener, "ssh", guard, move |stream, visit| connection(&host, stream, visit), refuse)
+}
+
+/// Disconnects the client right after the versions are exchanged, which
+/// clients show the reason of.
+fn refuse(stream: TcpStream, refusal: &Refusal) -> Result<(), io::Error> {
+    let mut output = stream.try_clone()?;
+    write!(output, "{}\r\n", transport::VERSION)?;
+    transport::read_version(&mut BufReader::new(stream))?;
+    let reason = match *refusal {
+        Refusal::Banned(_) => DISCONNECT_HOST_NOT_ALLOWED_TO_CONNECT,
+        _ => DISCONNECT_TOO_MANY_CONNECTIONS,
+    };
+    let message = disconnect(reason, &refusal.message());
+    PacketWriter::new(output).write(&message.into_bytes())
+}
+
+fn disconnect(reason: u32, description: &str) -> Message {
+    Message::new(MSG_DISCONNECT).uint32(reason).string(description.as_bytes()).string(b"")
+}
+
+fn timed_out(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut
+}
+
+/// What the reader of a connection asks its writer to send.
+enum Outgoing {
+    /// A message, sent as it is.
+    Message(Vec<u8>),
+    /// A key exchange has started, the data of the channels waits for its
+    /// end.
+    Rekey,
+    /// NEWKEYS has been sent, what follows is encrypted with these keys.
+    Keys(Box<Sealing>),
+    Open { channel: u32, remote: u32, window: u32, max_packet: u32 },
+    Data(u32, Vec<u8>),
+    /// The client has room for more data of a channel.
+    Adjust(u32, u32),
+    /// The session of a channel is over, the channel is closed once its
+    /// data is sent.
+    Exit(u32),
+    /// The client closed a channel.
+    Closed(u32),
+}
+
+fn connection(host: &Host, stream: TcpStream, visit: &Visit) -> Result<(), io::Error> {
+    // Anything the client sends wakes the reader up, to see how long ago
+    // the last key was.
+    stream.set_read_timeout(Some(host.idle))?;
+    let peer = stream.peer_addr()?;
+    let mut output = stream.try_clone()?;
+    write!(output, "{}\r\n", transport::VERSION)?;
+    let mut input = BufReader::new(stream.try_clone()?);
+    let client_version = transport::read_version(&mut input)?;
+
+    let (tx, rx) = mpsc::channel();
+    let writer = thread::spawn(move || write_all(PacketWriter::new(output), rx));
+    let result = Connection {
+        host,
+        visit,
+        packets: PacketReader::new(input),
+        tx,
+        client_version,
+        session_id: None,
+        authenticated: false,
+        channels: HashMap::new(),
+        next_channel: 0,
+        shell: false,
+        last_key: Instant::now(),
+        peer,
+    }
+    .run();
+    // Sessions still running end as their channels are dropped, and then
+    // the writer. An idle client is given a moment to take the disconnect
+    // message first.
+    match result {
+        Err(ref err) if timed_out(err) => {
+            stream.set_write_timeout(Some(GOODBYE)).ok();
+            writer.join().ok();
+            stream.shutdown(Shutdown::Both).ok();
+            Ok(())
+        }
+        result => {
+            stream.shutdown(Shutdown::Both).ok();
+            writer.join().ok();
+            result
+        }
+    }
+}
+
+/// A channel the client has opened, as the reader sees it.
+struct Channel {
+    remote: u32,
+    /// The size of the pseudo-terminal and of its cells, once the client
+    /// asked for one.
+    pty: Option<(Rect, (u32, u32))>,
+    /// `TERM` and the variables the client sent.
+    vars: HashMap<String, String>,
+    /// The events and the input of the session, once it runs.
+    events: Option<Sender<Event>>,
+    input: Option<Sender<Vec<u8>>>,
+    /// The bytes received since the window was last adjusted.
+    received: u32,
+}
+
+struct Connection<'a> {
+    host: &'a Host,
+    visit: &'a Visit,
+    packets: PacketReader<BufReader<TcpStream>>,
+    tx: Sender<Outgoing>,
+    client_version: String,
+    session_id: Option<Vec<u8>>,
+    authenticated: bool,
+    channels: HashMap<u32, Channel>,
+    next_channel: u32,
+    /// Whether the session of the connection started. It has one, on one
+    /// channel at a time, as it was let in as one.
+    shell: bool,
+    last_key: Instant,
+    /// The address of the client, for what goes wrong in its session.
+    peer: SocketAddr,
+}
+
+fn writer_gone() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "the connection is closed")
+}
+
+/// The size of a terminal from a `pty-req` or `window-change` request,
+/// which gives its columns, rows and pixels.
+fn size(reader: &mut Reader) -> Result<(Rect, (u32, u32)), io::Error> {
+    let clamp = |n: u32| cmp::min(n, u16::max_value() as u32) as u16;
+    let (columns, rows) = (clamp(reader.uint32()?), clamp(reader.uint32()?));
+    let pixels = (clamp(reader.uint32()?), clamp(reader.uint32()?));
+    let (columns, rows) = if columns == 0 || rows == 0 { (80, 24) } else { (columns, rows) };
+    Ok((Rect::new(0, 0, columns, rows), graphics::cell_size(columns, rows, pixels)))
+}
+
+impl<'a> Connection<'a> {
+    fn send(&self, outgoing: Outgoing) -> Result<(), io::Error> {
+        self.tx.send(outgoing).map_err(|_| writer_gone())
+    }
+
+    fn message(&self, message: Message) -> Result<(), io::Error> {
+        self.send(Outgoing::Message(message.into_bytes()))
+    }
+
+    /// Reads the next packet, which has to be a `kind` message.
+    fn expect(&mut self, kind: u8) -> Result<Vec<u8>, io::Error> {
+        loop {
+            let packet = self.packets.read()?;
+            match packet.first() {
+                Some(&k) if k == kind => return Ok(packet),
+                Some(&MSG_IGNORE) | Some(&MSG_DEBUG) => {}
+                _ => return Err(transport::error("unexpected message during the key exchange")),
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<(), io::Error> {
+        self.key_exchange(None)?;
+        loop {
+            let packet = match self.packets.read() {
+                Err(ref err) if timed_out(err) => Vec::new(),
+                packet => packet?,
+            };
+            if self.last_key.elapsed() >= self.host.idle {
+                let description = format!("Closed after {} seconds without a key.", self.host.idle.as_secs());
+                self.message(disconnect(DISCONNECT_BY_APPLICATION, &description))?;
+                return Err(io::Error::new(io::ErrorKind::TimedOut, description));
+            }
+            if packet.is_empty() {
+                continue;
+            }
+            let mut reader = Reader::new(&packet);
+            match reader.byte()? {
+                MSG_DISCONNECT => return Ok(()),
+                MSG_IGNORE | MSG_DEBUG | MSG_UNIMPLEMENTED => {}
+                transport::MSG_KEXINIT => self.key_exchange(Some(packet.clone()))?,
+                MSG_SERVICE_REQUEST => {
+                    let service = reader.string()?;
+                    if service != b"ssh-userauth" {
+                        return Err(transport::error("unknown service"));
+                    }
+                    self.message(Message::new(MSG_SERVICE_ACCEPT).string(service))?;
+                }
+                MSG_USERAUTH_REQUEST => self.authenticate(&mut reader)?,
+                _ if !self.authenticated => return Err(transport::error("request before authentication")),
+                MSG_GLOBAL_REQUEST => {
+                    reader.string()?;
+                    if reader.boolean()? {
+                        self.message(Message::new(MSG_REQUEST_FAILURE))?;
+                    }
+                }
+                MSG_CHANNEL_OPEN => self.open(&mut reader)?,
+                MSG_CHANNEL_REQUEST => self.request(&mut reader)?,
+                MSG_CHANNEL_DATA => self.data(&mut reader)?,
+                MSG_CHANNEL_WINDOW_ADJUST => {
+                    let (channel, bytes) = (reader.uint32()?, reader.uint32()?);
+                    self.send(Outgoing::Adjust(channel, bytes))?;
+                }
+                MSG_CHANNEL_EOF => {
+                    if let Some(channel) = self.channels.get_mut(&reader.uint32()?) {
+                        channel.input = None;
+                    }
+                }
+                MSG_CHANNEL_CLOSE => {
+                    let channel = reader.uint32()?;
+                    self.channels.remove(&channel);
+                    self.send(Outgoing::Closed(channel))?;
+                }
+                // Requests answered before are of no interest, and neither
+                // is the standard error of the client.
+                MSG_CHANNEL_SUCCESS | MSG_CHANNEL_FAILURE | 95 => {}
+                _ => {
+                    let sequence = self.packets.sequence();
+                    self.message(Message::new(MSG_UNIMPLEMENTED).uint32(sequence))?;
+                }
+            }
+        }
+    }
+
+    /// Runs a key exchange, the first one or one the client started with
+    /// `client_kexinit`.
+    fn key_exchange(&mut self, client_kexinit: Option<Vec<u8>>) -> Result<(), io::Error> {
+        if self.session_id.is_some() {
+            self.send(Outgoing::Rekey)?;
+        }
+        let server_kexinit = transport::kexinit()?;
+        self.send(Outgoing::Message(server_kexinit.clone()))?;
+        let client_kexinit = match client_kexinit {
+            Some(kexinit) => kexinit,
+            None => self.expect(transport::MSG_KEXINIT)?,
+        };
+        let algorithms = transport::negotiate(&client_kexinit)?;
+        if algorithms.wrong_guess {
+            self.packets.read()?;
+        }
+        let init = self.expect(transport::MSG_KEX_ECDH_INIT)?;
+        let exchange = Exchange {
+            client_version: &self.client_version,
+            client_kexinit: &client_kexinit,
+            server_kexinit: &server_kexinit,
+        };
+        let (reply, keys) = transport::exchange(&exchange, &algorithms, &self.host.key, &init, &mut self.session_id)?;
+        let (opening, sealing) = keys.split();
+        self.send(Outgoing::Message(reply))?;
+        self.send(Outgoing::Message(vec![transport::MSG_NEWKEYS]))?;
+        self.send(Outgoing::Keys(Box::new(sealing)))?;
+        self.expect(transport::MSG_NEWKEYS)?;
+        self.packets.set_keys(opening);
+        Ok(())
+    }
+
+    /// Lets in any user, or those allowed, whatever the method.
+    fn authenticate(&mut self, reader: &mut Reader) -> Result<(), io::Error> {
+        let user = reader.text()?;
+        if self.authenticated {
+            return Ok(());
+        }
+        if self.host.allow.is_empty() || self.host.allow.contains(&user) {
+            self.authenticated = true;
+            self.message(Message::new(MSG_USERAUTH_SUCCESS))
+        } else {
+            // No method can work, so none is offered.
+            self.message(Message::new(MSG_USERAUTH_FAILURE).string(b"").boolean(false))
+        }
+    }
+
+    fn open(&mut self, reader: &mut Reader) -> Result<(), io::Error> {
+        let kind = reader.string()?;
+        let remote = reader.uint32()?;
+        let (window, max_packet) = (reader.uint32()?, reader.uint32()?);
+        if kind != b"session" {
+            return self.message(
+                Message::new(MSG_CHANNEL_OPEN_FAILURE)
+                    .uint32(remote)
+                    .uint32(OPEN_UNKNOWN_CHANNEL_TYPE)
+                    .string(b"only sessions are supported")
+                    .string(b""),
+            );
+        }
+        if !self.channels.is_empty() {
+            return self.message(
+                Message::new(MSG_CHANNEL_OPEN_FAILURE)
+                    .uint32(remote)
+                    .uint32(OPEN_ADMINISTRATIVELY_PROHIBITED)
+                    .string(b"a connection has one session")
+                    .string(b""),
+            );
+        }
+        let channel = self.next_channel;
+        self.next_channel += 1;
+        self.channels.insert(
+            channel,
+            Channel { remote, pty: None, vars: HashMap::new(), events: None, input: None, received: 0 },
+        );
+        self.send(Outgoing::Open { channel, remote, window, max_packet })?;
+        self.message(
+            Message::new(MSG_CHANNEL_OPEN_CONFIRMATION)
+                .uint32(remote)
+                .uint32(channel)
+                .uint32(WINDOW)
+                .uint32(MAX_DATA),
+        )
+    }
+
+    fn request(&mut self, reader: &mut Reader) -> Result<(), io::Error> {
+        let id = reader.uint32()?;
+        let kind = reader.text()?;
+        let want_reply = reader.boolean()?;
+        let done = match self.channels.get_mut(&id) {
+            Some(channel) => match kind.as_str() {
+                "pty-req" => {
+                    let term = reader.text()?;
+                    channel.pty = Some(size(reader)?);
+                    channel.vars.insert("TERM".to_string(), term);
+                    true
+                }
+                "env" => {
+                    let (name, value) = (reader.text()?, reader.text()?);
+                    channel.vars.insert(name, value);
+                    true
+                }
+                "window-change" => {
+                    let (size, cell) = size(reader)?;
+                    channel.pty = Some((size, cell));
+                    if let Some(ref events) = channel.events {
+                        events.send(Event::Resize(size, cell)).ok();
+                    }
+                    true
+                }
+                "shell" if !self.shell => true,
+                _ => false,
+            },
+            None => return Err(transport::error("request for an unknown channel")),
+        };
+        if done && kind == "shell" {
+            self.shell(id)?;
+        }
+        if want_reply {
+            let remote = self.channels[&id].remote;
+            let kind = if done { MSG_CHANNEL_SUCCESS } else { MSG_CHANNEL_FAILURE };
+            self.message(Message::new(kind).uint32(remote))?;
+        }
+        Ok(())
+    }
+
+    /// Starts a session of the UI on a channel, which needs a terminal.
+    fn shell(&mut self, id: u32) -> Result<(), io::Error> {
+        self.shell = true;
+        let channel = self.channels.get_mut(&id).expect("the channel exists");
+        let (size, cell) = match channel.pty {
+            Some(pty) => pty,
+            None => {
+                let message = b"rust-cv needs a terminal, connect with ssh -t\r\n".to_vec();
+                self.tx.send(Outgoing::Data(id, message)).map_err(|_| writer_gone())?;
+                return self.send(Outgoing::Exit(id));
+            }
+        };
+        let settings = Settings::new(&self.host.ui, &Term::remote(channel.vars.clone()));
+        let (events, rx) = mpsc::channel();
+        let (input, data) = mpsc::channel();
+        channel.events = Some(events.clone());
+        channel.input = Some(input);
+
+        thread::spawn(move || ::forward_keys(Pipe::new(data), &events));
+        let content = self.host.content.get();
+        let output = Output { channel: id, tx: self.tx.clone(), buffer: Vec::new() };
+        let exit = self.tx.clone();
+        let visit = self.visit.clone();
+        let peer = self.peer;
+        thread::spawn(move || {
+            if let Err(err) = ::run_session(&content, settings, size, cell, output, rx, &visit) {
+                note!("rust-cv: ssh: {}: {}", peer, err);
+            }
+            exit.send(Outgoing::Exit(id)).ok();
+        });
+        Ok(())
+    }
+
+    fn data(&mut self, reader: &mut Reader) -> Result<(), io::Error> {
+        let id = reader.uint32()?;
+        let data = reader.string()?;
+        let adjust = match self.channels.get_mut(&id) {
+            Some(channel) => {
+                if let Some(ref input) = channel.input {
+                    input.send(data.to_vec()).ok();
+                    self.last_key = Instant::now();
+                }
+                channel.received += data.len() as u32;
+                if channel.received >= WINDOW / 2 {
+                    Some((channel.remote, mem::replace(&mut channel.received, 0)))
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+        match adjust {
+            Some((remote, bytes)) => self.message(Message::new(MSG_CHANNEL_WINDOW_ADJUST).uint32(remote).uint32(bytes)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A channel as the writer sees it.
+struct Peer {
+    remote: u32,
+    /// How much more data the client takes.
+    window: u32,
+    max_packet: u32,
+    pending: Vec<u8>,
+    exited: bool,
+    closed: bool,
+}
+
+/// Sends what the reader and the sessions of a connection ask for.
+fn write_all(mut packets: PacketWriter<TcpStream>, rx: Receiver<Outgoing>) -> Result<(), io::Error> {
+    let mut peers: HashMap<u32, Peer> = HashMap::new();
+    let mut rekeying = false;
+    for outgoing in rx {
+        match outgoing {
+            Outgoing::Message(message) => packets.write(&message)?,
+            Outgoing::Rekey => rekeying = true,
+            Outgoing::Keys(keys) => {
+                packets.set_keys(*keys);
+                rekeying = false;
+            }
+            Outgoing::Open { channel, remote, window, max_packet } => {
+                let peer = Peer { remote, window, max_packet, pending: Vec::new(), exited: false, closed: false };
+                peers.insert(channel, peer);
+            }
+            Outgoing::Data(channel, data) => {
+                if let Some(peer) = peers.get_mut(&channel) {
+                    if !peer.closed {
+                        peer.pending.extend_from_slice(&data);
+                    }
+                }
+            }
+            Outgoing::Adjust(channel, bytes) => {
+                if let Some(peer) = peers.get_mut(&channel) {
+                    peer.window = peer.window.saturating_add(bytes);
+                }
+            }
+            Outgoing::Exit(channel) => {
+                if let Some(peer) = peers.get_mut(&channel) {
+                    peer.exited = true;
+                }
+            }
+            Outgoing::Closed(channel) => {
+                if let Some(peer) = peers.remove(&channel) {
+                    if !peer.closed {
+                        packets.write(&Message::new(MSG_CHANNEL_CLOSE).uint32(peer.remote).into_bytes())?;
+                    }
+                }
+            }
+        }
+        if !rekeying {
+            for peer in peers.values_mut() {
+                flush(&mut packets, peer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends as much of the data of a channel as the client takes, and closes
+/// it after the last of it once the session is over.
+fn flush(packets: &mut PacketWriter<TcpStream>, peer: &mut Peer) -> Result<(), io::Error> {
+    while !peer.pending.is_empty() && peer.window > 0 {
+        let length = cmp::min(peer.pending.len() as u32, cmp::min(peer.window, cmp::min(peer.max_packet, MAX_DATA))) as usize;
+        let data: Vec<u8> = peer.pending.drain(..length).collect();
+        packets.write(&Message::new(MSG_CHANNEL_DATA).uint32(peer.remote).string(&data).into_bytes())?;
+        peer.window -= length as u32;
+    }
+    if peer.exited && peer.pending.is_empty() && !peer.closed {
+        packets.write(
+            &Message::new(MSG_CHANNEL_REQUEST)
+                .uint32(peer.remote)
+                .string(b"exit-status")
+                .boolean(false)
+                .uint32(0)
+                .into_bytes(),
+        )?;
+        packets.write(&Message::new(MSG_CHANNEL_EOF).uint32(peer.remote).into_bytes())?;
+        packets.write(&Message::new(MSG_CHANNEL_CLOSE).uint32(peer.remote).into_bytes())?;
+        peer.closed = true;
+    }
+    Ok(())
+}
+
+/// What a session writes, sent to its channel on every flush. Copies start
+/// with nothing to send.
+struct Output {
+    channel: u32,
+    tx: Sender<Outgoing>,
+    buffer: Vec<u8>,
+}
+
+impl Clone for Output {
+    fn clone(&self) -> Output {
+        Output { channel: self.channel, tx: self.tx.clone(), buffer: Vec::new() }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let data = mem::take(&mut self.buffer);
+        self.tx.send(Outgoing::Data(self.channel, data)).map_err(|_| writer_gone())
+    }
+}
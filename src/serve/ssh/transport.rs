@@ -0,0 +1,330 @@
+//! The transport layer of SSH (RFC 4253): packets, and the key exchange
+//! that encrypts them.
+//!
+//! Only what current clients all have is offered: curve25519-sha256 for
+//! the exchange (RFC 8731), ssh-ed25519 host keys and AES-GCM (RFC 5647,
+//! as OpenSSH does it), which needs no separate MAC.
+
+use std::io::{self, Read, Write};
+
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey};
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use super::key::HostKey;
+use super::wire::{Message, Reader};
+
+pub const VERSION: &str = "SSH-2.0-rust-cv";
+
+pub const MSG_KEXINIT: u8 = 20;
+pub const MSG_NEWKEYS: u8 = 21;
+pub const MSG_KEX_ECDH_INIT: u8 = 30;
+const MSG_KEX_ECDH_REPLY: u8 = 31;
+
+const KEX: &[&str] = &["curve25519-sha256", "curve25519-sha256@libssh.org"];
+const HOST_KEY: &[&str] = &["ssh-ed25519"];
+const CIPHERS: &[&str] = &["aes256-gcm@openssh.com", "aes128-gcm@openssh.com"];
+/// The MAC of an AEAD cipher is not negotiated, but clients still want a
+/// name in the list.
+const MACS: &[&str] = &["hmac-sha2-256"];
+const COMPRESSION: &[&str] = &["none"];
+
+/// Longer packets are refused, clients keep under 35000 bytes.
+const MAX_PACKET: usize = 256 * 1024;
+const TAG_LEN: usize = 16;
+
+pub fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn random(bytes: &mut [u8]) -> Result<(), io::Error> {
+    SystemRandom::new().fill(bytes).map_err(|_| io::Error::new(io::ErrorKind::Other, "no randomness"))
+}
+
+/// Reads the version line of the client, skipping the lines it may send
+/// before it. The line is returned without its line break.
+pub fn read_version<R: Read>(input: &mut R) -> Result<String, io::Error> {
+    for _ in 0..32 {
+        let mut line = Vec::new();
+        let mut byte = [0u8];
+        while line.len() < 255 {
+            input.read_exact(&mut byte)?;
+            if byte[0] == b'\n' {
+                break;
+            }
+            line.push(byte[0]);
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.starts_with(b"SSH-") {
+            if !line.starts_with(b"SSH-2.0-") && !line.starts_with(b"SSH-1.99-") {
+                return Err(error("unsupported SSH version"));
+            }
+            return String::from_utf8(line).map_err(|_| error("bad version line"));
+        }
+    }
+    Err(error("no SSH version line"))
+}
+
+/// The AES-GCM key of one direction, and its nonce, which counts the
+/// packets in its last eight bytes.
+struct Cipher {
+    key: LessSafeKey,
+    nonce: [u8; 12],
+}
+
+impl Cipher {
+    fn new(name: &str, key: &[u8], nonce: &[u8]) -> Cipher {
+        let algorithm = if name == CIPHERS[0] { &aead::AES_256_GCM } else { &aead::AES_128_GCM };
+        let key = UnboundKey::new(algorithm, &key[..algorithm.key_len()]).expect("the key is as long as the algorithm wants");
+        let mut fixed = [0u8; 12];
+        fixed.copy_from_slice(&nonce[..12]);
+        Cipher { key: LessSafeKey::new(key), nonce: fixed }
+    }
+
+    fn next_nonce(&mut self) -> Nonce {
+        let nonce = Nonce::assume_unique_for_key(self.nonce);
+        let mut counter = [0u8; 8];
+        counter.copy_from_slice(&self.nonce[4..]);
+        self.nonce[4..].copy_from_slice(&u64::from_be_bytes(counter).wrapping_add(1).to_be_bytes());
+        nonce
+    }
+}
+
+/// The keys a key exchange ends with, for packets from the client and to
+/// it.
+pub struct Keys {
+    opening: Cipher,
+    sealing: Cipher,
+}
+
+impl Keys {
+    pub fn split(self) -> (Opening, Sealing) {
+        (Opening(self.opening), Sealing(self.sealing))
+    }
+}
+
+pub struct Opening(Cipher);
+pub struct Sealing(Cipher);
+
+/// Reads the packets of the client.
+pub struct PacketReader<R> {
+    input: R,
+    keys: Option<Opening>,
+    sequence: u32,
+}
+
+impl<R: Read> PacketReader<R> {
+    pub fn new(input: R) -> PacketReader<R> {
+        PacketReader { input, keys: None, sequence: 0 }
+    }
+
+    /// The sequence number of the last packet read.
+    pub fn sequence(&self) -> u32 {
+        self.sequence.wrapping_sub(1)
+    }
+
+    pub fn set_keys(&mut self, keys: Opening) {
+        self.keys = Some(keys);
+    }
+
+    /// The payload of the next packet.
+    pub fn read(&mut self) -> Result<Vec<u8>, io::Error> {
+        let mut length = [0u8; 4];
+        self.input.read_exact(&mut length)?;
+        let size = u32::from_be_bytes(length) as usize;
+        if !(5..=MAX_PACKET).contains(&size) {
+            return Err(error("bad packet length"));
+        }
+        let mut packet = vec![0u8; size + if self.keys.is_some() { TAG_LEN } else { 0 }];
+        self.input.read_exact(&mut packet)?;
+        if let Some(Opening(ref mut cipher)) = self.keys {
+            let nonce = cipher.next_nonce();
+            let plain = cipher.key.open_in_place(nonce, Aad::from(length), &mut packet).map_err(|_| error("corrupted packet"))?.len();
+            packet.truncate(plain);
+        }
+        self.sequence = self.sequence.wrapping_add(1);
+        let padding = packet[0] as usize;
+        if padding + 1 > packet.len() {
+            return Err(error("bad padding length"));
+        }
+        packet.truncate(packet.len() - padding);
+        packet.remove(0);
+        Ok(packet)
+    }
+}
+
+/// Writes packets to the client.
+pub struct PacketWriter<W> {
+    output: W,
+    keys: Option<Sealing>,
+}
+
+impl<W: Write> PacketWriter<W> {
+    pub fn new(output: W) -> PacketWriter<W> {
+        PacketWriter { output, keys: None }
+    }
+
+    pub fn set_keys(&mut self, keys: Sealing) {
+        self.keys = Some(keys);
+    }
+
+    pub fn write(&mut self, payload: &[u8]) -> Result<(), io::Error> {
+        // The length is not encrypted, and so not part of the blocks, once
+        // there are keys.
+        let (block, outside) = if self.keys.is_some() { (16, 0) } else { (8, 4) };
+        let mut padding = block - (outside + 1 + payload.len()) % block;
+        if padding < 4 {
+            padding += block;
+        }
+        let mut packet = Vec::with_capacity(4 + 1 + payload.len() + padding + TAG_LEN);
+        packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+        packet.push(padding as u8);
+        packet.extend_from_slice(payload);
+        let mut filler = vec![0u8; padding];
+        random(&mut filler)?;
+        packet.extend_from_slice(&filler);
+        if let Some(Sealing(ref mut cipher)) = self.keys {
+            let nonce = cipher.next_nonce();
+            let (length, body) = packet.split_at_mut(4);
+            let tag = cipher.key.seal_in_place_separate_tag(nonce, Aad::from(&*length), body).map_err(|_| error("could not encrypt"))?;
+            packet.extend_from_slice(tag.as_ref());
+        }
+        self.output.write_all(&packet)?;
+        self.output.flush()
+    }
+}
+
+/// Our KEXINIT message.
+pub fn kexinit() -> Result<Vec<u8>, io::Error> {
+    let mut cookie = [0u8; 16];
+    random(&mut cookie)?;
+    let list = |names: &[&str]| names.join(",");
+    Ok(Message::new(MSG_KEXINIT)
+        .bytes(&cookie)
+        .string(list(KEX).as_bytes())
+        .string(list(HOST_KEY).as_bytes())
+        .string(list(CIPHERS).as_bytes())
+        .string(list(CIPHERS).as_bytes())
+        .string(list(MACS).as_bytes())
+        .string(list(MACS).as_bytes())
+        .string(list(COMPRESSION).as_bytes())
+        .string(list(COMPRESSION).as_bytes())
+        .string(b"")
+        .string(b"")
+        .boolean(false)
+        .uint32(0)
+        .into_bytes())
+}
+
+/// The algorithms the two sides agree on: the first of the client that the
+/// server has (RFC 4253, section 7.1).
+pub struct Algorithms {
+    cipher_in: String,
+    cipher_out: String,
+    /// Whether the client sent a packet of the key exchange it guessed,
+    /// and guessed wrong, so that the packet is to be ignored.
+    pub wrong_guess: bool,
+}
+
+fn choose(client: &[String], server: &[&str], what: &str) -> Result<String, io::Error> {
+    client
+        .iter()
+        .find(|name| server.contains(&name.as_str()))
+        .cloned()
+        .ok_or_else(|| error(&format!("no {} in common with the client", what)))
+}
+
+pub fn negotiate(client: &[u8]) -> Result<Algorithms, io::Error> {
+    let mut reader = Reader::new(client);
+    reader.byte()?;
+    for _ in 0..16 {
+        reader.byte()?;
+    }
+    let kex = reader.name_list()?;
+    choose(&kex, KEX, "key exchange")?;
+    let host_key = reader.name_list()?;
+    choose(&host_key, HOST_KEY, "host key type")?;
+    let cipher_in = choose(&reader.name_list()?, CIPHERS, "cipher")?;
+    let cipher_out = choose(&reader.name_list()?, CIPHERS, "cipher")?;
+    reader.name_list()?;
+    reader.name_list()?;
+    choose(&reader.name_list()?, COMPRESSION, "compression")?;
+    choose(&reader.name_list()?, COMPRESSION, "compression")?;
+    reader.name_list()?;
+    reader.name_list()?;
+    // The guess is that both sides prefer the same key exchange and host
+    // key type.
+    let preferred = |names: &[String], ours: &[&str]| names.first().map(String::as_str) == ours.first().cloned();
+    let follows = reader.boolean()?;
+    let wrong_guess = follows && !(preferred(&kex, KEX) && preferred(&host_key, HOST_KEY));
+    Ok(Algorithms { cipher_in, cipher_out, wrong_guess })
+}
+
+/// What the exchange hash covers besides the keys.
+pub struct Exchange<'a> {
+    pub client_version: &'a str,
+    pub client_kexinit: &'a [u8],
+    pub server_kexinit: &'a [u8],
+}
+
+fn sha256(parts: &[&[u8]]) -> Vec<u8> {
+    let mut context = digest::Context::new(&digest::SHA256);
+    for part in parts {
+        context.update(part);
+    }
+    context.finish().as_ref().to_vec()
+}
+
+/// Answers the KEX_ECDH_INIT of the client. The session id is the exchange
+/// hash of the first exchange, which is set here.
+pub fn exchange(
+    exchange: &Exchange,
+    algorithms: &Algorithms,
+    host_key: &HostKey,
+    init: &[u8],
+    session_id: &mut Option<Vec<u8>>,
+) -> Result<(Vec<u8>, Keys), io::Error> {
+    let mut reader = Reader::new(init);
+    reader.byte()?;
+    let client_public = reader.string()?;
+
+    let random = SystemRandom::new();
+    let private = EphemeralPrivateKey::generate(&agreement::X25519, &random).map_err(|_| error("could not create a key"))?;
+    let public = private.compute_public_key().map_err(|_| error("could not create a key"))?;
+    let shared = agreement::agree_ephemeral(
+        private,
+        &UnparsedPublicKey::new(&agreement::X25519, client_public),
+        error("bad public key of the client"),
+        |secret| Ok(Message::blob().mpint(secret).into_bytes()),
+    )?;
+
+    let host_blob = host_key.blob();
+    let hashed = Message::blob()
+        .string(exchange.client_version.as_bytes())
+        .string(VERSION.as_bytes())
+        .string(exchange.client_kexinit)
+        .string(exchange.server_kexinit)
+        .string(&host_blob)
+        .string(client_public)
+        .string(public.as_ref())
+        .bytes(&shared)
+        .into_bytes();
+    let hash = sha256(&[&hashed]);
+    let session = session_id.get_or_insert_with(|| hash.clone()).clone();
+
+    // RFC 4253, section 7.2.
+    let derive = |letter: u8| sha256(&[&shared, &hash, &[letter], &session]);
+    let keys = Keys {
+        opening: Cipher::new(&algorithms.cipher_in, &derive(b'C'), &derive(b'A')),
+        sealing: Cipher::new(&algorithms.cipher_out, &derive(b'D'), &derive(b'B')),
+    };
+    let reply = Message::new(MSG_KEX_ECDH_REPLY)
+        .string(&host_blob)
+        .string(public.as_ref())
+        .string(&host_key.sign(&hash))
+        .into_bytes();
+    Ok((reply, keys))
+}
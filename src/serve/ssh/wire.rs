@@ -0,0 +1,104 @@
+//! The data types of SSH messages (RFC 4251, section 5).
+
+use std::io;
+
+/// A message being written.
+pub struct Message(Vec<u8>);
+
+impl Message {
+    pub fn new(kind: u8) -> Message {
+        Message(vec![kind])
+    }
+
+    /// Data that is not a message of its own, like a key.
+    pub fn blob() -> Message {
+        Message(Vec::new())
+    }
+
+    pub fn byte(mut self, value: u8) -> Message {
+        self.0.push(value);
+        self
+    }
+
+    pub fn bytes(mut self, value: &[u8]) -> Message {
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    pub fn boolean(self, value: bool) -> Message {
+        self.byte(value as u8)
+    }
+
+    pub fn uint32(self, value: u32) -> Message {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    pub fn string(self, value: &[u8]) -> Message {
+        self.uint32(value.len() as u32).bytes(value)
+    }
+
+    /// A positive number from its big-endian bytes.
+    pub fn mpint(self, value: &[u8]) -> Message {
+        let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+        let value = &value[start..];
+        if value.first().map(|&b| b & 0x80 != 0).unwrap_or(false) {
+            self.uint32(value.len() as u32 + 1).byte(0).bytes(value)
+        } else {
+            self.string(value)
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// A message being read.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed SSH message")
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], io::Error> {
+        if self.data.len() < length {
+            return Err(malformed());
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    pub fn byte(&mut self) -> Result<u8, io::Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn boolean(&mut self) -> Result<bool, io::Error> {
+        Ok(self.byte()? != 0)
+    }
+
+    pub fn uint32(&mut self) -> Result<u32, io::Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn string(&mut self) -> Result<&'a [u8], io::Error> {
+        let length = self.uint32()? as usize;
+        self.take(length)
+    }
+
+    pub fn text(&mut self) -> Result<String, io::Error> {
+        Ok(String::from_utf8_lossy(self.string()?).into_owned())
+    }
+
+    pub fn name_list(&mut self) -> Result<Vec<String>, io::Error> {
+        Ok(self.text()?.split(',').filter(|n| !n.is_empty()).map(|n| n.to_string()).collect())
+    }
+}
@@ -0,0 +1,328 @@
+//! The terminal UI over Telnet (RFC 854).
+//!
+//! The server echoes nothing and has every key sent as it is typed, with
+//! ECHO and SUPPRESS-GO-AHEAD (RFC 857, 858). It learns the size of the
+//! window of the client with NAWS (RFC 1073), and the type of its terminal
+//! with TERMINAL-TYPE (RFC 1091), before the session starts.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tui::layout::Rect;
+
+use cli::{Listen, Ui};
+use graphics;
+use term::Term;
+use visits::Visit;
+use {Content, Event, Settings};
+
+use super::limits::{Guard, Refusal};
+use super::{Pipe, Shared};
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const BINARY: u8 = 0;
+const ECHO: u8 = 1;
+const SGA: u8 = 3;
+const TTYPE: u8 = 24;
+const NAWS: u8 = 31;
+
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+
+/// How long a client has to answer before the session starts anyway.
+const NEGOTIATION: Duration = Duration::from_millis(1500);
+
+/// The size of a terminal that does not tell.
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+struct Host {
+    ui: Ui,
+    idle: Duration,
+    content: Arc<Shared<Content>>,
+}
+
+pub fn serve(listen: &Listen, idle: Duration, ui: Ui, guard: Guard, content: Arc<Shared<Content>>) -> Result<(), io::Error> {
+    let host = Host { ui, idle, content };
+    super::serve(listen, "telnet", "Telnet", guard, move |stream, visit| connection(&host, stream, visit), refuse)
+}
+
+fn refuse(mut stream: TcpStream, refusal: &Refusal) -> Result<(), io::Error> {
+    write!(stream, "{}\r\n", refusal.message())
+}
+
+/// What the client says besides the data.
+enum Command {
+    Will(u8),
+    Wont(u8),
+    Do(u8),
+    Size(u16, u16),
+    Type(String),
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Data,
+    /// After a carriage return, which clients follow with a NUL or a line
+    /// feed that is not a key of its own.
+    Return,
+    Iac,
+    Verb(u8),
+    Sub,
+    SubIac,
+}
+
+/// Splits what the client sends into data and commands. Commands may be
+/// cut between two reads.
+struct Parser {
+    state: State,
+    sub: Vec<u8>,
+}
+
+impl Parser {
+    fn feed(&mut self, bytes: &[u8], data: &mut Vec<u8>, commands: &mut Vec<Command>) {
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (State::Return, 0) | (State::Return, b'\n') => State::Data,
+                (State::Data, IAC) | (State::Return, IAC) => State::Iac,
+                (State::Data, b'\r') | (State::Return, b'\r') => {
+                    data.push(byte);
+                    State::Return
+                }
+                (State::Data, _) | (State::Return, _) => {
+                    data.push(byte);
+                    State::Data
+                }
+                (State::Iac, IAC) => {
+                    data.push(IAC);
+                    State::Data
+                }
+                (State::Iac, WILL) | (State::Iac, WONT) | (State::Iac, DO) | (State::Iac, DONT) => State::Verb(byte),
+                (State::Iac, SB) => {
+                    self.sub.clear();
+                    State::Sub
+                }
+                // NOP, GA and the other commands mean nothing here.
+                (State::Iac, _) => State::Data,
+                (State::Verb(verb), option) => {
+                    match verb {
+                        WILL => commands.push(Command::Will(option)),
+                        WONT => commands.push(Command::Wont(option)),
+                        DO => commands.push(Command::Do(option)),
+                        _ => {}
+                    }
+                    State::Data
+                }
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, _) => {
+                    if self.sub.len() < 64 {
+                        self.sub.push(byte);
+                    }
+                    State::Sub
+                }
+                (State::SubIac, IAC) => {
+                    self.sub.push(IAC);
+                    State::Sub
+                }
+                (State::SubIac, SE) => {
+                    commands.extend(subnegotiation(&self.sub));
+                    State::Data
+                }
+                (State::SubIac, _) => State::Data,
+            };
+        }
+    }
+}
+
+fn subnegotiation(sub: &[u8]) -> Option<Command> {
+    match sub.first() {
+        Some(&NAWS) if sub.len() == 5 => Some(Command::Size(
+            u16::from_be_bytes([sub[1], sub[2]]),
+            u16::from_be_bytes([sub[3], sub[4]]),
+        )),
+        // Names are in upper case, which no TERM is.
+        Some(&TTYPE) if sub.get(1) == Some(&TTYPE_IS) => Some(Command::Type(String::from_utf8_lossy(&sub[2..]).to_lowercase())),
+        _ => None,
+    }
+}
+
+/// The options of a connection, as negotiated so far.
+struct Telnet {
+    parser: Parser,
+    size: Option<(u16, u16)>,
+    term: Option<String>,
+    /// Whether the client refused NAWS and TERMINAL-TYPE.
+    no_size: bool,
+    no_term: bool,
+}
+
+impl Telnet {
+    fn negotiated(&self) -> bool {
+        (self.size.is_some() || self.no_size) && (self.term.is_some() || self.no_term)
+    }
+
+    /// Handles what the client sent, answering its commands, and returns
+    /// the data and whether the size of the window changed.
+    fn receive(&mut self, bytes: &[u8], out: &mut dyn Write) -> Result<(Vec<u8>, bool), io::Error> {
+        let (mut data, mut commands) = (Vec::new(), Vec::new());
+        self.parser.feed(bytes, &mut data, &mut commands);
+        let mut resized = false;
+        for command in commands {
+            match command {
+                Command::Will(TTYPE) if self.term.is_none() => out.write_all(&[IAC, SB, TTYPE, TTYPE_SEND, IAC, SE])?,
+                Command::Will(NAWS) | Command::Will(TTYPE) | Command::Will(SGA) | Command::Will(BINARY) => {}
+                Command::Will(option) => out.write_all(&[IAC, DONT, option])?,
+                Command::Wont(NAWS) => self.no_size = true,
+                Command::Wont(TTYPE) => self.no_term = true,
+                Command::Wont(_) => {}
+                Command::Do(ECHO) | Command::Do(SGA) | Command::Do(BINARY) => {}
+                Command::Do(option) => out.write_all(&[IAC, WONT, option])?,
+                Command::Size(columns, rows) => {
+                    self.size = Some((columns, rows));
+                    resized = true;
+                }
+                Command::Type(name) => {
+                    if self.term.is_none() {
+                        self.term = Some(name);
+                    }
+                }
+            }
+        }
+        out.flush()?;
+        Ok((data, resized))
+    }
+
+    fn size(&self) -> (Rect, (u32, u32)) {
+        let (columns, rows) = match self.size {
+            Some((columns, rows)) if columns > 0 && rows > 0 => (columns, rows),
+            _ => DEFAULT_SIZE,
+        };
+        (Rect::new(0, 0, columns, rows), graphics::cell_size(columns, rows, (0, 0)))
+    }
+}
+
+fn timed_out(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut
+}
+
+fn connection(host: &Host, stream: TcpStream, visit: &Visit) -> Result<(), io::Error> {
+    let stream = Arc::new(stream);
+    let mut out = &*stream;
+    out.write_all(&[
+        IAC, WILL, ECHO, IAC, WILL, SGA, IAC, DO, SGA, IAC, WILL, BINARY, IAC, DO, BINARY, IAC, DO, NAWS, IAC, DO, TTYPE,
+    ])?;
+    let mut telnet = Telnet {
+        parser: Parser { state: State::Data, sub: Vec::new() },
+        size: None,
+        term: None,
+        no_size: false,
+        no_term: false,
+    };
+    let mut buf = [0u8; 1024];
+    let mut typed = Vec::new();
+    let start = Instant::now();
+    stream.set_read_timeout(Some(Duration::from_millis(100)))?;
+    while !telnet.negotiated() && start.elapsed() < NEGOTIATION {
+        match (&*stream).read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => typed.extend(telnet.receive(&buf[..n], &mut out)?.0),
+            Err(ref err) if timed_out(err) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let mut vars = HashMap::new();
+    if let Some(ref term) = telnet.term {
+        vars.insert("TERM".to_string(), term.clone());
+    }
+    let settings = Settings::new(&host.ui, &Term::remote(vars));
+    let (size, cell) = telnet.size();
+    let (events, rx) = mpsc::channel();
+    let (input, data) = mpsc::channel();
+    input.send(typed).ok();
+    let keys = events.clone();
+    thread::spawn(move || ::forward_keys(Pipe::new(data), &keys));
+    let content = host.content.get();
+    let output = Output { stream: stream.clone(), buffer: Vec::new() };
+    let visit = visit.clone();
+    let peer = stream.peer_addr()?;
+    let session = thread::spawn(move || {
+        if let Err(err) = ::run_session(&content, settings, size, cell, output.clone(), rx, &visit) {
+            note!("rust-cv: telnet: {}: {}", peer, err);
+        }
+        output.stream.shutdown(Shutdown::Both).ok();
+    });
+
+    // Any key counts as activity, resizing the window does not.
+    stream.set_read_timeout(Some(host.idle))?;
+    let mut last_key = Instant::now();
+    loop {
+        match (&*stream).read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                let (data, resized) = telnet.receive(&buf[..n], &mut out)?;
+                if resized {
+                    let (size, cell) = telnet.size();
+                    events.send(Event::Resize(size, cell)).ok();
+                }
+                if !data.is_empty() {
+                    last_key = Instant::now();
+                    input.send(data).ok();
+                }
+            }
+            Err(ref err) if timed_out(err) => {}
+            Err(_) => break,
+        }
+        if last_key.elapsed() >= host.idle {
+            break;
+        }
+    }
+    // The session ends without its input, and closes the connection.
+    drop(events);
+    drop(input);
+    session.join().ok();
+    Ok(())
+}
+
+/// What a session writes, sent on every flush with the bytes that would be
+/// commands escaped. Copies start with nothing to send.
+struct Output {
+    stream: Arc<TcpStream>,
+    buffer: Vec<u8>,
+}
+
+impl Clone for Output {
+    fn clone(&self) -> Output {
+        Output { stream: self.stream.clone(), buffer: Vec::new() }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte == IAC {
+                self.buffer.push(IAC);
+            }
+            self.buffer.push(byte);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let buffer = mem::take(&mut self.buffer);
+        (&*self.stream).write_all(&buffer)?;
+        (&*self.stream).flush()
+    }
+}
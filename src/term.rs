@@ -0,0 +1,31 @@
+//! What is known of the terminal a session of the UI runs in: its
+//! environment variables, from which links and graphics are detected.
+//!
+//! A local session has those of the process. A remote one only has those
+//! its client sent, which are usually `TERM` and the locale.
+
+use std::collections::HashMap;
+use std::env;
+
+pub struct Term {
+    vars: HashMap<String, String>,
+}
+
+impl Term {
+    pub fn local() -> Term {
+        Term { vars: env::vars().collect() }
+    }
+
+    pub fn remote(vars: HashMap<String, String>) -> Term {
+        Term { vars }
+    }
+
+    /// The value of a variable, empty when it is not set.
+    pub fn var(&self, name: &str) -> &str {
+        self.vars.get(name).map(|v| v.as_str()).unwrap_or("")
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+}
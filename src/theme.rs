@@ -0,0 +1,81 @@
+//! The roles text plays in the curriculum and how each one looks.
+//!
+//! The terminal UI takes its styles from here, and the document exporters
+//! name their styles after the same roles so a restyled document keeps the
+//! structure of the UI.
+
+use tui::style::{Color, Modifier, Style};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The title of a panel, e.g. "About me".
+    Section,
+    /// The title of an entry inside a panel, e.g. a course or a skill.
+    Heading,
+    /// The name in front of a value, e.g. "Email:".
+    Label,
+    /// Words marked with `**` in the content.
+    Emphasis,
+    /// Running text.
+    Body,
+    /// The bar of a skill level.
+    Gauge,
+    Tab,
+    SelectedTab,
+    /// The panel or entry that is copied with `y`.
+    Focus,
+}
+
+impl Role {
+    /// The name used for the style of this role in exported documents.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Role::Section => "Section",
+            Role::Heading => "Heading",
+            Role::Label => "Label",
+            Role::Emphasis => "Emphasis",
+            Role::Body => "Body",
+            Role::Gauge => "Gauge",
+            Role::Tab => "Tab",
+            Role::SelectedTab => "SelectedTab",
+            Role::Focus => "Focus",
+        }
+    }
+
+    pub fn style(&self) -> Style {
+        match *self {
+            Role::Section => Style::default().fg(Color::Green).modifier(Modifier::Bold),
+            Role::Heading | Role::Label => Style::default().fg(Color::Yellow).modifier(Modifier::Bold),
+            Role::Emphasis => Style::default().modifier(Modifier::Bold),
+            Role::Body => Style::default(),
+            Role::Gauge => Style::default().fg(Color::Magenta).bg(Color::Black).modifier(Modifier::Italic),
+            Role::Tab => Style::default().fg(Color::Green),
+            Role::SelectedTab => Style::default().fg(Color::Yellow),
+            Role::Focus => Style::default().fg(Color::Cyan).modifier(Modifier::Bold),
+        }
+    }
+
+    /// The markup `Paragraph` uses to show text in this role.
+    pub fn markup(&self, text: &str) -> String {
+        match *self {
+            Role::Heading | Role::Label => format!("{{mod=bold;fg=yellow {}}}", text),
+            Role::Emphasis => format!("{{mod=bold {}}}", text),
+            Role::Focus => format!("{{mod=bold;fg=cyan {}}}", text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// The colour of a role in documents, as `#rrggbb`. Terminal colours are
+/// darkened a bit so they stay readable on paper.
+pub fn hex(color: Color) -> Option<&'static str> {
+    match color {
+        Color::Green => Some("#2e7d32"),
+        Color::Yellow => Some("#9e7c00"),
+        Color::Magenta => Some("#8e24aa"),
+        Color::Red => Some("#c62828"),
+        Color::Blue => Some("#1565c0"),
+        Color::Cyan => Some("#00838f"),
+        _ => None,
+    }
+}
@@ -0,0 +1,263 @@
+//! Who looked at the curriculum on the servers. Every session writes what
+//! it does to `visits.log` in the log directory, a JSON object a line, with
+//! the address of the client cut down to its network so visitors cannot be
+//! told apart by it. The Dashboard tab of the local UI sums the log up.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{self, Value};
+
+use TABS;
+
+const FILE: &str = "visits.log";
+/// How far back the dashboard shows visits, an hour a bar.
+pub const HOURS: usize = 7 * 24;
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+/// The network of an address, as much of it as a provider gives a home:
+/// the first three bytes of IPv4 addresses and the first 48 bits of IPv6
+/// ones.
+pub fn anonymise(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets = v4.octets();
+            IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], 0))
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            IpAddr::V6(Ipv6Addr::new(segments[0], segments[1], segments[2], 0, 0, 0, 0, 0))
+        }
+    }
+}
+
+/// What a session does.
+pub enum Action<'a> {
+    /// A tab of the UI is opened, or a section is asked for.
+    Tab(&'a str),
+    /// Something is taken away: a copy, or the curriculum in a format.
+    Export(&'a str),
+    /// The panels are searched for some text.
+    Search(&'a str),
+    /// The guestbook is signed.
+    Sign,
+    /// A message is sent with the contact form.
+    Contact,
+}
+
+/// The log a server writes its visits to. Lines are written at once to a
+/// file opened for appending, so the processes of sessions share it
+/// without a lock.
+pub struct Log {
+    file: File,
+    /// When the server started, which with its process and a count names
+    /// the sessions.
+    started: u64,
+    sessions: AtomicUsize,
+}
+
+impl Log {
+    /// Opens the log of `dir`, which only its owner reads.
+    pub fn open(dir: &str) -> Result<Log, io::Error> {
+        let path = Path::new(dir).join(FILE);
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(&path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        Ok(Log { file, started: now(), sessions: AtomicUsize::new(0) })
+    }
+}
+
+impl AsRawFd for Log {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// A session of a client, which its copies write the log of.
+#[derive(Clone)]
+pub struct Visit {
+    log: Option<Arc<Log>>,
+    session: String,
+    protocol: &'static str,
+    address: String,
+    start: Instant,
+}
+
+impl Visit {
+    /// A session nothing is written of, that of the local UI.
+    pub fn none() -> Visit {
+        Visit { log: None, session: String::new(), protocol: "", address: String::new(), start: Instant::now() }
+    }
+
+    /// Starts the session of a client at `ip`, written down when there is a
+    /// log.
+    pub fn begin(log: &Option<Arc<Log>>, protocol: &'static str, ip: IpAddr) -> Visit {
+        let log = match *log {
+            Some(ref log) => log.clone(),
+            // The guestbook and the contact form still tell clients apart.
+            None => return Visit { address: anonymise(ip).to_string(), ..Visit::none() },
+        };
+        let count = log.sessions.fetch_add(1, Ordering::Relaxed);
+        let visit = Visit {
+            session: format!("{:x}-{}-{}", log.started, process::id(), count),
+            log: Some(log),
+            protocol,
+            address: anonymise(ip).to_string(),
+            start: Instant::now(),
+        };
+        visit.write(json!({ "event": "connect" }));
+        visit
+    }
+
+    pub fn record(&self, action: Action) {
+        match action {
+            Action::Tab(tab) => self.write(json!({ "event": "tab", "tab": tab })),
+            Action::Export(what) => self.write(json!({ "event": "export", "what": what })),
+            Action::Search(query) => self.write(json!({ "event": "search", "query": query })),
+            Action::Sign => self.write(json!({ "event": "sign" })),
+            Action::Contact => self.write(json!({ "event": "contact" })),
+        }
+    }
+
+    /// The network of the client, as the log has it; empty for the local
+    /// UI.
+    pub fn network(&self) -> &str {
+        &self.address
+    }
+
+    /// Ends the session.
+    pub fn end(&self) {
+        self.write(json!({ "event": "disconnect", "seconds": self.start.elapsed().as_secs() }));
+    }
+
+    fn write(&self, mut entry: Value) {
+        let log = match self.log {
+            Some(ref log) => log,
+            None => return,
+        };
+        entry["time"] = json!(now());
+        entry["session"] = json!(self.session);
+        entry["protocol"] = json!(self.protocol);
+        entry["address"] = json!(self.address);
+        let line = format!("{}\n", entry);
+        // A visit that cannot be written is no reason to end the session.
+        (&log.file).write_all(line.as_bytes()).ok();
+    }
+}
+
+/// A tab, how often it was opened and for how long.
+pub struct Tab {
+    pub title: String,
+    pub views: u64,
+    pub seconds: u64,
+}
+
+/// The sum of a log.
+pub struct Stats {
+    pub sessions: u64,
+    /// The networks the sessions came from.
+    pub networks: usize,
+    /// The sessions of every protocol, from the most used.
+    pub protocols: Vec<(String, u64)>,
+    /// Those of the UI first, in order, and then any other.
+    pub tabs: Vec<Tab>,
+    pub exports: u64,
+    pub searches: u64,
+    /// How long the sessions that ended lasted, in all.
+    pub seconds: u64,
+    pub ended: u64,
+    /// The sessions that started in every one of the last `HOURS` hours, up
+    /// to the current one.
+    pub hourly: Vec<u64>,
+}
+
+impl Stats {
+    fn tab(&mut self, title: &str) -> &mut Tab {
+        match self.tabs.iter().position(|tab| tab.title == title) {
+            Some(i) => &mut self.tabs[i],
+            None => {
+                self.tabs.push(Tab { title: title.to_string(), views: 0, seconds: 0 });
+                self.tabs.last_mut().expect("a tab was just added")
+            }
+        }
+    }
+}
+
+/// Sums up the log of `dir`, skipping lines that are not entries.
+pub fn read(dir: &str) -> Result<Stats, io::Error> {
+    let path = Path::new(dir).join(FILE);
+    let file = File::open(&path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let mut stats = Stats {
+        sessions: 0,
+        networks: 0,
+        protocols: Vec::new(),
+        tabs: TABS.iter().map(|title| Tab { title: title.to_string(), views: 0, seconds: 0 }).collect(),
+        exports: 0,
+        searches: 0,
+        seconds: 0,
+        ended: 0,
+        hourly: vec![0; HOURS],
+    };
+    let hour = now() / 3600;
+    let mut networks = HashSet::new();
+    let mut protocols: HashMap<String, u64> = HashMap::new();
+    // The tab every session is on, and since when.
+    let mut open: HashMap<String, (String, u64)> = HashMap::new();
+    for line in BufReader::new(file).lines() {
+        let entry: Value = match serde_json::from_str(&line?) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let (event, session, time) = match (entry["event"].as_str(), entry["session"].as_str(), entry["time"].as_u64()) {
+            (Some(event), Some(session), Some(time)) => (event, session, time),
+            _ => continue,
+        };
+        if event == "tab" || event == "disconnect" {
+            if let Some((tab, since)) = open.remove(session) {
+                stats.tab(&tab).seconds += time.saturating_sub(since);
+            }
+        }
+        match event {
+            "connect" => {
+                stats.sessions += 1;
+                networks.insert(entry["address"].as_str().unwrap_or("").to_string());
+                *protocols.entry(entry["protocol"].as_str().unwrap_or("").to_string()).or_insert(0) += 1;
+                let ago = hour.saturating_sub(time / 3600) as usize;
+                if ago < HOURS {
+                    stats.hourly[HOURS - 1 - ago] += 1;
+                }
+            }
+            "tab" => {
+                let title = entry["tab"].as_str().unwrap_or("").to_string();
+                stats.tab(&title).views += 1;
+                open.insert(session.to_string(), (title, time));
+            }
+            "export" => stats.exports += 1,
+            "search" => stats.searches += 1,
+            "disconnect" => {
+                stats.ended += 1;
+                stats.seconds += entry["seconds"].as_u64().unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    stats.networks = networks.len();
+    stats.protocols = protocols.into_iter().collect();
+    stats.protocols.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(stats)
+}
@@ -0,0 +1,32 @@
+//! Lines of text drawn as they are, centered, for banners in FIGlet fonts
+//! that `Paragraph` would take as markup.
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Style;
+use tui::widgets::Widget;
+
+pub struct Banner<'a> {
+    lines: &'a [String],
+    style: Style,
+}
+
+impl<'a> Banner<'a> {
+    pub fn new(lines: &'a [String]) -> Banner<'a> {
+        Banner { lines, style: Style::default() }
+    }
+
+    pub fn style(mut self, style: Style) -> Banner<'a> {
+        self.style = style;
+        self
+    }
+}
+
+impl<'a> Widget for Banner<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        for (line, y) in self.lines.iter().zip(area.top()..area.bottom()) {
+            let width = (line.chars().count() as u16).min(area.width);
+            buf.set_stringn(area.left() + (area.width - width) / 2, y, line, width as usize, &self.style);
+        }
+    }
+}
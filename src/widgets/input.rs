@@ -0,0 +1,135 @@
+//! A line of text the user types, for the forms of the UI.
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::widgets::Widget;
+
+/// What has been typed in a field, and where the cursor is in it, in
+/// characters.
+pub struct Input {
+    text: String,
+    cursor: usize,
+    /// How many characters the field takes.
+    limit: usize,
+}
+
+impl Input {
+    pub fn new(limit: usize) -> Input {
+        Input { text: String::new(), cursor: 0, limit }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Where character `i` starts in `text`.
+    fn byte(&self, i: usize) -> usize {
+        self.text.char_indices().nth(i).map_or(self.text.len(), |(byte, _)| byte)
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Types `c` at the cursor, unless the field is full. Control
+    /// characters are not text.
+    pub fn insert(&mut self, c: char) {
+        if c.is_control() || self.len() >= self.limit {
+            return;
+        }
+        let byte = self.byte(self.cursor);
+        self.text.insert(byte, c);
+        self.cursor += 1;
+    }
+
+    /// Deletes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let byte = self.byte(self.cursor);
+            self.text.remove(byte);
+        }
+    }
+
+    /// Deletes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let byte = self.byte(self.cursor);
+            self.text.remove(byte);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+}
+
+/// A field on a single line, scrolled so the cursor stays in it. The
+/// cursor is drawn as an inverted cell while the field has the focus, and
+/// an empty field without it shows its placeholder.
+pub struct TextInput<'a> {
+    input: &'a Input,
+    focused: bool,
+    placeholder: &'a str,
+}
+
+impl<'a> TextInput<'a> {
+    pub fn new(input: &'a Input) -> TextInput<'a> {
+        TextInput { input, focused: false, placeholder: "" }
+    }
+
+    pub fn focused(mut self, focused: bool) -> TextInput<'a> {
+        self.focused = focused;
+        self
+    }
+
+    pub fn placeholder(mut self, placeholder: &'a str) -> TextInput<'a> {
+        self.placeholder = placeholder;
+        self
+    }
+}
+
+impl<'a> Widget for TextInput<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let y = area.top();
+        let width = area.width as usize;
+        if self.input.text.is_empty() && !self.focused {
+            let faint = Style::default().modifier(Modifier::Faint);
+            buf.set_stringn(area.left(), y, self.placeholder, width, &faint);
+            return;
+        }
+        // The last column is kept for the cursor at the end of the text.
+        let first = (self.input.cursor + 1).saturating_sub(width);
+        let shown: String = self.input.text.chars().skip(first).take(width).collect();
+        buf.set_stringn(area.left(), y, &shown, width, &Style::default());
+        if self.focused {
+            let x = area.left() + (self.input.cursor - first) as u16;
+            let cell = buf.get_mut(x, y);
+            if self.input.cursor == self.input.len() {
+                cell.set_symbol(" ");
+            }
+            cell.set_style(Style::default().modifier(Modifier::Invert));
+        }
+    }
+}
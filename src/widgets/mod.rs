@@ -0,0 +1,11 @@
+//! Widgets that tui does not provide.
+
+mod banner;
+mod input;
+mod picture;
+mod qr;
+
+pub use self::banner::Banner;
+pub use self::input::{Input, TextInput};
+pub use self::picture::Picture;
+pub use self::qr::Qr;
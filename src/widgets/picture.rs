@@ -0,0 +1,55 @@
+//! Pictures drawn with half blocks in true colour, for terminals without a
+//! graphics protocol.
+
+use image::{imageops, RgbaImage};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::widgets::Widget;
+
+use graphics;
+
+/// A picture scaled to fit its area, two pixels per cell: the upper one is
+/// the foreground of `▀` and the lower one the background.
+pub struct Picture<'a> {
+    image: &'a RgbaImage,
+}
+
+impl<'a> Picture<'a> {
+    pub fn new(image: &'a RgbaImage) -> Picture<'a> {
+        Picture { image }
+    }
+}
+
+fn color(pixel: &image::Rgba<u8>) -> Color {
+    if pixel[3] < 128 {
+        Color::Reset
+    } else {
+        Color::Rgb(pixel[0], pixel[1], pixel[2])
+    }
+}
+
+impl<'a> Widget for Picture<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        let target = graphics::fit(self.image.width(), self.image.height(), area, (1, 2));
+        if target.width == 0 || target.height == 0 {
+            return;
+        }
+        let pixels = imageops::resize(
+            self.image,
+            target.width as u32,
+            target.height as u32 * 2,
+            imageops::FilterType::Triangle,
+        );
+        for row in 0..target.height {
+            for column in 0..target.width {
+                let top = pixels.get_pixel(column as u32, 2 * row as u32);
+                let bottom = pixels.get_pixel(column as u32, 2 * row as u32 + 1);
+                buf.get_mut(target.x + column, target.y + row)
+                    .set_symbol("▀")
+                    .set_fg(color(top))
+                    .set_bg(color(bottom));
+            }
+        }
+    }
+}
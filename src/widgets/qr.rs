@@ -0,0 +1,79 @@
+//! QR codes that scan straight off the terminal.
+
+use qrcode::{self, QrCode};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::widgets::Widget;
+
+/// The light modules kept around the code, in modules. The standard asks
+/// for four, but the rest of the area is light as well.
+const QUIET_ZONE: usize = 2;
+
+/// A QR code drawn with half blocks, two modules per cell, dark on light
+/// whatever the colours of the terminal. The whole area is painted light and
+/// the code centered in it.
+pub struct Qr<'a> {
+    code: &'a QrCode,
+    caption: &'a str,
+}
+
+impl<'a> Qr<'a> {
+    pub fn new(code: &'a QrCode) -> Qr<'a> {
+        Qr { code, caption: "" }
+    }
+
+    /// A line shown under the code when there is room for it.
+    pub fn caption(mut self, caption: &'a str) -> Qr<'a> {
+        self.caption = caption;
+        self
+    }
+
+    fn dark(&self, x: usize, y: usize) -> bool {
+        let width = self.code.width();
+        if x < QUIET_ZONE || y < QUIET_ZONE || x >= width + QUIET_ZONE || y >= width + QUIET_ZONE {
+            return false;
+        }
+        self.code[(x - QUIET_ZONE, y - QUIET_ZONE)] == qrcode::Color::Dark
+    }
+}
+
+impl<'a> Widget for Qr<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        let style = Style::default().fg(Color::Black).bg(Color::White);
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_symbol(" ").set_style(style);
+            }
+        }
+        let modules = self.code.width() + 2 * QUIET_ZONE;
+        let (width, height) = (modules as u16, ((modules + 1) / 2) as u16);
+        if width > area.width || height > area.height {
+            // A cropped code does not scan, so ask for room instead.
+            let message = "Enlarge the terminal to show the code";
+            let y = area.top() + area.height / 2;
+            buf.set_stringn(area.left(), y, message, area.width as usize, &style);
+            return;
+        }
+        let caption = self.caption.chars().count() as u16;
+        let below = if caption > 0 && caption <= area.width && height < area.height { 1 } else { 0 };
+        let left = area.left() + (area.width - width) / 2;
+        let top = area.top() + (area.height - height - below) / 2;
+        for row in 0..height {
+            for column in 0..width {
+                let x = column as usize;
+                let y = 2 * row as usize;
+                let symbol = match (self.dark(x, y), self.dark(x, y + 1)) {
+                    (true, true) => "█",
+                    (true, false) => "▀",
+                    (false, true) => "▄",
+                    (false, false) => " ",
+                };
+                buf.get_mut(left + column, top + row).set_symbol(symbol);
+            }
+        }
+        if below > 0 {
+            buf.set_string(area.left() + (area.width - caption) / 2, top + height, self.caption, &style);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! What the `y` and `Y` keys copy: the text of each panel of a tab, in the
+//! order focus moves through them, and whole tabs as Markdown. The `/` key
+//! searches the same panels.
+//!
+//! The tabs are numbered as in the UI, the Welcome tab having nothing to
+//! copy.
+
+use cv::{self, Cv};
+use TABS;
+
+/// A panel, or an entry of one, that can take the focus.
+pub struct Item {
+    /// What the toast calls it once copied.
+    pub title: String,
+    pub text: String,
+}
+
+fn item(title: &str, text: String) -> Item {
+    Item { title: title.to_string(), text }
+}
+
+fn paragraphs(texts: &[String]) -> String {
+    texts.iter().map(|t| cv::plain(t)).collect::<Vec<_>>().join("\n\n")
+}
+
+/// The items of a tab. In the Personal tab every contact detail is an item
+/// of its own after the panels.
+pub fn items(cv: &Cv, tab: usize) -> Vec<Item> {
+    match tab {
+        1 => {
+            let information: Vec<String> = Some(("Name", cv.name.clone()))
+                .into_iter()
+                .chain(cv.information())
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect();
+            let languages: Vec<String> = cv.languages.iter().map(|l| format!("{}: {}", l.name, l.level)).collect();
+            let studies: Vec<String> = cv
+                .studies
+                .iter()
+                .map(|s| format!("{} - {}: {}\n{}", s.start, s.end, s.degree, cv::plain(&s.summary)))
+                .collect();
+            let mut items = vec![
+                item("Information", information.join("\n")),
+                item("About me", paragraphs(&cv.about)),
+                item("Languages", languages.join("\n")),
+                item("Studies", studies.join("\n\n")),
+            ];
+            items.extend(cv.contacts.iter().map(|c| item(c.kind.label(), c.value.clone())));
+            items
+        }
+        2 => {
+            let mut items: Vec<Item> = cv
+                .skills
+                .iter()
+                .map(|group| {
+                    let skills: Vec<String> = group.skills.iter().map(|s| format!("{}: {} / 100", s.name, s.level)).collect();
+                    item(&group.title, skills.join("\n"))
+                })
+                .collect();
+            let tools: Vec<String> = cv.tools.iter().map(|g| format!("{}: {}", g.title, g.items.join(", "))).collect();
+            items.push(item("Others", tools.join("\n")));
+            items
+        }
+        3 => cv
+            .experience
+            .iter()
+            .map(|job| {
+                let highlights: Vec<String> = job
+                    .highlights
+                    .iter()
+                    .map(|h| match h.name {
+                        Some(ref name) => format!("{}: {}", name, cv::plain(&h.text)),
+                        None => cv::plain(&h.text),
+                    })
+                    .collect();
+                item(&job.company, format!("{}\n\n{}", job.title(), highlights.join("\n\n")))
+            })
+            .collect(),
+        4 => cv
+            .courses
+            .iter()
+            .map(|c| item(&c.title, format!("{}\n\n{}", c.heading(), cv::plain(&c.summary))))
+            .collect(),
+        5 => {
+            let roles: Vec<String> = cv.looking_for.roles.iter().map(|r| format!("* {}", cv::plain(r))).collect();
+            let text = format!("{}\n\n{}\n\n{}", cv.looking_for.headline, cv::plain(&cv.looking_for.intro), roles.join("\n"));
+            vec![item("What I am looking for", text)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The first panel after panel `focus` of `tab` whose title or text has
+/// `query` in it, whatever the case, going on from the first tab after
+/// the last one.
+pub fn find(cv: &Cv, query: &str, tab: usize, focus: usize) -> Option<(usize, usize)> {
+    let query = query.to_lowercase();
+    let panels: Vec<(usize, usize, Item)> = (0..TABS.len())
+        .flat_map(|tab| items(cv, tab).into_iter().enumerate().map(move |(i, item)| (tab, i, item)))
+        .collect();
+    let next = panels.iter().position(|&(t, i, _)| (t, i) > (tab, focus)).unwrap_or(0);
+    panels
+        .iter()
+        .cycle()
+        .skip(next)
+        .take(panels.len())
+        .find(|(_, _, item)| item.title.to_lowercase().contains(&query) || item.text.to_lowercase().contains(&query))
+        .map(|&(tab, i, _)| (tab, i))
+}
+
+/// A whole tab as Markdown, under a heading with its title. The `**` of the
+/// content are already Markdown.
+pub fn section(cv: &Cv, tab: usize, title: &str) -> Option<String> {
+    let body = match tab {
+        1 => {
+            let information: String = Some(("Name", cv.name.clone()))
+                .into_iter()
+                .chain(cv.information())
+                .map(|(name, value)| format!("- **{}:** {}\n", name, value))
+                .collect();
+            let languages: String = cv.languages.iter().map(|l| format!("- **{}:** {}\n", l.name, l.level)).collect();
+            let studies: String = cv
+                .studies
+                .iter()
+                .map(|s| format!("### {} - {}: {}\n\n{}\n\n", s.start, s.end, s.degree, s.summary))
+                .collect();
+            let contacts: String = cv.contacts.iter().map(|c| format!("- **{}:** {}\n", c.kind.label(), c.value)).collect();
+            format!(
+                "## Information\n\n{}\n## About me\n\n{}\n\n## Languages\n\n{}\n## Studies\n\n{}## Contact\n\n{}",
+                information,
+                cv.about.join("\n\n"),
+                languages,
+                studies,
+                contacts
+            )
+        }
+        2 => {
+            let skills: String = cv
+                .skills
+                .iter()
+                .map(|group| {
+                    let skills: String = group.skills.iter().map(|s| format!("- {}: {} / 100\n", s.name, s.level)).collect();
+                    format!("## {}\n\n{}\n", group.title, skills)
+                })
+                .collect();
+            let tools: String = cv.tools.iter().map(|g| format!("- **{}:** {}\n", g.title, g.items.join(", "))).collect();
+            format!("{}## Others\n\n{}", skills, tools)
+        }
+        3 => cv
+            .experience
+            .iter()
+            .map(|job| {
+                let highlights: String = job
+                    .highlights
+                    .iter()
+                    .map(|h| match h.name {
+                        Some(ref name) => format!("- **{}:** {}\n", name, h.text),
+                        None => format!("- {}\n", h.text),
+                    })
+                    .collect();
+                format!("## {}\n\n{}\n", job.title(), highlights)
+            })
+            .collect(),
+        4 => cv.courses.iter().map(|c| format!("## {}\n\n{}\n\n", c.heading(), c.summary)).collect(),
+        5 => {
+            let roles: String = cv.looking_for.roles.iter().map(|r| format!("- {}\n", r)).collect();
+            format!("## {}\n\n{}\n\n{}", cv.looking_for.headline, cv.looking_for.intro, roles)
+        }
+        _ => return None,
+    };
+    Some(format!("# {}\n\n{}", title, body.trim_end()))
+}
@@ -0,0 +1,89 @@
+//! What the integration tests share: the binary they run, the content they
+//! give it and the tools they check its output with.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, ChildStderr, Command, Stdio};
+
+/// The content file of the tests, a resume whose every value is known.
+pub const CONTENT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/content.json");
+
+/// The `rust-cv` binary Cargo built with the tests, in the directory above
+/// theirs.
+pub fn rust_cv() -> Command {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    Command::new(path.join("rust-cv"))
+}
+
+/// Fails unless `tool` is installed. The tests that need one are ignored
+/// by default, and run with `cargo test -- --ignored` where it is, as on
+/// Travis.
+pub fn require(tool: &str) {
+    let found = Command::new(tool).arg("--version").output().is_ok();
+    assert!(found, "{} is not installed", tool);
+}
+
+/// A directory of its own for the test `name`, empty.
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("rust-cv-{}-{}", name, process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Exports the content of the tests to `file` in `dir`.
+pub fn export(format: &str, dir: &Path, file: &str) -> PathBuf {
+    let output = dir.join(file);
+    let status = rust_cv()
+        .args(&["export", "--format", format, "--content", CONTENT, "--output"])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(status.success(), "rust-cv export --format {} failed", format);
+    output
+}
+
+/// A server the test started, killed when the test ends, passed or not.
+pub struct Server {
+    pub address: SocketAddr,
+    child: Child,
+    // What the server logs after it started, which it must be able to.
+    _stderr: BufReader<ChildStderr>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Starts `rust-cv serve` with `args` on a port of its choosing, and waits
+/// for it to say which: "rust-cv: serving Gopher on 127.0.0.1:4242".
+pub fn serve(args: &[&str]) -> Server {
+    let mut child = rust_cv().arg("serve").args(args).stderr(Stdio::piped()).spawn().unwrap();
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    let address = line
+        .split(" on ")
+        .nth(1)
+        .and_then(|rest| rest.trim_end().split(',').next())
+        .and_then(|address| address.parse().ok());
+    match address {
+        Some(address) => Server { address, child, _stderr: stderr },
+        None => {
+            child.kill().ok();
+            panic!("rust-cv serve {} did not start: {}", args.join(" "), line);
+        }
+    }
+}
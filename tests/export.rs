@@ -0,0 +1,90 @@
+//! The exported documents, read back by the programs they are for.
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// The text LibreOffice reads in `document`, a line a paragraph or a cell.
+fn libreoffice_text(dir: &Path, document: &Path) -> Vec<String> {
+    // A profile of its own, as tests run at the same time.
+    let profile = format!("-env:UserInstallation=file://{}", dir.join("profile").display());
+    let status = Command::new("soffice")
+        .args(&[&profile, "--headless", "--convert-to", "txt:Text (encoded):UTF8", "--outdir"])
+        .arg(dir)
+        .arg(document)
+        .status()
+        .unwrap();
+    assert!(status.success(), "soffice cannot convert {}", document.display());
+    let text = fs::read_to_string(document.with_extension("txt")).unwrap();
+    text.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect()
+}
+
+fn has_line(lines: &[String], line: &str) {
+    assert!(lines.iter().any(|l| l == line), "no line '{}' in:\n{}", line, lines.join("\n"));
+}
+
+/// A paragraph of a bulleted list, which the text has with its bullet.
+fn has_bullet(lines: &[String], item: &str) {
+    let bulleted = lines.iter().any(|l| l.starts_with('•') && l.trim_start_matches('•').trim() == item);
+    assert!(bulleted, "no bullet '{}' in:\n{}", item, lines.join("\n"));
+}
+
+/// Exports the content of the tests to `format` and checks what
+/// LibreOffice reads in it: the headings, the bullets, the skills tables and
+/// the text of the links.
+fn reads_back_in_libreoffice(format: &str) {
+    common::require("soffice");
+    let dir = common::scratch(format);
+    let document = common::export(format, &dir, &format!("cv.{}", format));
+    let lines = libreoffice_text(&dir, &document);
+
+    has_line(&lines, "Ada Lovelace");
+    for heading in &["Contact", "Experience", "Programmer at Babbage & Co", "Skills", "Languages", "What I am looking for"] {
+        has_line(&lines, heading);
+    }
+    has_bullet(&lines, "Translated the memoir of Menabrea on the engine.");
+    has_bullet(&lines, "Note G: Computed the Bernoulli numbers.");
+    has_bullet(&lines, "Writing programs for general purpose machines.");
+    // The skills tables, a cell a line.
+    for cell in &["Skill", "Level", "Punched cards", "95 / 100", "Mathematics", "90 / 100", "Machines", "Difference Engine, Analytical Engine"] {
+        has_line(&lines, cell);
+    }
+    // The text of the links, after their labels.
+    has_line(&lines, "Email: ada@example.org");
+    has_line(&lines, "Website: https://ada.example.org/");
+    has_line(&lines, "GitHub: https://github.com/ada");
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+#[ignore]
+fn docx_reads_back_in_libreoffice() {
+    reads_back_in_libreoffice("docx");
+}
+
+#[test]
+#[ignore]
+fn odt_reads_back_in_libreoffice() {
+    reads_back_in_libreoffice("odt");
+}
+
+/// The Europass export, checked by xmllint against the Europass schema
+/// that `EUROPASS_SCHEMA` names, a file or an http URL, as Travis sets it.
+#[test]
+#[ignore]
+fn europass_is_valid() {
+    common::require("xmllint");
+    let schema = env::var("EUROPASS_SCHEMA").expect("EUROPASS_SCHEMA names the Europass schema");
+    let dir = common::scratch("europass");
+    let document = common::export("europass", &dir, "cv.xml");
+    let output = Command::new("xmllint").args(&["--noout", "--schema", &schema]).arg(&document).output().unwrap();
+    assert!(output.status.success(), "xmllint rejects {}:\n{}", document.display(), String::from_utf8_lossy(&output.stderr));
+    // The course is training, not education.
+    let xml = fs::read_to_string(&document).unwrap();
+    assert!(xml.contains("<Achievement><Title><Code>courses</Code>"), "no training in:\n{}", xml);
+    assert_eq!(xml.matches("<Education>").count(), 1, "courses in the education of:\n{}", xml);
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,76 @@
+//! The Gopher server, asked for its menu and a tab as a client would.
+
+mod common;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// What the server answers to `selector`.
+fn ask(server: &common::Server, selector: &str) -> String {
+    let mut stream = TcpStream::connect(server.address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    stream.write_all(selector.as_bytes()).unwrap();
+    stream.write_all(b"\r\n").unwrap();
+    let mut answer = String::new();
+    stream.read_to_string(&mut answer).unwrap();
+    answer
+}
+
+/// Every line ends with CRLF, and the last is the dot that ends them all.
+fn lines(answer: &str) -> Vec<&str> {
+    assert!(answer.ends_with("\r\n.\r\n"), "no '.' line at the end of:\n{}", answer);
+    answer[..answer.len() - 5].split("\r\n").collect()
+}
+
+/// A server for the test `name`. It serves a copy of the content, which
+/// the user it becomes when started by root can read.
+fn start(name: &str) -> common::Server {
+    let content = common::scratch(name).join("content.json");
+    fs::copy(common::CONTENT, &content).unwrap();
+    common::serve(&["gopher", "--listen", "127.0.0.1:0", "--content", content.to_str().unwrap()])
+}
+
+#[test]
+fn menu() {
+    let server = start("gopher-menu");
+    let port = server.address.port().to_string();
+    let item = |line: &str| format!("{}\t127.0.0.1\t{}", line, port);
+    for selector in &["", "/"] {
+        let answer = ask(&server, selector);
+        let lines = lines(&answer);
+        assert_eq!(lines[0], item("iAda Lovelace\t"));
+        for line in &[
+            "0Welcome\t/welcome",
+            "0Personal\t/personal",
+            "0Skills\t/skills",
+            "0Experience\t/experience",
+            "0Courses\t/courses",
+            "0Looking For\t/looking-for",
+            "hEmail: ada@example.org\tURL:mailto:ada@example.org",
+            "hGitHub: https://github.com/ada\tURL:https://github.com/ada",
+        ] {
+            assert!(lines.contains(&item(line).as_str()), "no '{}' in the menu:\n{}", line, answer);
+        }
+    }
+}
+
+#[test]
+fn tab() {
+    let server = start("gopher-tab");
+    let answer = ask(&server, "/skills");
+    let lines = lines(&answer);
+    assert!(lines.contains(&"Punched cards: 95 / 100"), "no skill in:\n{}", answer);
+    assert!(lines.contains(&"Machines: Difference Engine, Analytical Engine"), "no tools in:\n{}", answer);
+    // Gopher+ clients send more after a tab, which is not the selector.
+    assert_eq!(ask(&server, "/skills\t+"), answer);
+}
+
+#[test]
+fn missing() {
+    let server = start("gopher-missing");
+    let answer = ask(&server, "/nothing");
+    assert!(answer.starts_with("3'/nothing' does not exist\t"), "no error item in:\n{}", answer);
+    lines(&answer);
+}
@@ -0,0 +1,60 @@
+//! A resume written by another tool, imported and exported again.
+
+extern crate serde_json;
+
+mod common;
+
+use std::fs::{self, File};
+use std::path::Path;
+
+use serde_json::Value;
+
+/// A resume as jsonresume.org documents it, with fields and networks the
+/// curriculum has no place for.
+const RESUME: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/jsonresume.json");
+
+fn read(path: &Path) -> Value {
+    serde_json::from_reader(File::open(path).unwrap()).unwrap()
+}
+
+/// Asserts that `exported` has everything `original` has, at `path`.
+fn has(exported: &Value, original: &Value, path: &str) {
+    match (original, exported) {
+        (Value::Object(original), Value::Object(exported)) => {
+            for (key, value) in original {
+                let path = format!("{}/{}", path, key);
+                match exported.get(key) {
+                    Some(exported) => has(exported, value, &path),
+                    None => panic!("{} is missing", path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(exported)) => {
+            assert_eq!(original.len(), exported.len(), "{} has {} items", path, exported.len());
+            for (i, (original, exported)) in original.iter().zip(exported).enumerate() {
+                has(exported, original, &format!("{}/{}", path, i));
+            }
+        }
+        _ => assert_eq!(original, exported, "{} changed", path),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let dir = common::scratch("jsonresume");
+    let content = dir.join("content.json");
+    let exported = dir.join("resume.json");
+    let status = common::rust_cv().args(&["import", "--from", "jsonresume", RESUME, "--output"]).arg(&content).status().unwrap();
+    assert!(status.success(), "rust-cv import failed");
+    let status = common::rust_cv()
+        .args(&["export", "--format", "jsonresume", "--content"])
+        .arg(&content)
+        .arg("--output")
+        .arg(&exported)
+        .status()
+        .unwrap();
+    assert!(status.success(), "rust-cv export failed");
+
+    has(&read(&exported), &read(Path::new(RESUME)), "");
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,189 @@
+//! The SSH server, with OpenSSH as the client, and with a client of the
+//! tests for what OpenSSH never does.
+
+extern crate ring;
+
+mod common;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ring::agreement::{self, EphemeralPrivateKey};
+use ring::rand::SystemRandom;
+
+const MSG_KEXINIT: u8 = 20;
+const MSG_KEX_ECDH_INIT: u8 = 30;
+const MSG_KEX_ECDH_REPLY: u8 = 31;
+
+/// A server with its host key, content and visits log in `dir`. The
+/// content is a copy, which the user it becomes when started by root can
+/// read.
+fn start(dir: &Path) -> common::Server {
+    fs::copy(common::CONTENT, dir.join("content.json")).unwrap();
+    let path = |file: &str| dir.join(file).to_str().unwrap().to_string();
+    common::serve(&[
+        "ssh",
+        "--listen",
+        "127.0.0.1:0",
+        "--host-key",
+        &path("host_key"),
+        "--content",
+        &path("content.json"),
+        "--log-dir",
+        &path(""),
+    ])
+}
+
+/// Waits for the visits log of `dir` to have `text`.
+fn logged(dir: &Path, text: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let log = fs::read_to_string(dir.join("visits.log")).unwrap_or_default();
+        if log.contains(text) {
+            return;
+        }
+        assert!(Instant::now() < deadline, "no {} in the visits log:\n{}", text, log);
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// ssh logs in, gets a terminal, and the keys typed in it move the UI to
+/// the next tab and then out of it.
+#[test]
+#[ignore]
+fn openssh_session() {
+    common::require("ssh");
+    let dir = common::scratch("ssh-openssh");
+    let server = start(&dir);
+    let mut ssh = Command::new("ssh")
+        .args(&["-tt", "-o", "StrictHostKeyChecking=no", "-o", "UserKnownHostsFile=/dev/null", "-o", "BatchMode=yes"])
+        .args(&["-o", "LogLevel=ERROR", "-p", &server.address.port().to_string(), "visitor@127.0.0.1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut keys = ssh.stdin.take().unwrap();
+    logged(&dir, "\"tab\":\"Welcome\"");
+    keys.write_all(b"\x1b[C").unwrap();
+    logged(&dir, "\"tab\":\"Personal\"");
+    keys.write_all(b"q").unwrap();
+    logged(&dir, "\"event\":\"disconnect\"");
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let status = loop {
+        if let Some(status) = ssh.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            ssh.kill().ok();
+            panic!("ssh is still connected after q");
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+    assert!(status.success(), "ssh exited with {}", status);
+    fs::remove_dir_all(&dir).ok();
+}
+
+fn string(bytes: &[u8]) -> Vec<u8> {
+    let mut string = (bytes.len() as u32).to_be_bytes().to_vec();
+    string.extend_from_slice(bytes);
+    string
+}
+
+/// Sends `payload` as a packet before any key is agreed on.
+fn send(stream: &mut TcpStream, payload: &[u8]) {
+    let mut padding = 8 - (payload.len() + 5) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let mut packet = ((1 + payload.len() + padding) as u32).to_be_bytes().to_vec();
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.extend(vec![0; padding]);
+    stream.write_all(&packet).unwrap();
+}
+
+fn receive(stream: &mut TcpStream) -> Vec<u8> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).unwrap();
+    let mut packet = vec![0; u32::from_be_bytes(length) as usize];
+    stream.read_exact(&mut packet).unwrap();
+    let padding = packet[0] as usize;
+    packet[1..packet.len() - padding].to_vec()
+}
+
+/// A KEXINIT whose key exchanges are `kex`, saying that a guessed packet
+/// follows it.
+fn kexinit(kex: &str) -> Vec<u8> {
+    let mut kexinit = vec![MSG_KEXINIT];
+    kexinit.extend_from_slice(&[0; 16]);
+    for list in &[kex, "ssh-ed25519", "aes128-gcm@openssh.com", "aes128-gcm@openssh.com"] {
+        kexinit.extend(string(list.as_bytes()));
+    }
+    for list in &["hmac-sha2-256", "hmac-sha2-256", "none", "none", "", ""] {
+        kexinit.extend(string(list.as_bytes()));
+    }
+    kexinit.push(1);
+    kexinit.extend_from_slice(&[0; 4]);
+    kexinit
+}
+
+/// The version line of the server, read a byte at a time so that nothing
+/// after it is.
+fn version(stream: &mut TcpStream) -> String {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while line.last() != Some(&b'\n') {
+        stream.read_exact(&mut byte).unwrap();
+        line.push(byte[0]);
+    }
+    String::from_utf8_lossy(&line).into_owned()
+}
+
+/// Starts a key exchange with `kex` and the packets guessed for it, and
+/// says what the server answers to an X25519 KEX_ECDH_INIT after them.
+fn guess(name: &str, kex: &str, guessed: &[&[u8]]) -> u8 {
+    let dir = common::scratch(name);
+    let server = start(&dir);
+    let mut stream = TcpStream::connect(server.address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    stream.write_all(b"SSH-2.0-test\r\n").unwrap();
+    let version = version(&mut stream);
+    assert!(version.starts_with("SSH-2.0-"), "no version but {}", version);
+    assert_eq!(receive(&mut stream)[0], MSG_KEXINIT);
+
+    send(&mut stream, &kexinit(kex));
+    for packet in guessed {
+        send(&mut stream, packet);
+    }
+    let private = EphemeralPrivateKey::generate(&agreement::X25519, &SystemRandom::new()).unwrap();
+    let public = private.compute_public_key().unwrap();
+    let mut init = vec![MSG_KEX_ECDH_INIT];
+    init.extend(string(public.as_ref()));
+    send(&mut stream, &init);
+    let answer = receive(&mut stream)[0];
+    fs::remove_dir_all(&dir).ok();
+    answer
+}
+
+/// A client that prefers another key exchange guesses wrong, and what it
+/// sent for it is ignored (RFC 4253, section 7.1).
+#[test]
+fn wrong_guess_is_ignored() {
+    // The point of a NIST P-256 exchange, no X25519 key.
+    let mut nistp256 = vec![MSG_KEX_ECDH_INIT];
+    nistp256.extend(string(&[4; 65]));
+    assert_eq!(guess("ssh-wrong-guess", "ecdh-sha2-nistp256,curve25519-sha256", &[&nistp256]), MSG_KEX_ECDH_REPLY);
+}
+
+/// A client that prefers the same key exchange guesses right, and its
+/// guessed packet is the exchange.
+#[test]
+fn right_guess_is_used() {
+    assert_eq!(guess("ssh-right-guess", "curve25519-sha256", &[]), MSG_KEX_ECDH_REPLY);
+}